@@ -0,0 +1,218 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use chrono::{DateTime, Utc};
+use minidom::Element;
+
+use crate::{
+    parse_timestamp, ErrorContext, FallbackTimezone, IntoTcxError, NamespaceMatching, Tag,
+    TcxElement, TcxError, TimeInterpretation,
+};
+
+/// how to deal with invalid track point data
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ParseMode {
+    /// Fail on the first invalid value
+    #[default]
+    Strict,
+    /// Drop invalid track point fields, skip track points without a valid time and record a [`Warning`] for each
+    ///
+    /// Invalid [`Sport`][Tag::Sport] attributes are dropped as well, other invalid values outside of track points still
+    /// fail.
+    Lenient,
+}
+
+/// options for parsing and warnings collected while parsing
+///
+/// The `parse_with` functions take a context, the `parse` functions use a default, i.e., strict, context.
+///
+/// # Examples
+/// ```
+/// # use tcx::*;
+///
+/// let doc = r#"<Track xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+///   <Trackpoint><Time>2022-12-31T12:00:00Z</Time><DistanceMeters>0.0</DistanceMeters></Trackpoint>
+///   <Trackpoint><Time>2022-12-31T12:00:01Z</Time><DistanceMeters>garbled</DistanceMeters></Trackpoint>
+///   <Trackpoint><Time>garbled</Time><DistanceMeters>7.2</DistanceMeters></Trackpoint>
+/// </Track>"#;
+/// let element = doc.parse().unwrap();
+///
+/// assert!(Track::parse(&element).is_err());
+///
+/// let mut context = ParseContext::new(ParseMode::Lenient);
+/// let track = Track::parse_with(&element, &mut context).unwrap();
+///
+/// assert_eq!(track.trackpoints.len(), 2);
+/// assert_eq!(track.trackpoints[1].distance, None);
+/// assert_eq!(context.warnings.len(), 2);
+/// assert_eq!(context.warnings[0].recovery, Recovery::FieldDropped);
+/// assert_eq!(context.warnings[1].recovery, Recovery::TrackpointSkipped);
+/// ```
+#[derive(Debug, Default)]
+pub struct ParseContext {
+    /// The parse mode
+    pub mode: ParseMode,
+    /// Warnings collected in [`ParseMode::Lenient`]
+    pub warnings: Vec<Warning>,
+    /// Timezone for timestamps without UTC designator or offset, see [`parse_timestamp`]
+    pub timezone: FallbackTimezone,
+    /// Number of timestamps parsed per interpretation
+    pub interpretations: HashMap<TimeInterpretation, usize>,
+    /// How elements are matched to tags
+    pub namespaces: NamespaceMatching,
+}
+
+impl ParseContext {
+    /// Create a context with the given mode
+    pub fn new(mode: ParseMode) -> Self {
+        ParseContext {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    /// Parse a timestamp with [`parse_timestamp`] using the context's timezone and count its interpretation
+    pub fn parse_time(
+        &mut self,
+        text: &str,
+        context: ErrorContext,
+    ) -> Result<DateTime<Utc>, TcxError> {
+        let (time, interpretation) =
+            parse_timestamp(text, self.timezone).map_err(|e| e.into_tcx_error(context, text))?;
+        *self.interpretations.entry(interpretation).or_default() += 1;
+        Ok(time)
+    }
+
+    /// Get child element using the context's [`NamespaceMatching`], see [`TcxElement::child`]
+    pub(crate) fn child<'a>(&self, element: &'a Element, tags: &[Tag]) -> Option<&'a Element> {
+        element.child_matching(tags, self.namespaces)
+    }
+
+    /// Iterate over child elements which are a `tag` using the context's [`NamespaceMatching`], see
+    /// [`TcxElement::children_tagged`]
+    pub(crate) fn children_tagged<'a>(
+        &self,
+        element: &'a Element,
+        tag: Tag,
+    ) -> impl Iterator<Item = &'a Element> + 'a {
+        element.children_matching(tag, self.namespaces)
+    }
+
+    /// Get text of child parsed into `T` using the context's [`NamespaceMatching`], see [`TcxElement::child_value`]
+    pub(crate) fn child_value<T: FromStr>(
+        &self,
+        element: &Element,
+        tags: &[Tag],
+    ) -> Result<Option<T>, TcxError>
+    where
+        T::Err: IntoTcxError,
+    {
+        element.child_value_matching(tags, self.namespaces)
+    }
+
+    /// Get text of child parsed as timestamp, see [`TcxElement::child_value`]
+    pub(crate) fn child_time(
+        &mut self,
+        element: &Element,
+        tags: &[Tag],
+    ) -> Result<Option<DateTime<Utc>>, TcxError> {
+        self.child(element, tags)
+            .map(|e| {
+                self.parse_time(
+                    &e.text(),
+                    ErrorContext {
+                        tags: tags.to_vec(),
+                        ..Default::default()
+                    },
+                )
+            })
+            .transpose()
+    }
+
+    /// Get value of attribute parsed as timestamp, see [`TcxElement::attr_value`]
+    pub(crate) fn attr_time(
+        &mut self,
+        element: &Element,
+        tag: Tag,
+    ) -> Result<Option<DateTime<Utc>>, TcxError> {
+        element
+            .attr(tag.as_ref())
+            .map(|text| {
+                self.parse_time(
+                    text,
+                    ErrorContext {
+                        tags: vec![tag],
+                        ..Default::default()
+                    },
+                )
+            })
+            .transpose()
+    }
+
+    /// Get value of attribute parsed into `T`, see [`TcxElement::attr_value`]
+    ///
+    /// An invalid value is dropped with a [`Recovery::FieldDropped`] warning in [`ParseMode::Lenient`].
+    pub(crate) fn attr_value<T: FromStr>(
+        &mut self,
+        element: &Element,
+        tag: Tag,
+    ) -> Result<Option<T>, TcxError>
+    where
+        T::Err: IntoTcxError,
+    {
+        match element.attr_value(tag) {
+            Err(e) => self.recover(e, Recovery::FieldDropped).map(|_| None),
+            value => value,
+        }
+    }
+
+    /// Record a warning in lenient mode, return the error in strict mode
+    pub(crate) fn recover(&mut self, error: TcxError, recovery: Recovery) -> Result<(), TcxError> {
+        match self.mode {
+            ParseMode::Strict => Err(error),
+            ParseMode::Lenient => {
+                self.warnings.push(Warning { error, recovery });
+                Ok(())
+            }
+        }
+    }
+
+    /// Apply `f` to the errors of all warnings recorded since the number of warnings was `since`
+    pub(crate) fn update_warnings(&mut self, since: usize, f: impl Fn(TcxError) -> TcxError) {
+        let warnings = self.warnings.drain(since..).collect::<Vec<_>>();
+        self.warnings.extend(warnings.into_iter().map(|w| Warning {
+            error: f(w.error),
+            recovery: w.recovery,
+        }));
+    }
+}
+
+/// how a [`Warning`]'s error was recovered from
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Recovery {
+    /// The invalid field was set to `None`
+    FieldDropped,
+    /// The track point was skipped
+    TrackpointSkipped,
+}
+
+/// an error recovered from in [`ParseMode::Lenient`]
+#[derive(Debug)]
+pub struct Warning {
+    /// The error
+    pub error: TcxError,
+    /// How the error was recovered from
+    pub recovery: Recovery,
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.recovery {
+            Recovery::FieldDropped => write!(f, "{}, field dropped", self.error),
+            Recovery::TrackpointSkipped => write!(f, "{}, trackpoint skipped", self.error),
+        }
+    }
+}
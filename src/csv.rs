@@ -0,0 +1,336 @@
+use std::mem;
+
+use crate::{
+    ErrorContext, ParseContext, Recovery, Tag, TcxError, TextPosition, Trackpoint, TrkPtField,
+    TRK_PT_FIELD,
+};
+
+/// a cell of a CSV record and the position where it starts
+struct Cell {
+    text: String,
+    position: TextPosition,
+}
+
+/// a CSV column identified by its header
+enum Column {
+    Time,
+    Field(TrkPtField),
+    Extension(String),
+    Ignored,
+}
+
+impl Column {
+    fn from_header(header: &str) -> Self {
+        let header = header.trim();
+        if header.is_empty() {
+            Column::Ignored
+        } else if header.eq_ignore_ascii_case(Tag::Time.as_ref()) {
+            Column::Time
+        } else if let Some(field) = TRK_PT_FIELD
+            .iter()
+            .find(|field| field.as_ref().eq_ignore_ascii_case(header))
+        {
+            Column::Field(*field)
+        } else {
+            Column::Extension(header.to_string())
+        }
+    }
+}
+
+/// Split CSV text into records of cells
+///
+/// Cells are separated by `,`, records by line breaks (`\n` or `\r\n`). Quoted cells may contain separators, line
+/// breaks and quotes written as `""`. Blank lines are skipped.
+fn records(csv: &str) -> Result<Vec<Vec<Cell>>, TcxError> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut line = 1;
+    let mut column = 1;
+    let mut cell = Cell {
+        text: String::new(),
+        position: TextPosition { line, column },
+    };
+    let mut quoted = false;
+
+    let mut chars = csv.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += c.len_utf8();
+        }
+        let next = Cell {
+            text: String::new(),
+            position: TextPosition { line, column },
+        };
+
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                column += 1;
+                cell.text.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => cell.text.push(c),
+            (false, '"') if cell.text.is_empty() => quoted = true,
+            (false, ',') => record.push(mem::replace(&mut cell, next)),
+            (false, '\r') if chars.peek() == Some(&'\n') => (),
+            (false, '\n') => {
+                record.push(mem::replace(&mut cell, next));
+                records.push(mem::take(&mut record));
+            }
+            (false, c) => cell.text.push(c),
+        }
+    }
+
+    if quoted {
+        return Err(TcxError::Csv {
+            reason: "unterminated quote".to_string(),
+            line: cell.position.line,
+        });
+    }
+    record.push(cell);
+    records.push(record);
+
+    // skip blank lines
+    records.retain(|record| record.len() > 1 || !record[0].text.trim().is_empty());
+
+    Ok(records)
+}
+
+impl Trackpoint {
+    /// Read track points from CSV text as written by the CLI's `-d csv`
+    ///
+    /// The first line is a header naming the columns: `Time`, the names of the [`TrkPtField`]s, e.g., `Heartrate`, and
+    /// any other name for an entry in the [`extensions`][Trackpoint::extensions]. Headers are matched ignoring ASCII
+    /// case, columns may be in any order and all columns but `Time` may be missing. Empty cells and cells missing at
+    /// the end of a line are `None`.
+    ///
+    /// Timestamps are parsed with [`parse_timestamp`][crate::parse_timestamp], which accepts the `2022-12-31 12:00:00
+    /// UTC` format written by `-d csv`. The result is filtered and deduplicated like [`Trackpoint::from_tcx`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use tcx::*;
+    ///
+    /// let csv = "Heartrate,Time,Distance,{urn:example}Gear
+    /// 120,2022-12-31 12:00:00 UTC,0.0,3
+    /// ,2022-12-31 12:00:01 UTC,7.2
+    /// ";
+    ///
+    /// let points = Trackpoint::from_csv(csv, |_| true).unwrap();
+    ///
+    /// assert_eq!(points.len(), 2);
+    /// assert_eq!(points[0].heartrate, Some(120.0));
+    /// assert_eq!(points[0].extensions["{urn:example}Gear"], 3.0);
+    /// assert_eq!(points[1].heartrate, None);
+    /// assert_eq!(points[1].distance, Some(7.2));
+    /// assert_eq!(points[1].altitude, None);
+    /// ```
+    pub fn from_csv(csv: &str, filter: fn(&Self) -> bool) -> Result<Vec<Self>, TcxError> {
+        Trackpoint::from_csv_with(csv, filter, &mut ParseContext::default())
+    }
+
+    /// Read track points from CSV text using the given [`ParseContext`]
+    ///
+    /// See [`Trackpoint::from_csv`]. Invalid numbers are dropped and lines with a missing or invalid time are skipped
+    /// in [`ParseMode::Lenient`][crate::ParseMode::Lenient]. Track point indices of errors and warnings count all
+    /// lines after the header except blank lines. A missing `Time` column, unterminated quotes and lines with more
+    /// cells than columns are errors in any mode.
+    pub fn from_csv_with(
+        csv: &str,
+        filter: fn(&Self) -> bool,
+        context: &mut ParseContext,
+    ) -> Result<Vec<Self>, TcxError> {
+        let mut records = records(csv)?.into_iter();
+        let columns = records
+            .next()
+            .map(|header| {
+                header
+                    .iter()
+                    .map(|cell| Column::from_header(&cell.text))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        if !columns.iter().any(|column| matches!(column, Column::Time)) {
+            return Err(TcxError::Csv {
+                reason: "missing column Time".to_string(),
+                line: 1,
+            });
+        }
+
+        let mut points = Vec::new();
+        for (k, record) in records.enumerate() {
+            if record.len() > columns.len() {
+                return Err(TcxError::Csv {
+                    reason: format!("{} cells but {} columns", record.len(), columns.len()),
+                    line: record[0].position.line,
+                });
+            }
+
+            let since = context.warnings.len();
+            let result = Trackpoint::parse_csv_with(&columns, &record, context);
+            context.update_warnings(since, |err| err.with_trackpoint(k));
+            match result {
+                Ok(point) => points.push(point),
+                Err(err) => context.recover(err.with_trackpoint(k), Recovery::TrackpointSkipped)?,
+            }
+        }
+
+        Ok(Trackpoint::collect(points.iter(), filter))
+    }
+
+    /// Parse a single track point from the cells of a CSV record
+    fn parse_csv_with(
+        columns: &[Column],
+        record: &[Cell],
+        context: &mut ParseContext,
+    ) -> Result<Self, TcxError> {
+        let mut time = None;
+        let mut point = Trackpoint::default();
+
+        for (column, cell) in columns.iter().zip(record) {
+            let text = cell.text.trim();
+            if text.is_empty() {
+                continue;
+            }
+            let error_context = |field| ErrorContext {
+                field,
+                position: Some(cell.position),
+                ..Default::default()
+            };
+            let value = |field| {
+                text.parse::<f64>().map_err(|_| TcxError::InvalidNumber {
+                    context: error_context(field),
+                    text: text.to_string(),
+                })
+            };
+
+            match column {
+                Column::Time => {
+                    time = Some(context.parse_time(
+                        text,
+                        ErrorContext {
+                            tags: vec![Tag::Time],
+                            ..error_context(None)
+                        },
+                    )?)
+                }
+                Column::Field(field) => match value(Some(*field)) {
+                    Ok(value) => point[field] = Some(value),
+                    Err(err) => context.recover(err, Recovery::FieldDropped)?,
+                },
+                Column::Extension(key) => match value(None) {
+                    Ok(value) => {
+                        point.extensions.insert(key.clone(), value);
+                    }
+                    Err(err) => context.recover(err, Recovery::FieldDropped)?,
+                },
+                Column::Ignored => (),
+            }
+        }
+
+        point.time = time.ok_or_else(|| TcxError::MissingField {
+            context: ErrorContext {
+                tags: vec![Tag::Time],
+                position: record.first().map(|cell| cell.position),
+                ..Default::default()
+            },
+        })?;
+
+        Ok(point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ParseMode;
+
+    use super::*;
+
+    const CSV: &str = "Speed,\"{urn:a,b}\"\"x\"\"\",Time,unused,Latitude\r
+1.5,2,2022-12-31T12:00:00Z\r
+\r
+,,2022-12-31T12:00:01Z,,48.5\r
+garbled,\"multi\nline\",2022-12-31T12:00:02Z\r
+2.5,,garbled\r
+3.5\r
+";
+
+    #[test]
+    fn test_csv_records() {
+        let records = records(CSV).unwrap();
+
+        assert_eq!(records.len(), 6);
+        assert_eq!(records[0][1].text, "{urn:a,b}\"x\"");
+        assert_eq!(
+            records[0][2].position,
+            TextPosition {
+                line: 1,
+                column: 24
+            }
+        );
+        assert_eq!(records[3][1].text, "multi\nline");
+        assert_eq!(records[3][2].position, TextPosition { line: 6, column: 7 });
+        assert_eq!(records[5].len(), 1);
+    }
+
+    #[test]
+    fn test_from_csv() {
+        let err = Trackpoint::from_csv(CSV, |_| true).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid number 'garbled' in field Speed of trackpoint 2 at line 5, column 1"
+        );
+
+        let mut context = ParseContext::new(ParseMode::Lenient);
+        let points = Trackpoint::from_csv_with(CSV, |_| true, &mut context).unwrap();
+
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[0].speed, Some(1.5));
+        assert_eq!(points[0].extensions["{urn:a,b}\"x\""], 2.0);
+        assert_eq!(points[0].latitude, None);
+        assert_eq!(points[1].speed, None);
+        assert_eq!(points[1].latitude, Some(48.5));
+        assert!(points[1].extensions.is_empty());
+        assert_eq!(points[2].speed, None);
+        assert_eq!(
+            points[2].time,
+            "2022-12-31T12:00:02Z"
+                .parse::<chrono::DateTime<chrono::Utc>>()
+                .unwrap()
+        );
+
+        let warnings = context
+            .warnings
+            .iter()
+            .map(|w| w.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            warnings,
+            vec![
+                "Invalid number 'garbled' in field Speed of trackpoint 2 at line 5, column 1, field dropped",
+                "Invalid number 'multi\nline' in trackpoint 2 at line 5, column 9, field dropped",
+                "Invalid timestamp 'garbled' in <Time> of trackpoint 3 at line 7, column 6, trackpoint skipped",
+                "Missing <Time> of trackpoint 4 at line 8, column 1, trackpoint skipped",
+            ]
+        );
+
+        let err = Trackpoint::from_csv("Distance\n1.0\n", |_| true).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid CSV file at line 1: missing column Time"
+        );
+        let err = Trackpoint::from_csv("Time\n2022-12-31T12:00:02Z,1\n", |_| true).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid CSV file at line 2: 2 cells but 1 columns"
+        );
+        let err = Trackpoint::from_csv("Time\n\"2022", |_| true).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid CSV file at line 2: unterminated quote"
+        );
+    }
+}
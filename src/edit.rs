@@ -0,0 +1,564 @@
+use minidom::{Element, Node};
+
+use crate::{
+    unknown_extensions,
+    writer::{append_extension, append_path, element, time_text, value_text},
+    NamespaceMatching, ParseContext, Recovery, Tag, TcxElement, TcxError, Trackpoint, TRK_PT_FIELD,
+};
+
+/// Order of the elements written by [`Trackpoint::apply_to`] as defined by the schemas
+///
+/// New elements are inserted before the first sibling that comes later in this order.
+const ORDER: [Tag; 23] = [
+    Tag::Time,
+    Tag::Position,
+    Tag::LatitudeDegrees,
+    Tag::LongitudeDegrees,
+    Tag::AltitudeMeters,
+    Tag::DistanceMeters,
+    Tag::HeartRateBpm,
+    Tag::Value,
+    Tag::Cadence,
+    Tag::Extensions,
+    Tag::TPX,
+    Tag::Speed,
+    Tag::RunCadence,
+    Tag::Watts,
+    Tag::TrackPointExtension,
+    Tag::AirTemperature,
+    Tag::WaterTemperature,
+    Tag::Depth,
+    Tag::Hr,
+    Tag::Cad,
+    Tag::TpxV2Speed,
+    Tag::TpxV2Course,
+    Tag::Bearing,
+];
+
+fn rank(element: &Element) -> Option<usize> {
+    ORDER.iter().position(|tag| element.has_name(*tag))
+}
+
+/// Get a mutable child element for `tag` like [`TcxElement::child_matching`]
+fn child_mut(parent: &mut Element, tag: Tag, matching: NamespaceMatching) -> Option<&mut Element> {
+    let index = select_tag(parent, &tag, matching)?;
+    parent.children_mut().nth(index)
+}
+
+/// Replace all text in `element` by `text`
+fn set_text(element: &mut Element, text: String) {
+    let mut texts = element.texts_mut();
+    match texts.next() {
+        Some(first) => {
+            *first = text;
+            texts.for_each(|t| t.clear());
+        }
+        None => element.append_text_node(text),
+    }
+}
+
+/// Insert `child` into `parent` before the first sibling which comes later in [`ORDER`], append it otherwise
+fn insert_child(parent: &mut Element, child: Element) {
+    let child_rank = rank(&child);
+    parent.append_child(child);
+
+    let mut nodes = parent.nodes_mut().collect::<Vec<_>>();
+    let target = nodes.iter().position(|node| match (node, child_rank) {
+        (Node::Element(e), Some(child_rank)) => rank(e).is_some_and(|r| r > child_rank),
+        _ => false,
+    });
+    if let Some(target) = target {
+        // move the appended child to the target position
+        for k in (target..nodes.len() - 1).rev() {
+            let (head, tail) = nodes.split_at_mut(k + 1);
+            std::mem::swap(&mut *head[k], &mut *tail[0]);
+        }
+    }
+}
+
+/// Set the text of the element at `tags` below `parent`, creating missing elements
+fn set_path(parent: &mut Element, tags: &[Tag], text: String, matching: NamespaceMatching) {
+    match tags {
+        [] => set_text(parent, text),
+        [tag, tail @ ..] => match child_mut(parent, *tag, matching) {
+            Some(child) => set_path(child, tail, text, matching),
+            None => {
+                let mut child = element(*tag);
+                append_path(&mut child, tail, text);
+                insert_child(parent, child);
+            }
+        },
+    }
+}
+
+/// Whether an element has neither child elements nor text
+fn is_empty(element: &Element) -> bool {
+    element.children().next().is_none() && element.text().trim().is_empty()
+}
+
+/// Remove the element at `path` below `parent` and any ancestors below `parent` that become empty
+///
+/// `select` finds the next element on the path, it returns `None` if the path does not exist.
+fn remove_path<'a, T>(
+    parent: &mut Element,
+    path: &'a [T],
+    select: &impl Fn(&Element, &'a T) -> Option<usize>,
+) {
+    let Some((head, tail)) = path.split_first() else {
+        return;
+    };
+    let Some(index) = select(parent, head) else {
+        return;
+    };
+    let child = parent.children_mut().nth(index).expect("index of child");
+    if !tail.is_empty() {
+        remove_path(child, tail, select);
+        if !is_empty(child) {
+            return;
+        }
+    }
+    let (name, ns) = (child.name().to_string(), child.ns());
+    parent.remove_child(name, ns.as_str());
+}
+
+/// Remove the child element at node `index` of `parent` together with the whitespace before it
+fn remove_node(parent: &mut Element, index: usize) {
+    let mut nodes = parent.nodes_mut().collect::<Vec<_>>();
+    let Node::Element(child) = &*nodes[index] else {
+        return;
+    };
+    let (name, ns) = (child.name().to_string(), child.ns());
+    if let Some(Node::Text(text)) = index.checked_sub(1).map(|k| &mut *nodes[k]) {
+        if text.trim().is_empty() {
+            text.clear();
+        }
+    }
+
+    // `remove_child` removes the first element with the name, so move the child in front of it
+    let first = nodes
+        .iter()
+        .position(|node| matches!(node, Node::Element(e) if e.name() == name && e.ns() == ns))
+        .expect("child itself");
+    for k in (first..index).rev() {
+        let (head, tail) = nodes.split_at_mut(k + 1);
+        std::mem::swap(&mut *head[k], &mut *tail[0]);
+    }
+    parent.remove_child(name, ns.as_str());
+}
+
+/// Select a child by [`Tag`] like [`TcxElement::child_matching`]
+fn select_tag(parent: &Element, tag: &Tag, matching: NamespaceMatching) -> Option<usize> {
+    let child = parent.child_matching(&[*tag], matching)?;
+    parent.children().position(|c| std::ptr::eq(c, child))
+}
+
+/// Select a child by its name in Clark notation as used in [`Trackpoint::extensions`]
+fn select_clark(parent: &Element, name: &&str) -> Option<usize> {
+    parent.children().position(|c| clark(c) == *name)
+}
+
+fn clark(element: &Element) -> String {
+    match element.ns().as_str() {
+        "" => element.name().to_string(),
+        ns => format!("{{{}}}{}", ns, element.name()),
+    }
+}
+
+/// Split a key of [`Trackpoint::extensions`] into element names in Clark notation
+///
+/// Namespaces may contain `/`, so the key is not simply split at each `/`.
+fn split_key(key: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = key;
+    while !rest.is_empty() {
+        let ns_end = match rest.starts_with('{') {
+            true => rest.find('}').map_or(0, |k| k + 1),
+            false => 0,
+        };
+        let end = rest[ns_end..].find('/').map_or(rest.len(), |k| ns_end + k);
+        names.push(&rest[..end]);
+        rest = rest[end..].strip_prefix('/').unwrap_or("");
+    }
+    names
+}
+
+impl Trackpoint {
+    /// Update a [`<Trackpoint>`][Tag::Trackpoint] element so that parsing it yields this track point
+    ///
+    /// Only elements whose values changed are touched, everything else, in particular elements and attributes not
+    /// represented by [`Trackpoint`], is kept as it is. Changed values are written where they were read from, new
+    /// values are written to the first of the field's paths, see [`TrkPtField::get_tags`][crate::TrkPtField::get_tags].
+    /// Values set to `None` are removed from all paths together with elements that become empty. Heart rates, cadences
+    /// and powers are rounded and clamped to the range of their integer schema types.
+    ///
+    /// Elements are matched to fields with [`NamespaceMatching::Strict`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use tcx::*;
+    /// use minidom::Element;
+    ///
+    /// let doc = r#"<Trackpoint xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+    ///   <Time>2022-12-31T12:00:00Z</Time>
+    ///   <HeartRateBpm><Value>250</Value></HeartRateBpm>
+    ///   <Extensions><Vendor xmlns="urn:vendor" Sensor="chest">1</Vendor></Extensions>
+    /// </Trackpoint>"#;
+    /// let mut element: Element = doc.parse().unwrap();
+    ///
+    /// let mut point = Trackpoint::parse(&element).unwrap();
+    /// point.heartrate = None;
+    /// point.distance = Some(12.5);
+    /// point.apply_to(&mut element);
+    ///
+    /// assert_eq!(Trackpoint::parse(&element).unwrap(), point);
+    /// assert!(element.child(&[Tag::HeartRateBpm]).is_none());
+    /// let vendor = element.get_child("Extensions", minidom::NSChoice::Any).unwrap().children().next().unwrap();
+    /// assert_eq!(vendor.attr("Sensor"), Some("chest"));
+    /// ```
+    pub fn apply_to(&self, trackpoint: &mut Element) {
+        self.apply_matching(trackpoint, NamespaceMatching::Strict)
+    }
+
+    /// Update a [`<Trackpoint>`][Tag::Trackpoint] element like [`Trackpoint::apply_to`] with the given matching
+    fn apply_matching(&self, trackpoint: &mut Element, matching: NamespaceMatching) {
+        let select_tag = |parent: &Element, tag| select_tag(parent, tag, matching);
+        let time = trackpoint
+            .child_matching(&[Tag::Time], matching)
+            .and_then(|e| {
+                ParseContext::default()
+                    .parse_time(&e.text(), Default::default())
+                    .ok()
+            });
+        if time != Some(self.time) {
+            set_path(trackpoint, &[Tag::Time], time_text(&self.time), matching);
+        }
+
+        for field in &TRK_PT_FIELD {
+            let tags = field.get_tags();
+            let current = tags
+                .iter()
+                .find(|tags| trackpoint.child_matching(tags, matching).is_some())
+                .copied();
+            match (self[field], current) {
+                (Some(value), Some(tags)) => {
+                    let current = trackpoint.child_value_matching::<f64>(tags, matching);
+                    if current.ok().flatten() != Some(value) {
+                        set_path(trackpoint, tags, value_text(tags, value), matching);
+                    }
+                }
+                (Some(value), None) => {
+                    set_path(trackpoint, tags[0], value_text(tags[0], value), matching)
+                }
+                (None, Some(_)) => {
+                    for tags in tags {
+                        while trackpoint.child_matching(tags, matching).is_some() {
+                            remove_path(trackpoint, tags, &select_tag);
+                        }
+                    }
+                }
+                (None, None) => (),
+            }
+        }
+
+        let current = unknown_extensions(trackpoint, matching);
+        if current == self.extensions {
+            return;
+        }
+        for key in current.keys().filter(|k| !self.extensions.contains_key(*k)) {
+            let mut path = vec![clark(
+                trackpoint
+                    .child_matching(&[Tag::Extensions], matching)
+                    .expect("extensions"),
+            )];
+            path.extend(split_key(key).into_iter().map(str::to_string));
+            let path = path.iter().map(String::as_str).collect::<Vec<_>>();
+            remove_path(trackpoint, &path, &select_clark);
+        }
+        for (key, value) in &self.extensions {
+            if current.get(key) == Some(value) {
+                continue;
+            }
+            if trackpoint
+                .child_matching(&[Tag::Extensions], matching)
+                .is_none()
+            {
+                insert_child(trackpoint, element(Tag::Extensions));
+            }
+            let mut parent = child_mut(trackpoint, Tag::Extensions, matching).expect("extensions");
+            let names = split_key(key);
+            let mut k = 0;
+            while k < names.len() {
+                match select_clark(parent, &names[k]) {
+                    Some(index) => {
+                        parent = parent.children_mut().nth(index).expect("index of child");
+                        k += 1;
+                    }
+                    None => break,
+                }
+            }
+            match k == names.len() {
+                true => set_text(parent, value.to_string()),
+                false => append_extension(parent, &names[k..].join("/"), value.to_string()),
+            }
+        }
+    }
+
+    /// Edit the track points of a TCX document in place, keeping everything not represented by [`Trackpoint`]
+    ///
+    /// Each [`<Trackpoint>`][Tag::Trackpoint] in a [`<Track>`][Tag::Track] is parsed and passed to `f` in document
+    /// order. Changes made by `f` are mapped back onto the element with [`Trackpoint::apply_to`], the element is removed
+    /// if `f` returns `false` (like [`Vec::retain_mut`]). A [`<Track>`][Tag::Track] is removed as well when all its
+    /// track points are removed, the schema requires at least one. Unchanged track points are not touched at all.
+    ///
+    /// Comments cannot be kept: minidom's parser only accepts restricted XML and rejects documents with comments.
+    ///
+    /// # Examples
+    /// ```
+    /// # use tcx::*;
+    /// use minidom::Element;
+    ///
+    /// let doc = r#"<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+    ///   <Activities><Activity Sport="Biking"><Lap><Track>
+    ///     <Trackpoint><Time>2022-12-31T12:00:00Z</Time><HeartRateBpm><Value>120</Value></HeartRateBpm></Trackpoint>
+    ///     <Trackpoint><Time>2022-12-31T12:00:01Z</Time><HeartRateBpm><Value>255</Value></HeartRateBpm></Trackpoint>
+    ///     <Trackpoint><Time>2022-12-31T12:00:02Z</Time><HeartRateBpm><Value>122</Value></HeartRateBpm></Trackpoint>
+    ///   </Track></Lap></Activity></Activities>
+    /// </TrainingCenterDatabase>"#;
+    /// let mut tcx: Element = doc.parse().unwrap();
+    ///
+    /// // drop implausible heart rate values
+    /// Trackpoint::edit_tcx(&mut tcx, |point| point.heartrate.map_or(true, |hr| hr < 220.0)).unwrap();
+    ///
+    /// let points = Trackpoint::from_tcx(&tcx, |_| true).unwrap();
+    /// assert_eq!(points.len(), 2);
+    /// assert_eq!(points[1].heartrate, Some(122.0));
+    /// ```
+    pub fn edit_tcx(
+        tcx: &mut Element,
+        f: impl FnMut(&mut Trackpoint) -> bool,
+    ) -> Result<(), TcxError> {
+        Trackpoint::edit_tcx_with(tcx, f, &mut ParseContext::default())
+    }
+
+    /// Edit the track points of a TCX document in place using the given [`ParseContext`]
+    ///
+    /// See [`Trackpoint::edit_tcx`]. Elements are matched with [`ParseContext::namespaces`], also when changes are
+    /// mapped back. Track points that are skipped in [`ParseMode::Lenient`][crate::ParseMode] are
+    /// not passed to `f` and kept as they are. Track point indices of errors and warnings count all
+    /// [`<Trackpoint>`][Tag::Trackpoint]s in document order.
+    pub fn edit_tcx_with(
+        tcx: &mut Element,
+        mut f: impl FnMut(&mut Trackpoint) -> bool,
+        context: &mut ParseContext,
+    ) -> Result<(), TcxError> {
+        edit_tracks(tcx, &mut f, context, &mut 0).map(|_| ())
+    }
+}
+
+/// Edit the track points of all [`<Track>`][Tag::Track]s in `element`
+///
+/// Returns whether `element` is a track whose track points were all removed.
+fn edit_tracks(
+    element: &mut Element,
+    f: &mut impl FnMut(&mut Trackpoint) -> bool,
+    context: &mut ParseContext,
+    index: &mut usize,
+) -> Result<bool, TcxError> {
+    let namespaces = context.namespaces;
+    let matches = |e: &Element, tag| namespaces.matches(e.name(), &e.ns(), tag);
+    if !matches(element, Tag::Track) {
+        let mut emptied = Vec::new();
+        for (k, node) in element.nodes_mut().enumerate() {
+            if let Some(child) = node.as_element_mut() {
+                if edit_tracks(child, f, context, index)? {
+                    emptied.push(k);
+                }
+            }
+        }
+        for k in emptied.into_iter().rev() {
+            remove_node(element, k);
+        }
+        return Ok(false);
+    }
+
+    let mut removed = Vec::new();
+    for (position, node) in element.nodes_mut().enumerate() {
+        let Some(trackpoint) = node
+            .as_element_mut()
+            .filter(|e| matches(e, Tag::Trackpoint))
+        else {
+            continue;
+        };
+        let k = *index;
+        *index += 1;
+
+        let since = context.warnings.len();
+        let parsed = Trackpoint::parse_with(trackpoint, context);
+        context.update_warnings(since, |err| err.with_trackpoint(k));
+        let original = match parsed {
+            Ok(point) => point,
+            Err(err) => {
+                context.recover(err.with_trackpoint(k), Recovery::TrackpointSkipped)?;
+                continue;
+            }
+        };
+
+        let mut point = original.clone();
+        if !f(&mut point) {
+            removed.push(position);
+        } else if point != original {
+            point.apply_matching(trackpoint, context.namespaces);
+        }
+    }
+
+    for position in removed.iter().rev() {
+        remove_node(element, *position);
+    }
+    Ok(!removed.is_empty() && !element.children().any(|e| matches(e, Tag::Trackpoint)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = r#"<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2" xmlns:ns3="http://www.garmin.com/xmlschemas/ActivityExtension/v2">
+  <Activities>
+    <Activity Sport="Running">
+      <Id>2022-12-31T12:00:00Z</Id>
+      <Lap StartTime="2022-12-31T12:00:00Z">
+        <Track>
+          <Trackpoint>
+            <Time>2022-12-31T14:00:00+02:00</Time>
+            <DistanceMeters>0.0</DistanceMeters>
+            <Cadence>80</Cadence>
+            <Extensions>
+              <ns3:TPX><ns3:Speed>2.5</ns3:Speed></ns3:TPX>
+              <Stryd xmlns="urn:vendor/stryd"><FormPower>65</FormPower><LSS>9.1</LSS></Stryd>
+            </Extensions>
+          </Trackpoint>
+          <Trackpoint>
+            <Time>2022-12-31T12:00:01Z</Time>
+            <DistanceMeters>2.5</DistanceMeters>
+          </Trackpoint>
+        </Track>
+      </Lap>
+      <Creator><Name>Forerunner 255</Name></Creator>
+    </Activity>
+  </Activities>
+</TrainingCenterDatabase>"#;
+
+    fn write(element: &Element) -> String {
+        let mut buf = Vec::new();
+        element.write_to(&mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_edit_unchanged() {
+        let mut tcx: Element = DOC.parse().unwrap();
+        let before = write(&tcx);
+
+        Trackpoint::edit_tcx(&mut tcx, |_| true).unwrap();
+
+        assert_eq!(before, write(&tcx));
+    }
+
+    #[test]
+    fn test_edit_changes() {
+        let mut tcx: Element = DOC.parse().unwrap();
+        let mut expected = Trackpoint::from_tcx(&tcx, |_| true).unwrap();
+
+        let edit = |point: &mut Trackpoint| {
+            point.heartrate = Some(150.0);
+            point.cadence = None;
+            point.speed = point.speed.map(|v| v * 2.0);
+            point.power = Some(240.0);
+            point.longitude = Some(9.0);
+            point.latitude = Some(48.5);
+            point
+                .extensions
+                .remove("{urn:vendor/stryd}Stryd/{urn:vendor/stryd}LSS");
+            point.extensions.insert(
+                "{urn:vendor/stryd}Stryd/{urn:vendor/stryd}Power".into(),
+                300.0,
+            );
+        };
+        expected.iter_mut().for_each(edit);
+        let mut k = 0;
+        Trackpoint::edit_tcx(&mut tcx, |point| {
+            edit(point);
+            k += 1;
+            k == 1
+        })
+        .unwrap();
+
+        assert_eq!(expected[..1], Trackpoint::from_tcx(&tcx, |_| true).unwrap());
+
+        // the time keeps its format, the creator is kept, the heart rate is inserted in schema order
+        let doc = write(&tcx);
+        assert!(doc.contains("<Time>2022-12-31T14:00:00+02:00</Time>"));
+        assert!(doc.contains("<Creator><Name>Forerunner 255</Name></Creator>"));
+        assert!(!doc.contains("<Cadence>"));
+        let position = |tag: &str| doc.find(tag).unwrap();
+        assert!(position("<Time>") < position("<Position>"));
+        assert!(position("<Position>") < position("<DistanceMeters>"));
+        assert!(position("<DistanceMeters>") < position("<HeartRateBpm>"));
+        assert!(position("<HeartRateBpm>") < position("<Extensions>"));
+        assert!(
+            doc.contains("<ns3:TPX><ns3:Speed>5</ns3:Speed><ns3:Watts>240</ns3:Watts></ns3:TPX>")
+        );
+        assert_eq!(1, doc.matches("<ns3:TPX>").count());
+        assert_eq!(1, doc.matches("<Trackpoint>").count());
+    }
+
+    #[test]
+    fn test_edit_remove() {
+        let mut tcx: Element = DOC.parse().unwrap();
+        let mut k = 0;
+        Trackpoint::edit_tcx(&mut tcx, |_| {
+            k += 1;
+            k == 2
+        })
+        .unwrap();
+
+        let doc = write(&tcx);
+        assert_eq!(1, doc.matches("<Trackpoint>").count());
+        assert!(doc.contains(
+            "<Track>\n          <Trackpoint>\n            <Time>2022-12-31T12:00:01Z</Time>"
+        ));
+
+        // the track is removed with its last track point, the lap is kept
+        Trackpoint::edit_tcx(&mut tcx, |_| false).unwrap();
+
+        let doc = write(&tcx);
+        assert!(!doc.contains("<Track>"));
+        assert!(doc.contains("<Lap StartTime=\"2022-12-31T12:00:00Z\">"));
+    }
+
+    #[test]
+    fn test_edit_integer_values() {
+        let mut tcx: Element = DOC.parse().unwrap();
+        Trackpoint::edit_tcx(&mut tcx, |point| {
+            point.heartrate = Some(300.2);
+            point.cadence = Some(84.6);
+            true
+        })
+        .unwrap();
+
+        let doc = write(&tcx);
+        assert_eq!(
+            2,
+            doc.matches("<HeartRateBpm><Value>255</Value></HeartRateBpm>")
+                .count()
+        );
+        assert_eq!(2, doc.matches("<Cadence>85</Cadence>").count());
+    }
+
+    #[test]
+    fn test_split_key() {
+        assert_eq!(
+            vec!["{urn:a/b}X", "Y", "{c}Z"],
+            split_key("{urn:a/b}X/Y/{c}Z")
+        );
+    }
+}
@@ -0,0 +1,307 @@
+use std::{
+    convert::Infallible,
+    fmt::{self, Display},
+    num::{ParseFloatError, ParseIntError},
+};
+
+use crate::{Tag, TrkPtField};
+
+/// a position in a TCX document
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TextPosition {
+    /// Line, counting from 1
+    pub line: usize,
+    /// Column in bytes, counting from 1
+    pub column: usize,
+}
+
+/// where a [`TcxError`] occurred
+///
+/// All fields are optional since not all information is available everywhere. The [`TcxElement`][crate::TcxElement]
+/// functions only know the `tags` (relative to the element they are called on), the other fields are added by the
+/// callers as the error is propagated.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct ErrorContext {
+    /// Index of the track point in the document, counting from 0 in the order of [`Tcx::trackpoints`][crate::Tcx::trackpoints]
+    pub trackpoint: Option<usize>,
+    /// The track point field being read
+    pub field: Option<TrkPtField>,
+    /// Path of tags to the offending element or attribute
+    pub tags: Vec<Tag>,
+    /// Position in the document, only available for the [`TrackpointReader`][crate::TrackpointReader] and
+    /// [`Trackpoint::from_csv`][crate::Trackpoint::from_csv]
+    pub position: Option<TextPosition>,
+}
+
+/// errors when reading TCX files
+#[derive(Debug)]
+pub enum TcxError {
+    /// A required element or attribute is missing
+    MissingField { context: ErrorContext },
+    /// A text is not a valid number
+    InvalidNumber { context: ErrorContext, text: String },
+    /// A text is not a valid timestamp
+    InvalidTimestamp { context: ErrorContext, text: String },
+    /// A text is not a valid value, e.g., not a variant of an enumeration such as [`Sport`][crate::Sport]
+    InvalidValue { context: ErrorContext, text: String },
+    /// The document is not well-formed XML
+    Xml {
+        source: minidom::Error,
+        position: Option<TextPosition>,
+    },
+    /// Reading the document failed
+    Io(std::io::Error),
+    /// The data is not a valid FIT file, `offset` is the position in bytes where decoding failed
+    Fit { reason: String, offset: usize },
+    /// The text is not a valid CSV file, `line` is the line where reading failed counting from 1
+    Csv { reason: String, line: usize },
+}
+
+/// errors from parsing texts with [`FromStr`][std::str::FromStr] that can be converted into a [`TcxError`]
+///
+/// This is used by [`TcxElement::child_value`][crate::TcxElement::child_value] to classify errors.
+pub trait IntoTcxError {
+    /// Convert into [`TcxError`] given the context and the text that failed to parse
+    fn into_tcx_error(self, context: ErrorContext, text: &str) -> TcxError;
+}
+
+impl IntoTcxError for ParseFloatError {
+    fn into_tcx_error(self, context: ErrorContext, text: &str) -> TcxError {
+        TcxError::InvalidNumber {
+            context,
+            text: text.to_string(),
+        }
+    }
+}
+
+impl IntoTcxError for ParseIntError {
+    fn into_tcx_error(self, context: ErrorContext, text: &str) -> TcxError {
+        TcxError::InvalidNumber {
+            context,
+            text: text.to_string(),
+        }
+    }
+}
+
+impl IntoTcxError for chrono::ParseError {
+    fn into_tcx_error(self, context: ErrorContext, text: &str) -> TcxError {
+        TcxError::InvalidTimestamp {
+            context,
+            text: text.to_string(),
+        }
+    }
+}
+
+impl IntoTcxError for String {
+    fn into_tcx_error(self, context: ErrorContext, text: &str) -> TcxError {
+        TcxError::InvalidValue {
+            context,
+            text: text.to_string(),
+        }
+    }
+}
+
+impl IntoTcxError for Infallible {
+    fn into_tcx_error(self, _: ErrorContext, _: &str) -> TcxError {
+        match self {}
+    }
+}
+
+impl TcxError {
+    /// Create a [`TcxError::MissingField`] error for the element or attribute at `tags`
+    pub fn missing(tags: &[Tag]) -> Self {
+        TcxError::MissingField {
+            context: ErrorContext {
+                tags: tags.to_vec(),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Get the error's context, if any
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            TcxError::MissingField { context }
+            | TcxError::InvalidNumber { context, .. }
+            | TcxError::InvalidTimestamp { context, .. }
+            | TcxError::InvalidValue { context, .. } => Some(context),
+            TcxError::Xml { .. }
+            | TcxError::Io(_)
+            | TcxError::Fit { .. }
+            | TcxError::Csv { .. } => None,
+        }
+    }
+
+    fn context_mut(&mut self) -> Option<&mut ErrorContext> {
+        match self {
+            TcxError::MissingField { context }
+            | TcxError::InvalidNumber { context, .. }
+            | TcxError::InvalidTimestamp { context, .. }
+            | TcxError::InvalidValue { context, .. } => Some(context),
+            TcxError::Xml { .. }
+            | TcxError::Io(_)
+            | TcxError::Fit { .. }
+            | TcxError::Csv { .. } => None,
+        }
+    }
+
+    /// Set the track point index
+    pub fn with_trackpoint(mut self, trackpoint: usize) -> Self {
+        if let Some(context) = self.context_mut() {
+            context.trackpoint = Some(trackpoint);
+        }
+        self
+    }
+
+    /// Add `offset` to the track point index if set
+    ///
+    /// This is used to turn an index relative to some parent element into an index relative to the document.
+    pub fn shift_trackpoint(mut self, offset: usize) -> Self {
+        if let Some(ErrorContext {
+            trackpoint: Some(trackpoint),
+            ..
+        }) = self.context_mut()
+        {
+            *trackpoint += offset;
+        }
+        self
+    }
+
+    /// Set the track point field
+    pub fn with_field(mut self, field: TrkPtField) -> Self {
+        if let Some(context) = self.context_mut() {
+            context.field = Some(field);
+        }
+        self
+    }
+
+    /// Set the position in the document unless it is already set
+    pub fn with_position(mut self, position: TextPosition) -> Self {
+        match &mut self {
+            TcxError::Xml { position: p, .. } => {
+                p.get_or_insert(position);
+            }
+            _ => {
+                if let Some(context) = self.context_mut() {
+                    context.position.get_or_insert(position);
+                }
+            }
+        }
+        self
+    }
+}
+
+impl Display for TextPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+impl Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for tag in &self.tags {
+            write!(f, "<{}>", tag.as_ref())?;
+        }
+        match (self.tags.is_empty(), self.field) {
+            (true, Some(field)) => write!(f, "field {}", field.as_ref())?,
+            (false, Some(field)) => write!(f, " (field {})", field.as_ref())?,
+            (_, None) => (),
+        }
+        if let Some(trackpoint) = self.trackpoint {
+            if self.tags.is_empty() && self.field.is_none() {
+                write!(f, "trackpoint {}", trackpoint)?;
+            } else {
+                write!(f, " of trackpoint {}", trackpoint)?;
+            }
+        }
+        if let Some(position) = self.position {
+            write!(f, " at {}", position)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for TcxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TcxError::MissingField { context } => write!(f, "Missing {}", context),
+            TcxError::InvalidNumber { context, text } => {
+                write!(f, "Invalid number '{}' in {}", text, context)
+            }
+            TcxError::InvalidTimestamp { context, text } => {
+                write!(f, "Invalid timestamp '{}' in {}", text, context)
+            }
+            TcxError::InvalidValue { context, text } => {
+                write!(f, "Invalid value '{}' in {}", text, context)
+            }
+            TcxError::Xml {
+                source,
+                position: Some(position),
+            } => write!(f, "{} at {}", source, position),
+            TcxError::Xml {
+                source,
+                position: None,
+            } => write!(f, "{}", source),
+            TcxError::Io(e) => write!(f, "IO error: {}", e),
+            TcxError::Fit { reason, offset } => {
+                write!(f, "Invalid FIT file at byte {}: {}", offset, reason)
+            }
+            TcxError::Csv { reason, line } => {
+                write!(f, "Invalid CSV file at line {}: {}", line, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TcxError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TcxError::Xml { source, .. } => Some(source),
+            TcxError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<minidom::Error> for TcxError {
+    fn from(source: minidom::Error) -> Self {
+        TcxError::Xml {
+            source,
+            position: None,
+        }
+    }
+}
+
+impl From<std::io::Error> for TcxError {
+    fn from(e: std::io::Error) -> Self {
+        TcxError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_display() {
+        let err = "abc"
+            .parse::<f64>()
+            .unwrap_err()
+            .into_tcx_error(
+                ErrorContext {
+                    tags: vec![Tag::Extensions, Tag::TPX, Tag::Watts],
+                    ..Default::default()
+                },
+                "abc",
+            )
+            .with_field(TrkPtField::Power)
+            .with_trackpoint(2)
+            .shift_trackpoint(10)
+            .with_position(TextPosition { line: 7, column: 5 });
+
+        assert_eq!(
+            "Invalid number 'abc' in <Extensions><TPX><Watts> (field Power) of trackpoint 12 at line 7, column 5",
+            err.to_string()
+        );
+    }
+}
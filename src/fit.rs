@@ -0,0 +1,862 @@
+use std::{collections::HashMap, io::Write};
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use crate::{ErrorContext, ParseContext, Recovery, Tag, TcxError, Trackpoint};
+
+/// Seconds from the Unix epoch to the FIT epoch, 1989-12-31T00:00:00Z
+pub(crate) const FIT_EPOCH: i64 = 631_065_600;
+
+/// Global message number of record messages
+pub(crate) const RECORD: u16 = 20;
+
+/// Field numbers of record messages
+pub(crate) mod record {
+    pub const POSITION_LAT: u8 = 0;
+    pub const POSITION_LONG: u8 = 1;
+    pub const ALTITUDE: u8 = 2;
+    pub const HEART_RATE: u8 = 3;
+    pub const CADENCE: u8 = 4;
+    pub const DISTANCE: u8 = 5;
+    pub const SPEED: u8 = 6;
+    pub const POWER: u8 = 7;
+    pub const TEMPERATURE: u8 = 13;
+    pub const FRACTIONAL_CADENCE: u8 = 53;
+    pub const ENHANCED_SPEED: u8 = 73;
+    pub const ENHANCED_ALTITUDE: u8 = 78;
+}
+
+/// Field number of timestamps in all messages
+pub(crate) const TIMESTAMP: u8 = 253;
+
+/// Degrees per semicircle, the unit of FIT positions
+pub(crate) const SEMICIRCLE: f64 = 180.0 / 2_147_483_648.0;
+
+const CRC_TABLE: [u16; 16] = [
+    0x0000, 0xCC01, 0xD801, 0x1400, 0xF001, 0x3C00, 0x2800, 0xE401, 0xA001, 0x6C00, 0x7800, 0xB401,
+    0x5000, 0x9C01, 0x8801, 0x4400,
+];
+
+/// Update a FIT CRC with `bytes`
+pub(crate) fn crc(mut crc: u16, bytes: &[u8]) -> u16 {
+    for byte in bytes {
+        for nibble in [byte & 0x0F, byte >> 4] {
+            let tmp = CRC_TABLE[(crc & 0x0F) as usize];
+            crc = (crc >> 4) & 0x0FFF;
+            crc = crc ^ tmp ^ CRC_TABLE[nibble as usize];
+        }
+    }
+    crc
+}
+
+/// Size in bytes and invalid value of FIT base types by base type number
+///
+/// Signed types are flagged, the invalid value of floats is all bits set.
+fn base_type(number: u8) -> Option<(usize, u64, bool)> {
+    match number & 0x1F {
+        0x00 | 0x02 | 0x0D => Some((1, 0xFF, false)),
+        0x01 => Some((1, 0x7F, true)),
+        0x03 => Some((2, 0x7FFF, true)),
+        0x04 => Some((2, 0xFFFF, false)),
+        0x05 => Some((4, 0x7FFF_FFFF, true)),
+        0x06 => Some((4, 0xFFFF_FFFF, false)),
+        0x08 => Some((4, 0xFFFF_FFFF, false)),
+        0x09 => Some((8, u64::MAX, false)),
+        0x0A => Some((1, 0, false)),
+        0x0B => Some((2, 0, false)),
+        0x0C => Some((4, 0, false)),
+        0x0E => Some((8, 0x7FFF_FFFF_FFFF_FFFF, true)),
+        0x0F => Some((8, u64::MAX, false)),
+        0x10 => Some((8, 0, false)),
+        // strings and unknown types
+        _ => None,
+    }
+}
+
+/// Decode the first value of a field, `None` if it is invalid or not numeric
+fn decode_value(bytes: &[u8], base: u8, big_endian: bool) -> Option<f64> {
+    let (size, invalid, signed) = base_type(base)?;
+    let bytes = bytes.get(..size)?;
+    let raw = match big_endian {
+        true => bytes.iter().fold(0u64, |acc, b| acc << 8 | *b as u64),
+        false => bytes.iter().rev().fold(0u64, |acc, b| acc << 8 | *b as u64),
+    };
+    if raw == invalid {
+        return None;
+    }
+
+    Some(match base & 0x1F {
+        0x08 => f32::from_bits(raw as u32) as f64,
+        0x09 => f64::from_bits(raw),
+        _ if signed => {
+            let shift = 64 - 8 * size;
+            ((raw << shift) as i64 >> shift) as f64
+        }
+        _ => raw as f64,
+    })
+}
+
+struct FieldDefinition {
+    number: u8,
+    size: usize,
+    base: u8,
+}
+
+struct Definition {
+    global: u16,
+    big_endian: bool,
+    fields: Vec<FieldDefinition>,
+    developer_size: usize,
+}
+
+/// a decoded FIT data message with its valid numeric fields
+///
+/// Values are the raw values before scaling. The timestamp is in seconds since the FIT epoch, it is also set for
+/// messages with a compressed timestamp header.
+pub(crate) struct Message {
+    pub global: u16,
+    pub timestamp: Option<u32>,
+    pub values: HashMap<u8, f64>,
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn error(&self, reason: impl Into<String>) -> TcxError {
+        TcxError::Fit {
+            reason: reason.into(),
+            offset: self.offset,
+        }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], TcxError> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + len)
+            .ok_or_else(|| self.error("unexpected end of file"))?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, TcxError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    /// Decode one FIT file starting at the current offset, calling `f` for each data message
+    fn file(&mut self, f: &mut impl FnMut(Message)) -> Result<(), TcxError> {
+        let start = self.offset;
+        let header_size = self.byte()? as usize;
+        if header_size < 12 {
+            return Err(self.error(format!("header size {} too small", header_size)));
+        }
+        let header = self.bytes(header_size - 1)?;
+        if &header[7..11] != b".FIT" {
+            return Err(self.error("missing .FIT signature"));
+        }
+        let data_size = u32::from_le_bytes([header[3], header[4], header[5], header[6]]) as usize;
+        if header_size >= 14 {
+            let header_crc = u16::from_le_bytes([header[11], header[12]]);
+            if header_crc != 0 && header_crc != crc(0, &self.data[start..start + 12]) {
+                return Err(self.error("header CRC mismatch"));
+            }
+        }
+
+        let end = self.offset + data_size;
+        let mut definitions: HashMap<u8, Definition> = HashMap::new();
+        let mut timestamp: Option<u32> = None;
+        while self.offset < end {
+            let header = self.byte()?;
+            if header & 0x80 != 0 {
+                // compressed timestamp header
+                let local = (header >> 5) & 0x03;
+                let offset = (header & 0x1F) as u32;
+                let last = timestamp
+                    .ok_or_else(|| self.error("compressed timestamp without previous timestamp"))?;
+                let mut time = (last & !0x1F) | offset;
+                if offset < last & 0x1F {
+                    time = time
+                        .checked_add(0x20)
+                        .ok_or_else(|| self.error("compressed timestamp out of range"))?;
+                }
+                timestamp = Some(time);
+                let mut message = self.data_message(local, &definitions)?;
+                message.timestamp = Some(time);
+                f(message);
+            } else if header & 0x40 != 0 {
+                let local = header & 0x0F;
+                let definition = self.definition(header & 0x20 != 0)?;
+                definitions.insert(local, definition);
+            } else {
+                let message = self.data_message(header & 0x0F, &definitions)?;
+                if message.timestamp.is_some() {
+                    timestamp = message.timestamp;
+                }
+                f(message);
+            }
+        }
+        if self.offset != end {
+            return Err(self.error("message exceeds data size"));
+        }
+
+        let file_crc = u16::from_le_bytes(self.bytes(2)?.try_into().expect("two bytes"));
+        if file_crc != crc(0, &self.data[start..end]) {
+            return Err(self.error("file CRC mismatch"));
+        }
+
+        Ok(())
+    }
+
+    fn definition(&mut self, developer: bool) -> Result<Definition, TcxError> {
+        let header = self.bytes(5)?;
+        let big_endian = header[1] == 1;
+        let global = match big_endian {
+            true => u16::from_be_bytes([header[2], header[3]]),
+            false => u16::from_le_bytes([header[2], header[3]]),
+        };
+        let fields = self
+            .bytes(3 * header[4] as usize)?
+            .chunks(3)
+            .map(|field| FieldDefinition {
+                number: field[0],
+                size: field[1] as usize,
+                base: field[2],
+            })
+            .collect();
+        let developer_size = match developer {
+            true => {
+                let count = self.byte()? as usize;
+                self.bytes(3 * count)?
+                    .chunks(3)
+                    .map(|field| field[1] as usize)
+                    .sum()
+            }
+            false => 0,
+        };
+
+        Ok(Definition {
+            global,
+            big_endian,
+            fields,
+            developer_size,
+        })
+    }
+
+    fn data_message(
+        &mut self,
+        local: u8,
+        definitions: &HashMap<u8, Definition>,
+    ) -> Result<Message, TcxError> {
+        let definition = definitions
+            .get(&local)
+            .ok_or_else(|| self.error(format!("undefined local message type {}", local)))?;
+
+        let mut message = Message {
+            global: definition.global,
+            timestamp: None,
+            values: HashMap::new(),
+        };
+        for field in &definition.fields {
+            let bytes = self.bytes(field.size)?;
+            if let Some(value) = decode_value(bytes, field.base, definition.big_endian) {
+                if field.number == TIMESTAMP {
+                    message.timestamp = Some(value as u32);
+                }
+                message.values.insert(field.number, value);
+            }
+        }
+        self.bytes(definition.developer_size)?;
+
+        Ok(message)
+    }
+}
+
+/// Decode all data messages of FIT data, which may consist of several chained FIT files
+pub(crate) fn decode(data: &[u8], mut f: impl FnMut(Message)) -> Result<(), TcxError> {
+    let mut decoder = Decoder { data, offset: 0 };
+    while decoder.offset < data.len() {
+        decoder.file(&mut f)?;
+    }
+    Ok(())
+}
+
+/// Convert seconds since the FIT epoch into a timestamp
+pub(crate) fn fit_time(timestamp: u32) -> DateTime<Utc> {
+    Utc.timestamp_opt(FIT_EPOCH + timestamp as i64, 0)
+        .single()
+        .expect("FIT timestamps are in range")
+}
+
+impl Trackpoint {
+    /// Read track points from the record messages of FIT data
+    ///
+    /// Record fields are mapped onto the track point's fields scaled to the units used in TCX files:
+    /// - `position_lat` and `position_long` from semicircles to degrees
+    /// - `enhanced_altitude` or `altitude` to meters
+    /// - `distance` to meters
+    /// - `heart_rate` in beats per minute
+    /// - `cadence` including `fractional_cadence`
+    /// - `enhanced_speed` or `speed` to meters per second
+    /// - `power` in watts
+    /// - `temperature` in degree Celsius to the [`air_temperature`][Trackpoint::air_temperature]
+    ///
+    /// Records without timestamp are an error. The result is filtered and deduplicated like
+    /// [`Trackpoint::from_tcx`].
+    ///
+    /// All other messages are ignored. The data may consist of several chained FIT files. The file and header CRCs
+    /// are checked.
+    pub fn from_fit(data: &[u8], filter: fn(&Self) -> bool) -> Result<Vec<Self>, TcxError> {
+        Trackpoint::from_fit_with(data, filter, &mut ParseContext::default())
+    }
+
+    /// Read track points from the record messages of FIT data using the given [`ParseContext`]
+    ///
+    /// See [`Trackpoint::from_fit`]. Records without timestamp are skipped in
+    /// [`ParseMode::Lenient`][crate::ParseMode::Lenient]. Track point indices of errors and warnings count all record
+    /// messages. Invalid FIT data is an error in any mode.
+    pub fn from_fit_with(
+        data: &[u8],
+        filter: fn(&Self) -> bool,
+        context: &mut ParseContext,
+    ) -> Result<Vec<Self>, TcxError> {
+        let mut records = Vec::new();
+        decode(data, |message| {
+            if message.global == RECORD {
+                records.push(message);
+            }
+        })?;
+
+        let mut points = Vec::new();
+        for (k, message) in records.iter().enumerate() {
+            match Trackpoint::from_record(message) {
+                Some(point) => points.push(point),
+                None => {
+                    let err = TcxError::MissingField {
+                        context: ErrorContext {
+                            trackpoint: Some(k),
+                            tags: vec![Tag::Time],
+                            ..Default::default()
+                        },
+                    };
+                    context.recover(err, Recovery::TrackpointSkipped)?;
+                }
+            }
+        }
+
+        Ok(Trackpoint::collect(points.iter(), filter))
+    }
+
+    fn from_record(message: &Message) -> Option<Self> {
+        let value = |number| message.values.get(&number).copied();
+        let scaled = |numbers: &[u8], scale: f64, offset: f64| {
+            numbers
+                .iter()
+                .find_map(|number| value(*number))
+                .map(|v| v / scale - offset)
+        };
+
+        Some(Trackpoint {
+            time: fit_time(message.timestamp?),
+            latitude: scaled(&[record::POSITION_LAT], 1.0 / SEMICIRCLE, 0.0),
+            longitude: scaled(&[record::POSITION_LONG], 1.0 / SEMICIRCLE, 0.0),
+            altitude: scaled(&[record::ENHANCED_ALTITUDE, record::ALTITUDE], 5.0, 500.0),
+            distance: scaled(&[record::DISTANCE], 100.0, 0.0),
+            heartrate: value(record::HEART_RATE),
+            cadence: value(record::CADENCE)
+                .map(|c| c + value(record::FRACTIONAL_CADENCE).unwrap_or(0.0) / 128.0),
+            speed: scaled(&[record::ENHANCED_SPEED, record::SPEED], 1000.0, 0.0),
+            power: value(record::POWER),
+            air_temperature: value(record::TEMPERATURE),
+            ..Default::default()
+        })
+    }
+}
+
+/// Base types used for encoding, with the endianness bit set for multi byte types
+mod base {
+    pub const ENUM: u8 = 0x00;
+    pub const SINT8: u8 = 0x01;
+    pub const UINT8: u8 = 0x02;
+    pub const UINT16: u8 = 0x84;
+    pub const SINT32: u8 = 0x85;
+    pub const UINT32: u8 = 0x86;
+    pub const UINT32Z: u8 = 0x8C;
+}
+
+/// Global message numbers and field numbers of the messages besides records written by the encoder
+mod message {
+    pub const FILE_ID: u16 = 0;
+    pub const SESSION: u16 = 18;
+    pub const LAP: u16 = 19;
+    pub const ACTIVITY: u16 = 34;
+
+    // file_id
+    pub const TYPE: u8 = 0;
+    pub const MANUFACTURER: u8 = 1;
+    pub const PRODUCT: u8 = 2;
+    pub const SERIAL_NUMBER: u8 = 3;
+    pub const TIME_CREATED: u8 = 4;
+
+    // lap and session
+    pub const EVENT: u8 = 0;
+    pub const EVENT_TYPE: u8 = 1;
+    pub const START_TIME: u8 = 2;
+    pub const SPORT: u8 = 5;
+    pub const TOTAL_ELAPSED_TIME: u8 = 7;
+    pub const TOTAL_TIMER_TIME: u8 = 8;
+    pub const TOTAL_DISTANCE: u8 = 9;
+    pub const LAP_AVG_HEART_RATE: u8 = 15;
+    pub const LAP_MAX_HEART_RATE: u8 = 16;
+    pub const SESSION_AVG_HEART_RATE: u8 = 16;
+    pub const SESSION_MAX_HEART_RATE: u8 = 17;
+    pub const FIRST_LAP_INDEX: u8 = 25;
+    pub const NUM_LAPS: u8 = 26;
+
+    // activity
+    pub const ACTIVITY_TOTAL_TIMER_TIME: u8 = 0;
+    pub const NUM_SESSIONS: u8 = 1;
+    pub const ACTIVITY_TYPE: u8 = 2;
+    pub const ACTIVITY_EVENT: u8 = 3;
+    pub const ACTIVITY_EVENT_TYPE: u8 = 4;
+
+    // enum values
+    pub const FILE_ACTIVITY: f64 = 4.0;
+    pub const MANUFACTURER_DEVELOPMENT: f64 = 255.0;
+    pub const EVENT_SESSION: f64 = 8.0;
+    pub const EVENT_LAP: f64 = 9.0;
+    pub const EVENT_ACTIVITY: f64 = 26.0;
+    pub const EVENT_TYPE_STOP: f64 = 1.0;
+    pub const SPORT_GENERIC: f64 = 0.0;
+    pub const ACTIVITY_MANUAL: f64 = 0.0;
+}
+
+/// Encode `value` as field of base type `base` in little endian, the invalid value if it is `None` or out of range
+fn encode_value(value: Option<f64>, base: u8) -> Vec<u8> {
+    let (size, invalid, signed) = base_type(base).expect("numeric base type");
+    let (min, max) = match (signed, invalid) {
+        (true, _) => (-(invalid as f64) - 1.0, invalid as f64 - 1.0),
+        (false, 0) => (1.0, (u64::MAX >> (64 - 8 * size)) as f64),
+        (false, _) => (0.0, invalid as f64 - 1.0),
+    };
+    let raw = match value.map(f64::round) {
+        Some(v) if v >= min && v <= max => v as i64 as u64,
+        _ => invalid,
+    };
+    raw.to_le_bytes()[..size].to_vec()
+}
+
+/// Convert a timestamp into seconds since the FIT epoch
+pub(crate) fn fit_timestamp(time: &DateTime<Utc>) -> f64 {
+    (time.timestamp() - FIT_EPOCH) as f64
+}
+
+/// an encoder writing one FIT file with a 14 byte header
+struct Encoder {
+    data: Vec<u8>,
+}
+
+impl Encoder {
+    fn new() -> Self {
+        Encoder { data: vec![0; 14] }
+    }
+
+    /// Write a data message for `local` with the raw `fields` (number, base type, value)
+    ///
+    /// If `define` is set, a definition message of `global` with the fields is written first.
+    fn message(&mut self, local: u8, global: u16, fields: &[(u8, u8, Option<f64>)], define: bool) {
+        if define {
+            self.data.extend([0x40 | local, 0, 0]);
+            self.data.extend(global.to_le_bytes());
+            self.data.push(fields.len() as u8);
+            for (number, base, _) in fields {
+                let (size, _, _) = base_type(*base).expect("numeric base type");
+                self.data.extend([*number, size as u8, *base]);
+            }
+        }
+        self.data.push(local);
+        for (_, base, value) in fields {
+            self.data.extend(encode_value(*value, *base));
+        }
+    }
+
+    /// Fill in the header and append the file CRC
+    fn finish(mut self) -> Vec<u8> {
+        let data_size = (self.data.len() - 14) as u32;
+        self.data[..12]
+            .copy_from_slice(&[14, 0x20, 0x08, 0x08, 0, 0, 0, 0, b'.', b'F', b'I', b'T']);
+        self.data[4..8].copy_from_slice(&data_size.to_le_bytes());
+        let header_crc = crc(0, &self.data[..12]);
+        self.data[12..14].copy_from_slice(&header_crc.to_le_bytes());
+        let file_crc = crc(0, &self.data);
+        self.data.extend(file_crc.to_le_bytes());
+        self.data
+    }
+}
+
+impl Trackpoint {
+    /// Encode track points as FIT activity file
+    ///
+    /// The file consists of a `file_id` message, a `record` message per track point, and a `lap`, `session` and
+    /// `activity` message summarizing all points. Fields are scaled as described for [`Trackpoint::from_fit`],
+    /// missing values and values out of the range of a FIT field are written as invalid values. Fields without a
+    /// counterpart in record messages, e.g. [`depth`][Trackpoint::depth] or [`extensions`][Trackpoint::extensions],
+    /// are not written.
+    ///
+    /// # Examples
+    /// ```
+    /// # use tcx::*;
+    ///
+    /// let point = Trackpoint {
+    ///     time: "2023-01-01T12:00:00Z".parse().unwrap(),
+    ///     heartrate: Some(120.0),
+    ///     ..Default::default()
+    /// };
+    /// let data = Trackpoint::to_fit(&[point.clone()]);
+    /// assert_eq!(&data[8..12], b".FIT");
+    /// assert_eq!(Trackpoint::from_fit(&data, |_| true).unwrap(), vec![point]);
+    /// ```
+    pub fn to_fit<'a>(points: impl IntoIterator<Item = &'a Trackpoint>) -> Vec<u8> {
+        use message::*;
+
+        let points = points.into_iter().collect::<Vec<_>>();
+        let mut encoder = Encoder::new();
+        let first = points.first();
+        let last = points.last();
+
+        encoder.message(
+            0,
+            FILE_ID,
+            &[
+                (TYPE, base::ENUM, Some(FILE_ACTIVITY)),
+                (MANUFACTURER, base::UINT16, Some(MANUFACTURER_DEVELOPMENT)),
+                (PRODUCT, base::UINT16, Some(0.0)),
+                (SERIAL_NUMBER, base::UINT32Z, None),
+                (
+                    TIME_CREATED,
+                    base::UINT32,
+                    first.map(|p| fit_timestamp(&p.time)),
+                ),
+            ],
+            true,
+        );
+
+        for (k, point) in points.iter().enumerate() {
+            let scaled =
+                |value: Option<f64>, scale: f64, offset: f64| value.map(|v| (v + offset) * scale);
+            encoder.message(
+                1,
+                RECORD,
+                &[
+                    (TIMESTAMP, base::UINT32, Some(fit_timestamp(&point.time))),
+                    (
+                        record::POSITION_LAT,
+                        base::SINT32,
+                        scaled(point.latitude, 1.0 / SEMICIRCLE, 0.0),
+                    ),
+                    (
+                        record::POSITION_LONG,
+                        base::SINT32,
+                        scaled(point.longitude, 1.0 / SEMICIRCLE, 0.0),
+                    ),
+                    (
+                        record::ENHANCED_ALTITUDE,
+                        base::UINT32,
+                        scaled(point.altitude, 5.0, 500.0),
+                    ),
+                    (
+                        record::DISTANCE,
+                        base::UINT32,
+                        scaled(point.distance, 100.0, 0.0),
+                    ),
+                    (record::HEART_RATE, base::UINT8, point.heartrate),
+                    (record::CADENCE, base::UINT8, point.cadence.map(f64::trunc)),
+                    (
+                        record::FRACTIONAL_CADENCE,
+                        base::UINT8,
+                        point.cadence.map(|c| c.fract() * 128.0),
+                    ),
+                    (
+                        record::ENHANCED_SPEED,
+                        base::UINT32,
+                        scaled(point.speed, 1000.0, 0.0),
+                    ),
+                    (record::POWER, base::UINT16, point.power),
+                    (record::TEMPERATURE, base::SINT8, point.air_temperature),
+                ],
+                k == 0,
+            );
+        }
+
+        if let (Some(first), Some(last)) = (first, last) {
+            let start_time = Some(fit_timestamp(&first.time));
+            let timestamp = Some(fit_timestamp(&last.time));
+            let total_time = Some((last.time - first.time).num_milliseconds() as f64);
+            let total_distance = first
+                .distance
+                .zip(last.distance)
+                .map(|(first, last)| (last - first) * 100.0);
+            let heartrates = points
+                .iter()
+                .filter_map(|p| p.heartrate)
+                .collect::<Vec<_>>();
+            let avg_heartrate = match heartrates.len() {
+                0 => None,
+                n => Some(heartrates.iter().sum::<f64>() / n as f64),
+            };
+            let max_heartrate = heartrates.iter().copied().reduce(f64::max);
+
+            encoder.message(
+                2,
+                LAP,
+                &[
+                    (TIMESTAMP, base::UINT32, timestamp),
+                    (EVENT, base::ENUM, Some(EVENT_LAP)),
+                    (EVENT_TYPE, base::ENUM, Some(EVENT_TYPE_STOP)),
+                    (START_TIME, base::UINT32, start_time),
+                    (TOTAL_ELAPSED_TIME, base::UINT32, total_time),
+                    (TOTAL_TIMER_TIME, base::UINT32, total_time),
+                    (TOTAL_DISTANCE, base::UINT32, total_distance),
+                    (LAP_AVG_HEART_RATE, base::UINT8, avg_heartrate),
+                    (LAP_MAX_HEART_RATE, base::UINT8, max_heartrate),
+                ],
+                true,
+            );
+            encoder.message(
+                3,
+                SESSION,
+                &[
+                    (TIMESTAMP, base::UINT32, timestamp),
+                    (EVENT, base::ENUM, Some(EVENT_SESSION)),
+                    (EVENT_TYPE, base::ENUM, Some(EVENT_TYPE_STOP)),
+                    (START_TIME, base::UINT32, start_time),
+                    (SPORT, base::ENUM, Some(SPORT_GENERIC)),
+                    (TOTAL_ELAPSED_TIME, base::UINT32, total_time),
+                    (TOTAL_TIMER_TIME, base::UINT32, total_time),
+                    (TOTAL_DISTANCE, base::UINT32, total_distance),
+                    (SESSION_AVG_HEART_RATE, base::UINT8, avg_heartrate),
+                    (SESSION_MAX_HEART_RATE, base::UINT8, max_heartrate),
+                    (FIRST_LAP_INDEX, base::UINT16, Some(0.0)),
+                    (NUM_LAPS, base::UINT16, Some(1.0)),
+                ],
+                true,
+            );
+            encoder.message(
+                4,
+                ACTIVITY,
+                &[
+                    (TIMESTAMP, base::UINT32, timestamp),
+                    (ACTIVITY_TOTAL_TIMER_TIME, base::UINT32, total_time),
+                    (NUM_SESSIONS, base::UINT16, Some(1.0)),
+                    (ACTIVITY_TYPE, base::ENUM, Some(ACTIVITY_MANUAL)),
+                    (ACTIVITY_EVENT, base::ENUM, Some(EVENT_ACTIVITY)),
+                    (ACTIVITY_EVENT_TYPE, base::ENUM, Some(EVENT_TYPE_STOP)),
+                ],
+                true,
+            );
+        }
+
+        encoder.finish()
+    }
+
+    /// Write track points as FIT activity file, see [`Trackpoint::to_fit`]
+    pub fn write_fit<'a, W: Write>(
+        points: impl IntoIterator<Item = &'a Trackpoint>,
+        writer: &mut W,
+    ) -> Result<(), TcxError> {
+        Ok(writer.write_all(&Trackpoint::to_fit(points))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wrap `records` into a FIT file with 14 byte header and CRCs
+    fn fit_file(records: &[u8]) -> Vec<u8> {
+        let mut data = vec![14, 0x20, 0x08, 0x08];
+        data.extend((records.len() as u32).to_le_bytes());
+        data.extend(b".FIT");
+        data.extend(crc(0, &data).to_le_bytes());
+        data.extend(records);
+        data.extend(crc(0, &data).to_le_bytes());
+        data
+    }
+
+    fn records() -> Vec<u8> {
+        let mut records = Vec::new();
+
+        // definition of local type 0: little endian record with timestamp, position, enhanced altitude, heart rate,
+        // distance, power and temperature and one developer field of 2 bytes
+        records.extend([0x60, 0, 0, 20, 0, 7]);
+        records.extend([253, 4, 0x86, 0, 4, 0x85, 1, 4, 0x85, 78, 4, 0x86]);
+        records.extend([3, 1, 0x02, 5, 4, 0x86, 13, 1, 0x01]);
+        records.extend([1, 0, 2, 0]);
+        // data
+        records.push(0x00);
+        records.extend(1_000_000_000u32.to_le_bytes());
+        records.extend(((48.5 / SEMICIRCLE) as i32).to_le_bytes());
+        records.extend(((9.25 / SEMICIRCLE) as i32).to_le_bytes());
+        records.extend((((450.0 + 500.0) * 5.0) as u32).to_le_bytes());
+        records.extend([120, 0x10, 0x27, 0, 0, (-5i8) as u8]);
+        records.extend([0xAB, 0xCD]);
+
+        // definition of local type 1: big endian record with heart rate, cadence, speed and invalid power
+        records.extend([0x41, 0, 1, 0, 20, 4]);
+        records.extend([3, 1, 0x02, 4, 1, 0x02, 6, 2, 0x84, 7, 2, 0x84]);
+        // compressed timestamp, 2 seconds after the last one
+        let offset = ((1_000_000_000u32 + 2) & 0x1F) as u8;
+        records.extend([0x80 | 0x20 | offset, 121, 90]);
+        records.extend(7500u16.to_be_bytes());
+        records.extend([0xFF, 0xFF]);
+
+        // a non record message (event) with a timestamp 40 seconds later, then a compressed timestamp wrapping
+        records.extend([0x42, 0, 0, 21, 0, 1, 253, 4, 0x86]);
+        records.push(0x02);
+        records.extend((1_000_000_000u32 + 40).to_le_bytes());
+        let offset = ((1_000_000_000u32 + 40 + 31) & 0x1F) as u8;
+        records.extend([0x80 | 0x20 | offset, 122, 91, 0, 0, 0, 0]);
+
+        records
+    }
+
+    #[test]
+    fn test_crc() {
+        assert_eq!(0xBB3D, crc(0, b"123456789"));
+    }
+
+    #[test]
+    fn test_from_fit() {
+        let data = fit_file(&records());
+
+        let points = Trackpoint::from_fit(&data, |_| true).unwrap();
+
+        let time = fit_time(1_000_000_000);
+        assert_eq!(
+            "2021-09-08T01:46:40Z",
+            time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        );
+        assert_eq!(points.len(), 3);
+
+        let first = &points[0];
+        assert_eq!(first.time, time);
+        assert!((first.latitude.unwrap() - 48.5).abs() < 1e-6);
+        assert!((first.longitude.unwrap() - 9.25).abs() < 1e-6);
+        assert_eq!(first.altitude, Some(450.0));
+        assert_eq!(first.heartrate, Some(120.0));
+        assert_eq!(first.distance, Some(100.0));
+        assert_eq!(first.air_temperature, Some(-5.0));
+
+        let second = &points[1];
+        assert_eq!(second.time, time + chrono::Duration::seconds(2));
+        assert_eq!(second.heartrate, Some(121.0));
+        assert_eq!(second.cadence, Some(90.0));
+        assert_eq!(second.speed, Some(7.5));
+        assert_eq!(second.power, None);
+        assert_eq!(second.latitude, None);
+
+        assert_eq!(points[2].time, time + chrono::Duration::seconds(71));
+
+        // chained files
+        let mut chained = data.clone();
+        chained.extend(&data);
+        assert_eq!(6, Trackpoint::from_fit(&chained, |_| true).unwrap().len());
+    }
+
+    #[test]
+    fn test_from_fit_errors() {
+        let mut data = fit_file(&records());
+        let len = data.len();
+        data[len - 3] ^= 0xFF;
+        assert_eq!(
+            "Invalid FIT file at byte 118: file CRC mismatch",
+            Trackpoint::from_fit(&data, |_| true)
+                .unwrap_err()
+                .to_string()
+        );
+
+        let data = fit_file(&records()[..20]);
+        assert!(matches!(
+            Trackpoint::from_fit(&data, |_| true),
+            Err(TcxError::Fit { .. })
+        ));
+
+        let data = fit_file(&[0x03, 0x00]);
+        assert_eq!(
+            "Invalid FIT file at byte 15: undefined local message type 3",
+            Trackpoint::from_fit(&data, |_| true)
+                .unwrap_err()
+                .to_string()
+        );
+
+        // compressed timestamp rolling over past the largest timestamp
+        let mut records = vec![0x40, 0, 0, 20, 0, 1, 253, 4, 0x86, 0x00];
+        records.extend(0xFFFF_FFF0u32.to_le_bytes());
+        records.push(0x80 | 0x01);
+        assert_eq!(
+            "Invalid FIT file at byte 29: compressed timestamp out of range",
+            Trackpoint::from_fit(&fit_file(&records), |_| true)
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_from_fit_lenient() {
+        // a record without timestamp between two records with timestamps
+        let mut records = vec![0x40, 0, 0, 20, 0, 2, 253, 4, 0x86, 3, 1, 0x02];
+        for timestamp in [1_000_000_000u32, u32::MAX, 1_000_000_001] {
+            records.push(0x00);
+            records.extend(timestamp.to_le_bytes());
+            records.push(120);
+        }
+        let data = fit_file(&records);
+
+        let err = Trackpoint::from_fit(&data, |_| true).unwrap_err();
+        assert!(matches!(err, TcxError::MissingField { .. }));
+        assert_eq!(Some(1), err.context().unwrap().trackpoint);
+
+        let mut context = ParseContext::new(crate::ParseMode::Lenient);
+        let points = Trackpoint::from_fit_with(&data, |_| true, &mut context).unwrap();
+        assert_eq!(2, points.len());
+        assert_eq!(1, context.warnings.len());
+        assert_eq!(Recovery::TrackpointSkipped, context.warnings[0].recovery);
+    }
+
+    #[test]
+    fn test_to_fit() {
+        let points = Trackpoint::from_fit(&fit_file(&records()), |_| true).unwrap();
+        let data = Trackpoint::to_fit(&points);
+
+        assert_eq!(points, Trackpoint::from_fit(&data, |_| true).unwrap());
+
+        let mut messages = Vec::new();
+        decode(&data, |message| messages.push(message)).unwrap();
+        let globals = messages.iter().map(|m| m.global).collect::<Vec<_>>();
+        assert_eq!(globals, vec![0, 20, 20, 20, 19, 18, 34]);
+
+        let session = &messages[5];
+        assert_eq!(session.timestamp, Some(1_000_000_071));
+        assert_eq!(session.values[&message::START_TIME], 1_000_000_000.0);
+        assert_eq!(session.values[&message::TOTAL_ELAPSED_TIME], 71_000.0);
+        assert_eq!(session.values[&message::SESSION_MAX_HEART_RATE], 122.0);
+        assert!(!session.values.contains_key(&message::TOTAL_DISTANCE));
+
+        // out of range values are written as invalid
+        let point = Trackpoint {
+            heartrate: Some(300.0),
+            air_temperature: Some(-200.0),
+            ..points[0].clone()
+        };
+        let decoded = Trackpoint::from_fit(&Trackpoint::to_fit([&point]), |_| true).unwrap();
+        assert_eq!(decoded[0].heartrate, None);
+        assert_eq!(decoded[0].air_temperature, None);
+        assert_eq!(decoded[0].altitude, Some(450.0));
+
+        assert_eq!(
+            Trackpoint::from_fit(&Trackpoint::to_fit([]), |_| true).unwrap(),
+            vec![]
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use minidom::Element;
+
+use crate::{ParseContext, Tag, TcxError};
+
+/// a folder organizing activities, workouts or courses ([`<Folder>`][Tag::Folder] and the sport specific folders)
+///
+/// Folders do not contain the items themselves but references to them, i.e., the [`Activity::id`][crate::Activity::id]
+/// for activities ([`<ActivityRef>`][Tag::ActivityRef], [`<MultisportActivityRef>`][Tag::MultisportActivityRef]),
+/// the [`Workout::name`][crate::Workout::name] for workouts ([`<WorkoutNameRef>`][Tag::WorkoutNameRef]) and the
+/// [`Course::name`][crate::Course::name] for courses ([`<CourseNameRef>`][Tag::CourseNameRef]).
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Folder {
+    /// Name of the folder (attribute [`Name`][Tag::Name])
+    pub name: String,
+    /// Sub folders ([`<Folder>`][Tag::Folder])
+    pub folders: Vec<Folder>,
+    /// Referenced items' IDs ([`<Id>`][Tag::Id] of the reference elements)
+    pub refs: Vec<String>,
+    /// Notes ([`<Notes>`][Tag::Notes])
+    pub notes: Option<String>,
+}
+
+/// sport specific folders ([`<History>`][Tag::History] or [`<Workouts>`][Tag::Workouts] in
+/// [`<Folders>`][Tag::Folders])
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SportFolders {
+    /// Folder for running ([`<Running>`][Tag::Running])
+    pub running: Option<Folder>,
+    /// Folder for biking ([`<Biking>`][Tag::Biking])
+    pub biking: Option<Folder>,
+    /// Folder for other sports ([`<Other>`][Tag::Other])
+    pub other: Option<Folder>,
+    /// Folder for multi sport sessions ([`<MultiSport>`][Tag::MultiSport], history only)
+    pub multi_sport: Option<Folder>,
+}
+
+/// the folder tree of a TCX document ([`<Folders>`][Tag::Folders])
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Folders {
+    /// Folders for activities ([`<History>`][Tag::History])
+    pub history: Option<SportFolders>,
+    /// Folders for workouts ([`<Workouts>`][Tag::Workouts])
+    pub workouts: Option<SportFolders>,
+    /// Folder for courses ([`<Courses>`][Tag::Courses]&#173;[`<CourseFolder>`][Tag::CourseFolder])
+    pub courses: Option<Folder>,
+}
+
+impl Folder {
+    /// Parse a folder element
+    pub fn parse(folder: &Element) -> Result<Self, TcxError> {
+        Folder::parse_with(folder, &mut ParseContext::default())
+    }
+
+    /// Parse a folder element using the given [`ParseContext`]
+    pub fn parse_with(folder: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        let folders = context
+            .children_tagged(folder, Tag::Folder)
+            .map(|e| Folder::parse_with(e, context))
+            .collect::<Result<_, _>>()?;
+
+        let namespaces = context.namespaces;
+        let refs = folder
+            .children()
+            .filter(|e| {
+                [
+                    Tag::ActivityRef,
+                    Tag::MultisportActivityRef,
+                    Tag::WorkoutNameRef,
+                    Tag::CourseNameRef,
+                ]
+                .into_iter()
+                .any(|tag| namespaces.matches(e.name(), &e.ns(), tag))
+            })
+            .filter_map(|e| context.child_value(e, &[Tag::Id]).transpose())
+            .collect::<Result<_, _>>()?;
+
+        Ok(Folder {
+            name: folder.attr(Tag::Name.as_ref()).unwrap_or("").to_string(),
+            folders,
+            refs,
+            notes: context.child_value(folder, &[Tag::Notes])?,
+        })
+    }
+}
+
+impl SportFolders {
+    /// Parse a [`<History>`][Tag::History] or [`<Workouts>`][Tag::Workouts] element in
+    /// [`<Folders>`][Tag::Folders]
+    pub fn parse(folders: &Element) -> Result<Self, TcxError> {
+        SportFolders::parse_with(folders, &mut ParseContext::default())
+    }
+
+    /// Parse a [`<History>`][Tag::History] or [`<Workouts>`][Tag::Workouts] element using the given
+    /// [`ParseContext`]
+    pub fn parse_with(folders: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        let mut folder = |tag| {
+            context
+                .child(folders, &[tag])
+                .map(|e| Folder::parse_with(e, context))
+                .transpose()
+        };
+
+        Ok(SportFolders {
+            running: folder(Tag::Running)?,
+            biking: folder(Tag::Biking)?,
+            other: folder(Tag::Other)?,
+            multi_sport: folder(Tag::MultiSport)?,
+        })
+    }
+}
+
+impl Folders {
+    /// Parse a [`<Folders>`][Tag::Folders] element
+    ///
+    /// # Examples
+    /// ```
+    /// # use tcx::*;
+    ///
+    /// let doc = r#"<Folders xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+    ///   <History>
+    ///     <Biking Name="Biking">
+    ///       <Folder Name="Commute">
+    ///         <ActivityRef><Id>2022-12-31T12:00:00Z</Id></ActivityRef>
+    ///       </Folder>
+    ///     </Biking>
+    ///   </History>
+    ///   <Workouts>
+    ///     <Running Name="Running">
+    ///       <WorkoutNameRef><Id>Intervals</Id></WorkoutNameRef>
+    ///     </Running>
+    ///   </Workouts>
+    /// </Folders>"#;
+    ///
+    /// let folders = Folders::parse(&doc.parse().unwrap()).unwrap();
+    ///
+    /// let biking = folders.history.unwrap().biking.unwrap();
+    /// assert_eq!(biking.folders[0].name, "Commute");
+    /// assert_eq!(biking.folders[0].refs, vec!["2022-12-31T12:00:00Z"]);
+    /// let running = folders.workouts.unwrap().running.unwrap();
+    /// assert_eq!(running.refs, vec!["Intervals"]);
+    /// assert!(folders.courses.is_none());
+    /// ```
+    pub fn parse(folders: &Element) -> Result<Self, TcxError> {
+        Folders::parse_with(folders, &mut ParseContext::default())
+    }
+
+    /// Parse a [`<Folders>`][Tag::Folders] element using the given [`ParseContext`]
+    pub fn parse_with(folders: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        Ok(Folders {
+            history: context
+                .child(folders, &[Tag::History])
+                .map(|e| SportFolders::parse_with(e, context))
+                .transpose()?,
+            workouts: context
+                .child(folders, &[Tag::Workouts])
+                .map(|e| SportFolders::parse_with(e, context))
+                .transpose()?,
+            courses: context
+                .child(folders, &[Tag::Courses, Tag::CourseFolder])
+                .map(|e| Folder::parse_with(e, context))
+                .transpose()?,
+        })
+    }
+}
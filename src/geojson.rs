@@ -0,0 +1,330 @@
+use std::{fmt::Write as _, io::Write};
+
+use crate::{writer::time_text, Lap, TcxError, Trackpoint, LAP_FIELD, TRK_PT_FIELD};
+
+/// how track points are represented in GeoJSON, see [`Trackpoint::to_geojson`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GeoJsonLayout {
+    /// One `LineString` feature per track, properties are arrays with one value per coordinate
+    #[default]
+    LineString,
+    /// One `Point` feature per track point
+    Points,
+}
+
+/// Quote and escape `text` as JSON string
+fn string(text: &str) -> String {
+    let mut result = String::with_capacity(text.len() + 2);
+    result.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if c.is_control() => write!(result, "\\u{:04x}", c as u32).expect("write to string"),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Format a value as JSON number, `null` if it is `None` or not finite
+fn number(value: Option<f64>) -> String {
+    match value {
+        Some(v) if v.is_finite() => v.to_string(),
+        _ => "null".to_string(),
+    }
+}
+
+/// Get the GeoJSON position `[longitude, latitude]` or `[longitude, latitude, altitude]`, `None` if the point has no
+/// valid position
+fn position(point: &Trackpoint) -> Option<String> {
+    match (point.longitude, point.latitude, point.altitude) {
+        (Some(lon), Some(lat), _) if !lon.is_finite() || !lat.is_finite() => None,
+        (Some(lon), Some(lat), Some(alt)) if alt.is_finite() => {
+            Some(format!("[{},{},{}]", lon, lat, alt))
+        }
+        (Some(lon), Some(lat), _) => Some(format!("[{},{}]", lon, lat)),
+        _ => None,
+    }
+}
+
+/// Create a `LineString` feature, `properties` are additional properties ending with `,` if not empty
+///
+/// The geometry is `null` if there are less than two points with a position.
+fn line_string(points: &[(&Trackpoint, String)], properties: &str) -> String {
+    let geometry = match points.len() {
+        0 | 1 => "null".to_string(),
+        _ => format!(
+            r#"{{"type":"LineString","coordinates":[{}]}}"#,
+            points
+                .iter()
+                .map(|(_, position)| position.as_str())
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+    };
+
+    let mut values = vec![format!(
+        r#""Time":[{}]"#,
+        points
+            .iter()
+            .map(|(point, _)| string(&time_text(&point.time)))
+            .collect::<Vec<_>>()
+            .join(",")
+    )];
+    for field in &TRK_PT_FIELD {
+        values.push(format!(
+            "{}:[{}]",
+            string(field.as_ref()),
+            points
+                .iter()
+                .map(|(point, _)| number(point[field]))
+                .collect::<Vec<_>>()
+                .join(",")
+        ));
+    }
+
+    format!(
+        r#"{{"type":"Feature","geometry":{},"properties":{{{}{}}}}}"#,
+        geometry,
+        properties,
+        values.join(",")
+    )
+}
+
+/// Create a `Point` feature, `properties` are additional properties ending with `,` if not empty
+fn point(point: &Trackpoint, position: &str, properties: &str) -> String {
+    let mut values = vec![format!(r#""Time":{}"#, string(&time_text(&point.time)))];
+    for field in &TRK_PT_FIELD {
+        values.push(format!(
+            "{}:{}",
+            string(field.as_ref()),
+            number(point[field])
+        ));
+    }
+
+    format!(
+        r#"{{"type":"Feature","geometry":{{"type":"Point","coordinates":{}}},"properties":{{{}{}}}}}"#,
+        position,
+        properties,
+        values.join(",")
+    )
+}
+
+/// Get points with their position, skipping points without
+fn positioned<'a>(
+    points: impl IntoIterator<Item = &'a Trackpoint>,
+) -> Vec<(&'a Trackpoint, String)> {
+    points
+        .into_iter()
+        .filter_map(|p| position(p).map(|position| (p, position)))
+        .collect()
+}
+
+/// Create features for `points` in the given layout
+fn features(
+    points: &[(&Trackpoint, String)],
+    layout: GeoJsonLayout,
+    properties: &str,
+) -> Vec<String> {
+    match layout {
+        GeoJsonLayout::LineString => vec![line_string(points, properties)],
+        GeoJsonLayout::Points => points
+            .iter()
+            .map(|(p, position)| point(p, position, properties))
+            .collect(),
+    }
+}
+
+fn feature_collection(features: Vec<String>) -> String {
+    format!(
+        "{{\"type\":\"FeatureCollection\",\"features\":[\n{}\n]}}\n",
+        features.join(",\n")
+    )
+}
+
+impl Trackpoint {
+    /// Create a GeoJSON document for track points
+    ///
+    /// With [`GeoJsonLayout::LineString`] the document is a single `LineString` feature. Its properties are `Time`
+    /// and the name of each [`TrkPtField`][crate::TrkPtField], each an array with one value per coordinate. With
+    /// [`GeoJsonLayout::Points`] the document is a `FeatureCollection` of `Point` features, each with properties
+    /// `Time` and the names of the [`TrkPtField`][crate::TrkPtField]s.
+    ///
+    /// Points without latitude or longitude are skipped. Positions include the altitude if available. Times are RFC
+    /// 3339 strings, missing values are `null`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use tcx::*;
+    ///
+    /// let points = [
+    ///     Trackpoint {
+    ///         time: "2022-12-31T12:00:00Z".parse().unwrap(),
+    ///         latitude: Some(48.0),
+    ///         longitude: Some(9.0),
+    ///         heartrate: Some(120.0),
+    ///         ..Default::default()
+    ///     },
+    ///     Trackpoint {
+    ///         time: "2022-12-31T12:00:01Z".parse().unwrap(),
+    ///         ..Default::default()
+    ///     },
+    /// ];
+    ///
+    /// let geojson = Trackpoint::to_geojson(&points, GeoJsonLayout::Points);
+    ///
+    /// assert!(geojson.contains(r#""geometry":{"type":"Point","coordinates":[9,48]}"#));
+    /// assert!(geojson.contains(r#""Time":"2022-12-31T12:00:00Z""#));
+    /// assert!(geojson.contains(r#""Heartrate":120,"Cadence":null"#));
+    /// assert!(!geojson.contains("12:00:01"));
+    /// ```
+    pub fn to_geojson<'a>(
+        points: impl IntoIterator<Item = &'a Trackpoint>,
+        layout: GeoJsonLayout,
+    ) -> String {
+        let points = positioned(points);
+        match layout {
+            GeoJsonLayout::LineString => line_string(&points, "") + "\n",
+            GeoJsonLayout::Points => feature_collection(features(&points, layout, "")),
+        }
+    }
+
+    /// Write a GeoJSON document for track points, see [`Trackpoint::to_geojson`]
+    pub fn write_geojson<'a, W: Write>(
+        points: impl IntoIterator<Item = &'a Trackpoint>,
+        layout: GeoJsonLayout,
+        writer: &mut W,
+    ) -> Result<(), TcxError> {
+        Ok(writer.write_all(Trackpoint::to_geojson(points, layout).as_bytes())?)
+    }
+}
+
+impl Lap {
+    /// Create a GeoJSON `FeatureCollection` with separate features for each lap
+    ///
+    /// The features are created as described for [`Trackpoint::to_geojson`] from each lap's track points passing
+    /// `filter`, see [`Trackpoint::collect`]. Their properties additionally contain an object `Lap` with the lap's
+    /// `Index`, its `StartTime` and the name of each [`LapField`][crate::LapField] with the lap's value.
+    pub fn to_geojson<'a>(
+        laps: impl IntoIterator<Item = &'a Lap>,
+        filter: fn(&Trackpoint) -> bool,
+        layout: GeoJsonLayout,
+    ) -> String {
+        let mut result = Vec::new();
+        for (k, lap) in laps.into_iter().enumerate() {
+            let mut properties = format!(r#""Lap":{{"Index":{},"StartTime":"#, k);
+            match lap.start_time {
+                Some(time) => properties.push_str(&string(&time_text(&time))),
+                None => properties.push_str("null"),
+            }
+            for field in &LAP_FIELD {
+                write!(
+                    properties,
+                    ",{}:{}",
+                    string(field.as_ref()),
+                    number(lap[field])
+                )
+                .expect("write to string");
+            }
+            properties.push_str("},");
+
+            let points = Trackpoint::collect(lap.trackpoints(), filter);
+            result.extend(features(&positioned(&points), layout, &properties));
+        }
+
+        feature_collection(result)
+    }
+
+    /// Write a GeoJSON `FeatureCollection` with separate features for each lap, see [`Lap::to_geojson`]
+    pub fn write_geojson<'a, W: Write>(
+        laps: impl IntoIterator<Item = &'a Lap>,
+        filter: fn(&Trackpoint) -> bool,
+        layout: GeoJsonLayout,
+        writer: &mut W,
+    ) -> Result<(), TcxError> {
+        Ok(writer.write_all(Lap::to_geojson(laps, filter, layout).as_bytes())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Track;
+
+    use super::*;
+
+    fn points() -> Vec<Trackpoint> {
+        (0..3)
+            .map(|k| Trackpoint {
+                time: "2022-12-31T12:00:00Z"
+                    .parse::<chrono::DateTime<chrono::Utc>>()
+                    .unwrap()
+                    + chrono::Duration::seconds(k),
+                latitude: (k != 1).then_some(48.0 + k as f64),
+                longitude: Some(9.5),
+                altitude: (k == 0).then_some(450.0),
+                heartrate: (k != 2).then_some(f64::NAN),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_geojson_string() {
+        assert_eq!(string("a\"b\\c\nd\u{1}"), r#""a\"b\\c\nd\u0001""#);
+    }
+
+    #[test]
+    fn test_to_geojson() {
+        let points = points();
+
+        let geojson = Trackpoint::to_geojson(&points, GeoJsonLayout::LineString);
+        assert!(geojson.starts_with(
+            r#"{"type":"Feature","geometry":{"type":"LineString","coordinates":[[9.5,48,450],[9.5,50]]},"properties":{"Time":["2022-12-31T12:00:00Z","2022-12-31T12:00:02Z"],"Latitude":[48,50]"#
+        ));
+        assert!(geojson.contains(r#""Heartrate":[null,null]"#));
+        assert!(geojson.ends_with("}}\n"));
+
+        let geojson = Trackpoint::to_geojson(&points[..1], GeoJsonLayout::LineString);
+        assert!(geojson.starts_with(r#"{"type":"Feature","geometry":null,"#));
+
+        let laps = [
+            Lap {
+                start_time: Some(points[0].time),
+                distance: Some(100.0),
+                tracks: vec![Track {
+                    trackpoints: points[..2].to_vec(),
+                }],
+                ..Default::default()
+            },
+            Lap {
+                tracks: vec![Track {
+                    trackpoints: points[2..].to_vec(),
+                }],
+                ..Default::default()
+            },
+        ];
+        let geojson = Lap::to_geojson(&laps, |_| true, GeoJsonLayout::Points);
+        let lines = geojson.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], r#"{"type":"FeatureCollection","features":["#);
+        assert!(lines[1].contains(
+            r#""properties":{"Lap":{"Index":0,"StartTime":"2022-12-31T12:00:00Z","TotalTime":null,"Distance":100,"#
+        ));
+        assert!(lines[1].ends_with("},"));
+        assert!(lines[2].contains(
+            r#""coordinates":[9.5,50]},"properties":{"Lap":{"Index":1,"StartTime":null,"#
+        ));
+        assert_eq!(lines[3], "]}");
+
+        // points not passing the filter are left out
+        let geojson = Lap::to_geojson(&laps, |t| t.altitude.is_some(), GeoJsonLayout::Points);
+        let lines = geojson.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].contains(r#""coordinates":[9.5,48,450]}"#));
+    }
+}
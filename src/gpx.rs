@@ -0,0 +1,490 @@
+use std::io::Write;
+
+use minidom::Element;
+
+use crate::{
+    collect_extensions,
+    writer::{append_extension, append_path, element, time_text, value_text},
+    Namespace, ParseContext, Recovery, Tag, TcxElement, TcxError, Trackpoint, TrkPtField,
+    TRK_PT_FIELD,
+};
+
+/// Mean earth radius in meters used to calculate distances between GPX track points
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+impl TrkPtField {
+    /// Get tags for field in a GPX [`<trkpt>`][Tag::Trkpt] as slice of slices of [`Tag`]s.
+    ///
+    /// Like [`TrkPtField::get_tags`] but for GPX files with Garmin's track point and power extensions, the Cluetrust
+    /// GPX data extension or plain [`<power>`][Tag::GpxPower] elements. The slices are empty for
+    /// [`TrkPtField::Latitude`] and [`TrkPtField::Longitude`], which are attributes ([`lat`][Tag::Lat] and
+    /// [`lon`][Tag::Lon]), and for [`TrkPtField::Distance`], which is calculated from the positions, see
+    /// [`Trackpoint::from_gpx`].
+    pub fn get_gpx_tags(&self) -> &[&[Tag]] {
+        match self {
+            TrkPtField::Latitude | TrkPtField::Longitude | TrkPtField::Distance => &[],
+            TrkPtField::Altitude => &[&[Tag::Ele]],
+            TrkPtField::Heartrate => &[
+                &[Tag::GpxExtensions, Tag::TrackPointExtension, Tag::Hr],
+                &[Tag::GpxExtensions, Tag::Hr],
+            ],
+            TrkPtField::Cadence => &[
+                &[Tag::GpxExtensions, Tag::TrackPointExtension, Tag::Cad],
+                &[Tag::GpxExtensions, Tag::GpxdataCadence],
+            ],
+            TrkPtField::Speed => &[&[
+                Tag::GpxExtensions,
+                Tag::TrackPointExtension,
+                Tag::TpxV2Speed,
+            ]],
+            TrkPtField::Power => &[
+                &[Tag::GpxExtensions, Tag::PowerInWatts],
+                &[Tag::GpxExtensions, Tag::GpxPower],
+            ],
+            TrkPtField::AirTemperature => &[
+                &[
+                    Tag::GpxExtensions,
+                    Tag::TrackPointExtension,
+                    Tag::AirTemperature,
+                ],
+                &[Tag::GpxExtensions, Tag::Temp],
+            ],
+            TrkPtField::WaterTemperature => &[&[
+                Tag::GpxExtensions,
+                Tag::TrackPointExtension,
+                Tag::WaterTemperature,
+            ]],
+            TrkPtField::Depth => &[&[Tag::GpxExtensions, Tag::TrackPointExtension, Tag::Depth]],
+            TrkPtField::Course => &[&[
+                Tag::GpxExtensions,
+                Tag::TrackPointExtension,
+                Tag::TpxV2Course,
+            ]],
+            TrkPtField::Bearing => &[&[Tag::GpxExtensions, Tag::TrackPointExtension, Tag::Bearing]],
+        }
+    }
+}
+
+/// Fields written to a GPX [`<trkpt>`][Tag::Trkpt] in the order required by the schemas
+const GPX_FIELDS: [TrkPtField; 10] = [
+    TrkPtField::Altitude,
+    TrkPtField::AirTemperature,
+    TrkPtField::WaterTemperature,
+    TrkPtField::Depth,
+    TrkPtField::Heartrate,
+    TrkPtField::Cadence,
+    TrkPtField::Speed,
+    TrkPtField::Course,
+    TrkPtField::Bearing,
+    TrkPtField::Power,
+];
+
+/// Great circle distance in meters between two positions given as latitude and longitude in degrees
+fn haversine((lat_1, lon_1): (f64, f64), (lat_2, lon_2): (f64, f64)) -> f64 {
+    let (phi_1, phi_2) = (lat_1.to_radians(), lat_2.to_radians());
+    let d_phi = phi_2 - phi_1;
+    let d_lambda = (lon_2 - lon_1).to_radians();
+    let a =
+        (d_phi / 2.0).sin().powi(2) + phi_1.cos() * phi_2.cos() * (d_lambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
+
+impl Trackpoint {
+    /// Read track points from a GPX document's root element ([`<gpx>`][Tag::Gpx])
+    ///
+    /// All [`<trkpt>`][Tag::Trkpt]s of all [`<trkseg>`][Tag::Trkseg]s of all [`<trk>`][Tag::Trk]s are read, routes
+    /// and waypoints are ignored. GPX has no distance, so [`Trackpoint::distance`] is the distance travelled in the
+    /// track calculated from the positions. Distances between segments of a track are not counted, points without
+    /// position have no distance.
+    ///
+    /// The result is filtered and deduplicated like [`Trackpoint::from_tcx`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use tcx::*;
+    ///
+    /// let doc = r#"<gpx xmlns="http://www.topografix.com/GPX/1/1"
+    ///     xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v1">
+    ///   <trk><trkseg>
+    ///     <trkpt lat="48.0" lon="9.0">
+    ///       <ele>450.0</ele>
+    ///       <time>2022-12-31T12:00:00Z</time>
+    ///       <extensions>
+    ///         <gpxtpx:TrackPointExtension><gpxtpx:hr>120</gpxtpx:hr></gpxtpx:TrackPointExtension>
+    ///       </extensions>
+    ///     </trkpt>
+    ///     <trkpt lat="48.001" lon="9.0">
+    ///       <ele>451.0</ele>
+    ///       <time>2022-12-31T12:00:30Z</time>
+    ///     </trkpt>
+    ///   </trkseg></trk>
+    /// </gpx>"#;
+    ///
+    /// let points = Trackpoint::from_gpx(&doc.parse().unwrap(), |_| true).unwrap();
+    ///
+    /// assert_eq!(points.len(), 2);
+    /// assert_eq!(points[0].heartrate, Some(120.0));
+    /// assert_eq!(points[0].distance, Some(0.0));
+    /// assert_eq!(points[1].distance.unwrap().round(), 111.0);
+    /// ```
+    pub fn from_gpx(gpx: &Element, filter: fn(&Self) -> bool) -> Result<Vec<Self>, TcxError> {
+        Trackpoint::from_gpx_with(gpx, filter, &mut ParseContext::default())
+    }
+
+    /// Read track points from a GPX document's root element using the given [`ParseContext`]
+    ///
+    /// See [`Trackpoint::from_gpx`]. Track point indices of errors and warnings count all [`<trkpt>`][Tag::Trkpt]s
+    /// of all tracks.
+    pub fn from_gpx_with(
+        gpx: &Element,
+        filter: fn(&Self) -> bool,
+        context: &mut ParseContext,
+    ) -> Result<Vec<Self>, TcxError> {
+        let mut points = Vec::new();
+        let mut k = 0;
+        for trk in context.children_tagged(gpx, Tag::Trk) {
+            let mut distance = 0.0;
+            for trkseg in context.children_tagged(trk, Tag::Trkseg) {
+                let mut previous = None;
+                for trkpt in context.children_tagged(trkseg, Tag::Trkpt) {
+                    let since = context.warnings.len();
+                    let result = Trackpoint::parse_gpx_with(trkpt, context);
+                    context.update_warnings(since, |err| err.with_trackpoint(k));
+                    match result {
+                        Ok(mut point) => {
+                            if let (Some(lat), Some(lon)) = (point.latitude, point.longitude) {
+                                if let Some(previous) = previous {
+                                    distance += haversine(previous, (lat, lon));
+                                }
+                                previous = Some((lat, lon));
+                                point.distance = Some(distance);
+                            }
+                            points.push(point);
+                        }
+                        Err(err) => {
+                            context.recover(err.with_trackpoint(k), Recovery::TrackpointSkipped)?
+                        }
+                    }
+                    k += 1;
+                }
+            }
+        }
+
+        Ok(Trackpoint::collect(points.iter(), filter))
+    }
+
+    /// Parse a single trackpoint for a GPX [`<trkpt>`][Tag::Trkpt]
+    ///
+    /// The [`Trackpoint::distance`] is not set, see [`Trackpoint::from_gpx`].
+    pub fn parse_gpx(trkpt: &Element) -> Result<Self, TcxError> {
+        Trackpoint::parse_gpx_with(trkpt, &mut ParseContext::default())
+    }
+
+    /// Parse a single trackpoint for a GPX [`<trkpt>`][Tag::Trkpt] using the given [`ParseContext`]
+    ///
+    /// Invalid fields are dropped in [`ParseMode::Lenient`][crate::ParseMode::Lenient]. A missing or invalid time is
+    /// an error in any mode.
+    pub fn parse_gpx_with(trkpt: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        let time = context
+            .child_time(trkpt, &[Tag::GpxTime])?
+            .ok_or_else(|| TcxError::missing(&[Tag::GpxTime]))?;
+        let mut point = Trackpoint {
+            time,
+            ..Default::default()
+        };
+
+        for (field, tag) in [
+            (TrkPtField::Latitude, Tag::Lat),
+            (TrkPtField::Longitude, Tag::Lon),
+        ] {
+            match trkpt.attr_value(tag) {
+                Ok(val) => point[&field] = val,
+                Err(e) => context.recover(e.with_field(field), Recovery::FieldDropped)?,
+            }
+        }
+
+        for field in &TRK_PT_FIELD {
+            for tags in field.get_gpx_tags() {
+                match context.child_value(trkpt, tags) {
+                    Ok(Some(val)) => {
+                        point[field] = Some(val);
+                        break;
+                    }
+                    Ok(None) => (),
+                    Err(e) => context.recover(e.with_field(*field), Recovery::FieldDropped)?,
+                }
+            }
+        }
+
+        if let Some(extensions) = context.child(trkpt, &[Tag::GpxExtensions]) {
+            // elements represented by a field
+            let known = TRK_PT_FIELD
+                .iter()
+                .flat_map(|field| field.get_gpx_tags().iter())
+                .filter_map(|tags| context.child(trkpt, tags))
+                .collect::<Vec<_>>();
+            collect_extensions(extensions, "", &known, &mut point.extensions);
+        }
+
+        Ok(point)
+    }
+}
+
+impl Trackpoint {
+    /// Create a GPX [`<trkpt>`][Tag::Trkpt] element
+    ///
+    /// Each field is written to the first of its GPX paths, see [`TrkPtField::get_gpx_tags`], i.e., heart rate,
+    /// cadence, speed and temperatures go to Garmin's [track point extension v2][Namespace::TrackPointExtensionV2] and
+    /// power goes to Garmin's [power extension][Namespace::PowerExtensionV1]. The
+    /// [`extensions`][Trackpoint::extensions] are written to [`<extensions>`][Tag::GpxExtensions]. The distance is not
+    /// written, GPX has no element for it. Heart rate and cadence are rounded and clamped to the range of their integer
+    /// schema types.
+    ///
+    /// GPX requires a position for each track point, see [`Trackpoint::to_gpx`].
+    pub fn to_gpx_element(&self) -> Element {
+        let mut trkpt = element(Tag::Trkpt);
+        if let Some(latitude) = self.latitude {
+            trkpt.set_attr(Tag::Lat.as_ref(), latitude.to_string());
+        }
+        if let Some(longitude) = self.longitude {
+            trkpt.set_attr(Tag::Lon.as_ref(), longitude.to_string());
+        }
+
+        for field in GPX_FIELDS {
+            if let (Some(value), Some(tags)) = (self[&field], field.get_gpx_tags().first()) {
+                append_path(&mut trkpt, tags, value_text(tags, value));
+            }
+            if field == TrkPtField::Altitude {
+                // time follows the elevation
+                trkpt
+                    .append_child(element(Tag::GpxTime))
+                    .append_text_node(time_text(&self.time));
+            }
+        }
+
+        if !self.extensions.is_empty() {
+            if !trkpt.has_child(
+                Tag::GpxExtensions.as_ref(),
+                Tag::GpxExtensions.namespace().uri(),
+            ) {
+                trkpt.append_child(element(Tag::GpxExtensions));
+            }
+            let extensions = trkpt
+                .get_child_mut(
+                    Tag::GpxExtensions.as_ref(),
+                    Tag::GpxExtensions.namespace().uri(),
+                )
+                .expect("child appended above");
+            for (key, value) in &self.extensions {
+                append_extension(extensions, key, value.to_string());
+            }
+        }
+
+        trkpt
+    }
+
+    /// Create a GPX 1.1 document's root element ([`<gpx>`][Tag::Gpx]) with one track containing the `points`
+    ///
+    /// Points without latitude or longitude are skipped, GPX requires a position for each track point. The element
+    /// declares the GPX 1.1 namespace as default namespace, `gpxtpx` for the
+    /// [track point extension][Namespace::TrackPointExtensionV2] and `gpxpx` for the
+    /// [power extension][Namespace::PowerExtensionV1].
+    ///
+    /// # Examples
+    /// ```
+    /// # use tcx::*;
+    ///
+    /// let points = vec![Trackpoint {
+    ///     time: "2022-12-31T12:00:00Z".parse().unwrap(),
+    ///     latitude: Some(48.0),
+    ///     longitude: Some(9.0),
+    ///     altitude: Some(450.0),
+    ///     heartrate: Some(120.0),
+    ///     power: Some(210.0),
+    ///     ..Default::default()
+    /// }];
+    ///
+    /// let gpx = Trackpoint::to_gpx(&points);
+    ///
+    /// let trkpt = gpx.child(&[Tag::Trk, Tag::Trkseg, Tag::Trkpt]).unwrap();
+    /// assert!(trkpt.child(&[Tag::GpxExtensions, Tag::TrackPointExtension, Tag::Hr]).unwrap().is_tag(Tag::Hr));
+    /// assert_eq!(
+    ///     Trackpoint::from_gpx(&gpx, |_| true).unwrap(),
+    ///     vec![Trackpoint { distance: Some(0.0), ..points[0].clone() }]
+    /// );
+    /// ```
+    pub fn to_gpx<'a>(points: impl IntoIterator<Item = &'a Trackpoint>) -> Element {
+        let mut builder = Element::builder(Tag::Gpx.as_ref(), Tag::Gpx.namespace().uri())
+            .attr("version", "1.1")
+            .attr("creator", "tcx");
+        for (prefix, uri) in [
+            (None, Namespace::Gpx11.uri()),
+            (Some("gpxtpx"), Namespace::TrackPointExtensionV2.uri()),
+            (Some("gpxpx"), Namespace::PowerExtensionV1.uri()),
+        ] {
+            builder = builder
+                .prefix(prefix.map(str::to_string), uri)
+                .expect("prefixes are distinct");
+        }
+        let mut gpx = builder.build();
+
+        let mut trkseg = element(Tag::Trkseg);
+        for point in points {
+            if point.latitude.is_some() && point.longitude.is_some() {
+                trkseg.append_child(point.to_gpx_element());
+            }
+        }
+        gpx.append_child(element(Tag::Trk)).append_child(trkseg);
+
+        gpx
+    }
+
+    /// Write a GPX 1.1 document including the XML declaration, see [`Trackpoint::to_gpx`]
+    pub fn write_gpx<'a, W: Write>(
+        points: impl IntoIterator<Item = &'a Trackpoint>,
+        writer: &mut W,
+    ) -> Result<(), TcxError> {
+        Trackpoint::to_gpx(points)
+            .write_to_decl(writer)
+            .map_err(|source| TcxError::Xml {
+                source,
+                position: None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ParseMode, Recovery};
+
+    use super::*;
+
+    const DOC: &str = r#"<gpx xmlns="http://www.topografix.com/GPX/1/1"
+    xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v2"
+    xmlns:pwr="http://www.garmin.com/xmlschemas/PowerExtension/v1"
+    xmlns:gpxdata="http://www.cluetrust.com/XML/GPXDATA/1/0">
+  <wpt lat="0.0" lon="0.0"><time>2022-12-31T11:00:00Z</time></wpt>
+  <trk>
+    <trkseg>
+      <trkpt lat="48.0" lon="9.0">
+        <ele>450.5</ele>
+        <time>2022-12-31T12:00:00Z</time>
+        <extensions>
+          <gpxtpx:TrackPointExtension>
+            <gpxtpx:atemp>21.5</gpxtpx:atemp>
+            <gpxtpx:hr>120</gpxtpx:hr>
+            <gpxtpx:cad>85</gpxtpx:cad>
+            <gpxtpx:speed>7.5</gpxtpx:speed>
+          </gpxtpx:TrackPointExtension>
+          <pwr:PowerInWatts>210</pwr:PowerInWatts>
+        </extensions>
+      </trkpt>
+      <trkpt lat="48.0" lon="9.001">
+        <ele>garbled</ele>
+        <time>2022-12-31T12:00:10Z</time>
+        <extensions>
+          <gpxdata:hr>121</gpxdata:hr>
+          <gpxdata:cadence>86</gpxdata:cadence>
+          <gpxdata:temp>21.0</gpxdata:temp>
+          <power>215</power>
+          <gpxdata:lap>1</gpxdata:lap>
+        </extensions>
+      </trkpt>
+    </trkseg>
+    <trkseg>
+      <trkpt lat="48.1" lon="9.001"><time>2022-12-31T12:10:00Z</time></trkpt>
+      <trkpt><time>garbled</time></trkpt>
+      <trkpt lat="48.1" lon="9.002"><time>2022-12-31T12:10:10Z</time></trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
+
+    #[test]
+    fn test_gpx_parse() {
+        let gpx = DOC.parse().unwrap();
+
+        let err = Trackpoint::from_gpx(&gpx, |_| true).unwrap_err();
+        assert_eq!(err.context().unwrap().trackpoint, Some(1));
+
+        let mut context = ParseContext::new(ParseMode::Lenient);
+        let points = Trackpoint::from_gpx_with(&gpx, |_| true, &mut context).unwrap();
+
+        assert_eq!(points.len(), 4);
+        assert_eq!(
+            context
+                .warnings
+                .iter()
+                .map(|w| (w.error.context().unwrap().trackpoint, w.recovery))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some(1), Recovery::FieldDropped),
+                (Some(3), Recovery::TrackpointSkipped)
+            ]
+        );
+
+        let first = &points[0];
+        assert_eq!(first.latitude, Some(48.0));
+        assert_eq!(first.longitude, Some(9.0));
+        assert_eq!(first.altitude, Some(450.5));
+        assert_eq!(first.heartrate, Some(120.0));
+        assert_eq!(first.cadence, Some(85.0));
+        assert_eq!(first.speed, Some(7.5));
+        assert_eq!(first.power, Some(210.0));
+        assert_eq!(first.air_temperature, Some(21.5));
+        assert!(first.extensions.is_empty());
+
+        let second = &points[1];
+        assert_eq!(second.altitude, None);
+        assert_eq!(second.heartrate, Some(121.0));
+        assert_eq!(second.cadence, Some(86.0));
+        assert_eq!(second.power, Some(215.0));
+        assert_eq!(second.air_temperature, Some(21.0));
+        assert_eq!(
+            second.extensions.keys().collect::<Vec<_>>(),
+            vec!["{http://www.cluetrust.com/XML/GPXDATA/1/0}lap"]
+        );
+
+        // distances are counted within segments only
+        let d = haversine((48.0, 9.0), (48.0, 9.001));
+        assert!((d - 74.4).abs() < 0.1, "{}", d);
+        assert_eq!(
+            points.iter().map(|p| p.distance).collect::<Vec<_>>(),
+            vec![
+                Some(0.0),
+                Some(d),
+                Some(d),
+                Some(d + haversine((48.1, 9.001), (48.1, 9.002)))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gpx_write() {
+        let gpx = DOC.parse().unwrap();
+        let mut context = ParseContext::new(ParseMode::Lenient);
+        let points = Trackpoint::from_gpx_with(&gpx, |_| true, &mut context).unwrap();
+
+        let mut buf = Vec::new();
+        Trackpoint::write_gpx(&points, &mut buf).unwrap();
+        let written = String::from_utf8(buf).unwrap();
+
+        // segments are joined, so distances differ
+        let without_distance = |points: Vec<Trackpoint>| {
+            points
+                .into_iter()
+                .map(|p| Trackpoint {
+                    distance: None,
+                    ..p
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            without_distance(points),
+            without_distance(Trackpoint::from_gpx(&written.parse().unwrap(), |_| true).unwrap())
+        );
+        assert!(written.contains("<gpxtpx:TrackPointExtension><gpxtpx:atemp>21.5</gpxtpx:atemp><gpxtpx:hr>120</gpxtpx:hr><gpxtpx:cad>85</gpxtpx:cad><gpxtpx:speed>7.5</gpxtpx:speed></gpxtpx:TrackPointExtension><gpxpx:PowerInWatts>210</gpxpx:PowerInWatts>"));
+        assert!(written.contains(
+            "<trkpt lat=\"48\" lon=\"9\"><ele>450.5</ele><time>2022-12-31T12:00:00Z</time>"
+        ));
+    }
+}
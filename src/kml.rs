@@ -0,0 +1,470 @@
+use std::{
+    fmt::{self, Display},
+    io::Write,
+    str::FromStr,
+};
+
+use minidom::Element;
+
+use crate::{Namespace, TcxError, Trackpoint, TrkPtField};
+
+/// Number of colors a gradient is sampled at, each one is a KML style
+const LEVELS: usize = 16;
+
+/// Name of the KML document in a KMZ archive
+const KMZ_ENTRY: &str = "doc.kml";
+
+/// a color gradient mapping values linearly onto colors
+///
+/// Gradients parse from and format as comma separated RGB hex colors, e.g., `00ff00,ffff00,ff0000`, optionally
+/// prefixed with `#`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Gradient {
+    /// RGB colors from the lowest to the highest value, evenly spaced
+    pub colors: Vec<[u8; 3]>,
+    /// Value mapped to the first color, the minimum of all values if `None`
+    pub min: Option<f64>,
+    /// Value mapped to the last color, the maximum of all values if `None`
+    pub max: Option<f64>,
+}
+
+impl Default for Gradient {
+    /// Green over yellow to red
+    fn default() -> Self {
+        Gradient {
+            colors: vec![[0x00, 0xFF, 0x00], [0xFF, 0xFF, 0x00], [0xFF, 0x00, 0x00]],
+            min: None,
+            max: None,
+        }
+    }
+}
+
+impl Gradient {
+    /// Get the color at position `t` between 0 (first color) and 1 (last color)
+    ///
+    /// # Examples
+    /// ```
+    /// # use tcx::*;
+    ///
+    /// let gradient: Gradient = "000000,#ff8000".parse().unwrap();
+    ///
+    /// assert_eq!(gradient.color(0.0), [0x00, 0x00, 0x00]);
+    /// assert_eq!(gradient.color(0.5), [0x80, 0x40, 0x00]);
+    /// assert_eq!(gradient.color(7.0), [0xFF, 0x80, 0x00]);
+    /// ```
+    pub fn color(&self, t: f64) -> [u8; 3] {
+        let Some(last) = self.colors.len().checked_sub(1) else {
+            return [0x80, 0x80, 0x80];
+        };
+        let x = t.clamp(0.0, 1.0) * last as f64;
+        let k = (x.floor() as usize).min(last.saturating_sub(1));
+        let f = x - k as f64;
+        let (a, b) = (self.colors[k], self.colors[(k + 1).min(last)]);
+        [0, 1, 2].map(|i| (a[i] as f64 + (b[i] as f64 - a[i] as f64) * f).round() as u8)
+    }
+}
+
+impl FromStr for Gradient {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let colors = s
+            .split(',')
+            .map(|color| {
+                let hex = color.trim().trim_start_matches('#');
+                match (hex.len(), u32::from_str_radix(hex, 16)) {
+                    (6, Ok(rgb)) => Ok([(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8]),
+                    _ => Err(format!("'{}' is not a color, expected RRGGBB", color)),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Gradient {
+            colors,
+            ..Default::default()
+        })
+    }
+}
+
+impl Display for Gradient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let colors = self
+            .colors
+            .iter()
+            .map(|[r, g, b]| format!("{:02x}{:02x}{:02x}", r, g, b))
+            .collect::<Vec<_>>();
+        write!(f, "{}", colors.join(","))
+    }
+}
+
+/// a named point added to a KML document, see [`Trackpoint::to_kml`]
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct KmlPlacemark {
+    /// Name shown on the map
+    pub name: String,
+    /// Optional text shown in the placemark's balloon
+    pub description: Option<String>,
+    /// Latitude in degrees
+    pub latitude: f64,
+    /// Longitude in degrees
+    pub longitude: f64,
+}
+
+fn kml_element(name: &str) -> Element {
+    Element::bare(name, Namespace::Kml22.uri())
+}
+
+fn text_element(name: &str, text: impl Into<String>) -> Element {
+    let mut element = kml_element(name);
+    element.append_text_node(text);
+    element
+}
+
+fn coordinates(points: &[(f64, f64)]) -> String {
+    points
+        .iter()
+        .map(|(lat, lon)| format!("{},{}", lon, lat))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Create a line style with a color given as RGB, KML colors are `aabbggrr`
+fn style(id: &str, [r, g, b]: [u8; 3], width: f64) -> Element {
+    let mut line_style = kml_element("LineStyle");
+    line_style.append_child(text_element(
+        "color",
+        format!("ff{:02x}{:02x}{:02x}", b, g, r),
+    ));
+    line_style.append_child(text_element("width", width.to_string()));
+
+    let mut style = Element::builder("Style", Namespace::Kml22.uri())
+        .attr("id", id)
+        .build();
+    style.append_child(line_style);
+    style
+}
+
+fn line_placemark(level: Option<usize>, points: &[(f64, f64)]) -> Element {
+    let mut line_string = kml_element("LineString");
+    line_string.append_child(text_element("tessellate", "1"));
+    line_string.append_child(text_element("coordinates", coordinates(points)));
+
+    let mut placemark = kml_element("Placemark");
+    placemark.append_child(text_element(
+        "styleUrl",
+        match level {
+            Some(level) => format!("#level{}", level),
+            None => "#missing".to_string(),
+        },
+    ));
+    placemark.append_child(line_string);
+    placemark
+}
+
+impl KmlPlacemark {
+    fn to_element(&self) -> Element {
+        let mut point = kml_element("Point");
+        point.append_child(text_element(
+            "coordinates",
+            coordinates(&[(self.latitude, self.longitude)]),
+        ));
+
+        let mut placemark = kml_element("Placemark");
+        placemark.append_child(text_element("name", self.name.as_str()));
+        if let Some(description) = &self.description {
+            placemark.append_child(text_element("description", description.as_str()));
+        }
+        placemark.append_child(point);
+        placemark
+    }
+}
+
+impl Trackpoint {
+    /// Create a KML 2.2 document with the track colored by the values of `field`
+    ///
+    /// Each segment between two consecutive points gets the color of the mean of its points' values on the
+    /// `gradient`, or of the one value available. The gradient is sampled at 16 levels, consecutive segments of the
+    /// same level are joined into one line. Segments without any value are gray. Points without latitude or
+    /// longitude are skipped.
+    ///
+    /// The `placemarks` are added after the track.
+    ///
+    /// # Examples
+    /// ```
+    /// # use tcx::*;
+    ///
+    /// let points = (0..3)
+    ///     .map(|k| Trackpoint {
+    ///         time: "2022-12-31T12:00:00Z".parse().unwrap(),
+    ///         latitude: Some(48.0 + k as f64 / 1000.0),
+    ///         longitude: Some(9.0),
+    ///         heartrate: Some(100.0 + 20.0 * k as f64),
+    ///         ..Default::default()
+    ///     })
+    ///     .collect::<Vec<_>>();
+    ///
+    /// let kml = Trackpoint::to_kml(&points, TrkPtField::Heartrate, &Gradient::default(), &[]);
+    ///
+    /// let mut lines = kml.get_child("Document", Namespace::Kml22.uri()).unwrap().children()
+    ///     .filter(|e| e.name() == "Placemark");
+    /// assert!(lines.next().unwrap().children().any(|e| e.text() == "#level4"));
+    /// assert!(lines.next().unwrap().children().any(|e| e.text() == "#level11"));
+    /// assert!(lines.next().is_none());
+    /// ```
+    pub fn to_kml<'a>(
+        points: impl IntoIterator<Item = &'a Trackpoint>,
+        field: TrkPtField,
+        gradient: &Gradient,
+        placemarks: &[KmlPlacemark],
+    ) -> Element {
+        let points = points
+            .into_iter()
+            .filter_map(|p| match (p.latitude, p.longitude) {
+                (Some(lat), Some(lon)) => Some(((lat, lon), p[&field])),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // value range of the gradient
+        let values = points
+            .iter()
+            .filter_map(|(_, v)| *v)
+            .filter(|v| v.is_finite());
+        let min = gradient
+            .min
+            .or_else(|| values.clone().reduce(f64::min))
+            .unwrap_or(0.0);
+        let max = gradient
+            .max
+            .or_else(|| values.reduce(f64::max))
+            .unwrap_or(0.0);
+
+        let mut document = kml_element("Document");
+        document.append_child(text_element("name", field.as_ref()));
+        for level in 0..LEVELS {
+            let color = gradient.color(level as f64 / (LEVELS - 1) as f64);
+            document.append_child(style(&format!("level{}", level), color, 4.0));
+        }
+        document.append_child(style("missing", [0x80, 0x80, 0x80], 4.0));
+
+        // join segments of the same level
+        let mut line: Vec<(f64, f64)> = Vec::new();
+        let mut line_level = None;
+        for (m, n) in points.iter().zip(points.iter().skip(1)) {
+            let value = match (m.1, n.1) {
+                (Some(a), Some(b)) => Some((a + b) / 2.0),
+                (a, b) => a.or(b),
+            };
+            let level = value.filter(|v| v.is_finite()).map(|v| {
+                let t = if max > min {
+                    (v - min) / (max - min)
+                } else {
+                    0.5
+                };
+                (t.clamp(0.0, 1.0) * (LEVELS - 1) as f64).round() as usize
+            });
+
+            if level != line_level && !line.is_empty() {
+                document.append_child(line_placemark(line_level, &line));
+                line = vec![m.0];
+            } else if line.is_empty() {
+                line.push(m.0);
+            }
+            line.push(n.0);
+            line_level = level;
+        }
+        if !line.is_empty() {
+            document.append_child(line_placemark(line_level, &line));
+        }
+
+        for placemark in placemarks {
+            document.append_child(placemark.to_element());
+        }
+
+        let mut kml = kml_element("kml");
+        kml.append_child(document);
+        kml
+    }
+
+    /// Write a KML document including the XML declaration, see [`Trackpoint::to_kml`]
+    pub fn write_kml<'a, W: Write>(
+        points: impl IntoIterator<Item = &'a Trackpoint>,
+        field: TrkPtField,
+        gradient: &Gradient,
+        placemarks: &[KmlPlacemark],
+        writer: &mut W,
+    ) -> Result<(), TcxError> {
+        Trackpoint::to_kml(points, field, gradient, placemarks)
+            .write_to_decl(writer)
+            .map_err(|source| TcxError::Xml {
+                source,
+                position: None,
+            })
+    }
+
+    /// Write a KMZ archive, i.e., a ZIP archive containing the KML document as `doc.kml`, see [`Trackpoint::to_kml`]
+    pub fn write_kmz<'a, W: Write>(
+        points: impl IntoIterator<Item = &'a Trackpoint>,
+        field: TrkPtField,
+        gradient: &Gradient,
+        placemarks: &[KmlPlacemark],
+        writer: &mut W,
+    ) -> Result<(), TcxError> {
+        let mut kml = Vec::new();
+        Trackpoint::write_kml(points, field, gradient, placemarks, &mut kml)?;
+        Ok(writer.write_all(&zip(KMZ_ENTRY, &kml))?)
+    }
+}
+
+/// Compute the CRC-32 used by ZIP archives
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| match crc & 1 {
+            1 => (crc >> 1) ^ 0xEDB8_8320,
+            _ => crc >> 1,
+        })
+    })
+}
+
+/// Create a ZIP archive containing `data` uncompressed as file `name`
+fn zip(name: &str, data: &[u8]) -> Vec<u8> {
+    let crc = crc32(data);
+    let size = data.len() as u32;
+
+    // version, flags, method (stored), time, date (1980-01-01), CRC, compressed and uncompressed size, name length
+    let mut common = Vec::new();
+    common.extend(20u16.to_le_bytes());
+    common.extend([0, 0, 0, 0, 0, 0, 0x21, 0]);
+    common.extend(crc.to_le_bytes());
+    common.extend(size.to_le_bytes());
+    common.extend(size.to_le_bytes());
+    common.extend((name.len() as u16).to_le_bytes());
+
+    let mut archive = Vec::new();
+    archive.extend(0x0403_4b50u32.to_le_bytes());
+    archive.extend(&common);
+    archive.extend([0, 0]);
+    archive.extend(name.as_bytes());
+    archive.extend(data);
+
+    let directory = archive.len() as u32;
+    archive.extend(0x0201_4b50u32.to_le_bytes());
+    archive.extend(20u16.to_le_bytes());
+    archive.extend(&common);
+    // extra and comment length, disk, internal and external attributes, offset of local header
+    archive.extend([0; 12]);
+    archive.extend(0u32.to_le_bytes());
+    archive.extend(name.as_bytes());
+    let directory_size = archive.len() as u32 - directory;
+
+    // end of central directory with one entry
+    archive.extend(0x0605_4b50u32.to_le_bytes());
+    archive.extend([0, 0, 0, 0, 1, 0, 1, 0]);
+    archive.extend(directory_size.to_le_bytes());
+    archive.extend(directory.to_le_bytes());
+    archive.extend([0, 0]);
+
+    archive
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gradient() {
+        let gradient: Gradient = "#000000, 0000ff,ff0000".parse().unwrap();
+        assert_eq!(gradient.to_string(), "000000,0000ff,ff0000");
+        assert_eq!(gradient.color(0.25), [0x00, 0x00, 0x80]);
+        assert_eq!(gradient.color(0.75), [0x80, 0x00, 0x80]);
+        assert_eq!(gradient.color(1.0), [0xFF, 0x00, 0x00]);
+        assert_eq!(gradient.color(-1.0), [0x00, 0x00, 0x00]);
+
+        assert!("00ff0".parse::<Gradient>().is_err());
+        assert!("00ff00,".parse::<Gradient>().is_err());
+        let single: Gradient = "00ff00".parse().unwrap();
+        assert_eq!(single.color(0.7), [0x00, 0xFF, 0x00]);
+    }
+
+    #[test]
+    fn test_to_kml() {
+        let points = [
+            (Some(48.0), Some(100.0)),
+            (Some(48.1), Some(100.0)),
+            (None, Some(150.0)),
+            (Some(48.2), Some(100.0)),
+            (Some(48.3), None),
+            (Some(48.4), None),
+            (Some(48.5), Some(200.0)),
+        ]
+        .iter()
+        .map(|(latitude, power)| Trackpoint {
+            latitude: *latitude,
+            longitude: Some(9.0),
+            power: *power,
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+        let placemark = KmlPlacemark {
+            name: "Window 1".to_string(),
+            description: Some("1000 m".to_string()),
+            latitude: 48.25,
+            longitude: 9.0,
+        };
+
+        let gradient = Gradient {
+            min: Some(100.0),
+            ..Default::default()
+        };
+        let kml = Trackpoint::to_kml(&points, TrkPtField::Power, &gradient, &[placemark]);
+
+        let mut xml = Vec::new();
+        kml.write_to(&mut xml).unwrap();
+        let xml = String::from_utf8(xml).unwrap();
+        assert!(xml.contains(
+            r#"<Style id="level0"><LineStyle><color>ff00ff00</color><width>4</width></LineStyle></Style>"#
+        ));
+        assert!(xml.contains(
+            r#"<Style id="level15"><LineStyle><color>ff0000ff</color><width>4</width></LineStyle></Style>"#
+        ));
+        let lines = xml
+            .split("<Placemark>")
+            .skip(1)
+            .map(|p| p.split("</Placemark>").next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                "<styleUrl>#level0</styleUrl><LineString><tessellate>1</tessellate><coordinates>9,48 9,48.1 9,48.2 9,48.3</coordinates></LineString>",
+                "<styleUrl>#missing</styleUrl><LineString><tessellate>1</tessellate><coordinates>9,48.3 9,48.4</coordinates></LineString>",
+                "<styleUrl>#level15</styleUrl><LineString><tessellate>1</tessellate><coordinates>9,48.4 9,48.5</coordinates></LineString>",
+                "<name>Window 1</name><description>1000 m</description><Point><coordinates>9,48.25</coordinates></Point>",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_kmz() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+
+        let mut kmz = Vec::new();
+        Trackpoint::write_kmz(
+            &[],
+            TrkPtField::Heartrate,
+            &Gradient::default(),
+            &[],
+            &mut kmz,
+        )
+        .unwrap();
+
+        assert_eq!(&kmz[..4], b"PK\x03\x04");
+        assert_eq!(&kmz[30..37], KMZ_ENTRY.as_bytes());
+        assert!(
+            String::from_utf8_lossy(&kmz).contains("<kml xmlns='http://www.opengis.net/kml/2.2'>")
+        );
+        let end = kmz.len() - 22;
+        assert_eq!(&kmz[end..end + 4], b"PK\x05\x06");
+        let directory = u32::from_le_bytes(kmz[end + 16..end + 20].try_into().unwrap()) as usize;
+        assert_eq!(&kmz[directory..directory + 4], b"PK\x01\x02");
+    }
+}
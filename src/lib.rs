@@ -1,15 +1,53 @@
 use std::{
-    error::Error,
+    collections::BTreeMap,
     ops::{Index, IndexMut},
     str::FromStr,
 };
 
 use chrono::{DateTime, Utc};
-use minidom::{Element, NSChoice};
-use tcx_macro_derive::{AsRefStr, ConstArray};
+use minidom::Element;
+use tcx_macro_derive::{AsRefStr, ConstArray, EnumFromStr};
 
-/// relevant XML tags of TCX files
+mod context;
+mod csv;
+mod edit;
+mod error;
+mod fit;
+mod folder;
+mod geojson;
+mod gpx;
+mod kml;
+mod namespace;
+mod reader;
+#[cfg(feature = "arrow")]
+mod record_batch;
+#[cfg(feature = "serde")]
+mod serde_nan;
+mod time;
+mod validate;
+mod workout;
+mod writer;
+
+pub use context::*;
+pub use error::*;
+pub use folder::*;
+pub use geojson::*;
+pub use kml::*;
+pub use namespace::*;
+pub use reader::*;
+#[cfg(feature = "arrow")]
+pub use record_batch::*;
+pub use time::*;
+pub use validate::*;
+pub use workout::*;
+
+/// relevant XML tags of TCX and GPX files
+///
+/// Variants are named like the elements' local names. Lower case local names, e.g., of GPX and Garmin's track point
+/// extension, are given by an `as_ref` attribute, the variants are prefixed by their vocabulary where names would
+/// clash, e.g., [`Tag::TpxV2Speed`] for `speed` next to [`Tag::Speed`].
 #[derive(Clone, Copy, PartialEq, Eq, Debug, AsRefStr)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Tag {
     Time,
     Position,
@@ -32,10 +70,124 @@ pub enum Tag {
     Lap,
     Track,
     Trackpoint,
+    TrainingCenterDatabase,
+    StartTime,
+    TotalTimeSeconds,
+    MaximumSpeed,
+    Calories,
+    AverageHeartRateBpm,
+    MaximumHeartRateBpm,
+    Intensity,
+    TriggerMethod,
+    LX,
+    AvgSpeed,
+    AvgWatts,
+    MaxWatts,
+    Sport,
+    Id,
+    Notes,
+    Creator,
+    Name,
+    UnitId,
+    ProductID,
+    Version,
+    VersionMajor,
+    VersionMinor,
+    BuildMajor,
+    BuildMinor,
+    Author,
+    Build,
+    LangID,
+    PartNumber,
+    BeginPosition,
+    BeginAltitudeMeters,
+    EndPosition,
+    EndAltitudeMeters,
+    CoursePoint,
+    PointType,
+    Workouts,
+    Workout,
+    Step,
+    StepId,
+    Duration,
+    Seconds,
+    Meters,
+    HeartRate,
+    Target,
+    SpeedZone,
+    HeartRateZone,
+    Number,
+    ViewAs,
+    LowInMetersPerSecond,
+    HighInMetersPerSecond,
+    Low,
+    High,
+    Repetitions,
+    Child,
+    ScheduledOn,
+    Folders,
+    Folder,
+    History,
+    Running,
+    Biking,
+    Other,
+    MultiSport,
+    ActivityRef,
+    MultisportActivityRef,
+    WorkoutNameRef,
+    CourseFolder,
+    CourseNameRef,
+    MultiSportSession,
+    FirstSport,
+    NextSport,
+    Transition,
+    TrackPointExtension,
+    #[as_ref = "atemp"]
+    AirTemperature,
+    #[as_ref = "wtemp"]
+    WaterTemperature,
+    #[as_ref = "depth"]
+    Depth,
+    #[as_ref = "hr"]
+    Hr,
+    #[as_ref = "cad"]
+    Cad,
+    #[as_ref = "speed"]
+    TpxV2Speed,
+    #[as_ref = "course"]
+    TpxV2Course,
+    #[as_ref = "bearing"]
+    Bearing,
+    #[as_ref = "gpx"]
+    Gpx,
+    #[as_ref = "trk"]
+    Trk,
+    #[as_ref = "trkseg"]
+    Trkseg,
+    #[as_ref = "trkpt"]
+    Trkpt,
+    #[as_ref = "ele"]
+    Ele,
+    #[as_ref = "time"]
+    GpxTime,
+    #[as_ref = "extensions"]
+    GpxExtensions,
+    #[as_ref = "lat"]
+    Lat,
+    #[as_ref = "lon"]
+    Lon,
+    PowerInWatts,
+    #[as_ref = "cadence"]
+    GpxdataCadence,
+    #[as_ref = "temp"]
+    Temp,
+    #[as_ref = "power"]
+    GpxPower,
 }
 
 /// Fields of the [`Trackpoint`] enum
 #[derive(Clone, Copy, PartialEq, Eq, Debug, AsRefStr, ConstArray)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TrkPtField {
     /// Represent [`Trackpoint::latitude`]
     Latitude,
@@ -53,6 +205,16 @@ pub enum TrkPtField {
     Speed,
     /// Represent [`Trackpoint::power`]
     Power,
+    /// Represent [`Trackpoint::air_temperature`]
+    AirTemperature,
+    /// Represent [`Trackpoint::water_temperature`]
+    WaterTemperature,
+    /// Represent [`Trackpoint::depth`]
+    Depth,
+    /// Represent [`Trackpoint::course`]
+    Course,
+    /// Represent [`Trackpoint::bearing`]
+    Bearing,
 }
 
 impl TrkPtField {
@@ -67,19 +229,270 @@ impl TrkPtField {
             TrkPtField::Longitude => &[&[Tag::Position, Tag::LongitudeDegrees]],
             TrkPtField::Altitude => &[&[Tag::AltitudeMeters]],
             TrkPtField::Distance => &[&[Tag::DistanceMeters]],
-            TrkPtField::Heartrate => &[&[Tag::HeartRateBpm, Tag::Value]],
+            TrkPtField::Heartrate => &[
+                &[Tag::HeartRateBpm, Tag::Value],
+                &[Tag::Extensions, Tag::TrackPointExtension, Tag::Hr],
+            ],
             TrkPtField::Cadence => &[
                 &[Tag::Cadence],
                 &[Tag::Extensions, Tag::TPX, Tag::RunCadence],
+                &[Tag::Extensions, Tag::TrackPointExtension, Tag::Cad],
+            ],
+            TrkPtField::Speed => &[
+                &[Tag::Extensions, Tag::TPX, Tag::Speed],
+                &[Tag::Extensions, Tag::TrackPointExtension, Tag::TpxV2Speed],
             ],
-            TrkPtField::Speed => &[&[Tag::Extensions, Tag::TPX, Tag::Speed]],
             TrkPtField::Power => &[&[Tag::Extensions, Tag::TPX, Tag::Watts]],
+            TrkPtField::AirTemperature => &[&[
+                Tag::Extensions,
+                Tag::TrackPointExtension,
+                Tag::AirTemperature,
+            ]],
+            TrkPtField::WaterTemperature => &[&[
+                Tag::Extensions,
+                Tag::TrackPointExtension,
+                Tag::WaterTemperature,
+            ]],
+            TrkPtField::Depth => &[&[Tag::Extensions, Tag::TrackPointExtension, Tag::Depth]],
+            TrkPtField::Course => &[&[Tag::Extensions, Tag::TrackPointExtension, Tag::TpxV2Course]],
+            TrkPtField::Bearing => &[&[Tag::Extensions, Tag::TrackPointExtension, Tag::Bearing]],
         }
     }
 }
 
+/// Numeric summary fields of the [`Lap`] struct
+#[derive(Clone, Copy, PartialEq, Eq, Debug, AsRefStr, ConstArray)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LapField {
+    /// Represent [`Lap::total_time`]
+    TotalTime,
+    /// Represent [`Lap::distance`]
+    Distance,
+    /// Represent [`Lap::maximum_speed`]
+    MaximumSpeed,
+    /// Represent [`Lap::calories`]
+    Calories,
+    /// Represent [`Lap::average_heartrate`]
+    AverageHeartrate,
+    /// Represent [`Lap::maximum_heartrate`]
+    MaximumHeartrate,
+    /// Represent [`Lap::cadence`]
+    Cadence,
+    /// Represent [`Lap::average_speed`]
+    AverageSpeed,
+    /// Represent [`Lap::average_power`]
+    AveragePower,
+    /// Represent [`Lap::maximum_power`]
+    MaximumPower,
+}
+
+impl LapField {
+    /// Get tags for field as slice of slices of [`Tag`]s.
+    ///
+    /// If there is more than one possibility, each contained slice of [`Tag`]s represents one option.
+    ///
+    /// Use with [`TcxElement::child_value`]
+    pub fn get_tags(&self) -> &[&[Tag]] {
+        match self {
+            LapField::TotalTime => &[&[Tag::TotalTimeSeconds]],
+            LapField::Distance => &[&[Tag::DistanceMeters]],
+            LapField::MaximumSpeed => &[&[Tag::MaximumSpeed]],
+            LapField::Calories => &[&[Tag::Calories]],
+            LapField::AverageHeartrate => &[&[Tag::AverageHeartRateBpm, Tag::Value]],
+            LapField::MaximumHeartrate => &[&[Tag::MaximumHeartRateBpm, Tag::Value]],
+            LapField::Cadence => &[&[Tag::Cadence]],
+            LapField::AverageSpeed => &[&[Tag::Extensions, Tag::LX, Tag::AvgSpeed]],
+            LapField::AveragePower => &[&[Tag::Extensions, Tag::LX, Tag::AvgWatts]],
+            LapField::MaximumPower => &[&[Tag::Extensions, Tag::LX, Tag::MaxWatts]],
+        }
+    }
+}
+
+/// Intensity of a [`Lap`] ([`<Intensity>`][Tag::Intensity])
+#[derive(Clone, Copy, PartialEq, Eq, Debug, AsRefStr, EnumFromStr)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Intensity {
+    Active,
+    Resting,
+}
+
+/// What caused a [`Lap`] to end ([`<TriggerMethod>`][Tag::TriggerMethod])
+#[derive(Clone, Copy, PartialEq, Eq, Debug, AsRefStr, EnumFromStr)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TriggerMethod {
+    Manual,
+    Distance,
+    Location,
+    Time,
+    HeartRate,
+}
+
+/// Sport of an [`Activity`] (attribute [`Sport`][Tag::Sport])
+#[derive(Clone, Copy, PartialEq, Eq, Debug, AsRefStr, EnumFromStr)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Sport {
+    Running,
+    Biking,
+    Other,
+}
+
+/// a version number ([`<Version>`][Tag::Version])
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Version {
+    /// Major version ([`<VersionMajor>`][Tag::VersionMajor])
+    pub version_major: u16,
+    /// Minor version ([`<VersionMinor>`][Tag::VersionMinor])
+    pub version_minor: u16,
+    /// Major build number ([`<BuildMajor>`][Tag::BuildMajor])
+    pub build_major: Option<u16>,
+    /// Minor build number ([`<BuildMinor>`][Tag::BuildMinor])
+    pub build_minor: Option<u16>,
+}
+
+/// the device that recorded an [`Activity`] ([`<Creator>`][Tag::Creator])
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Device {
+    /// Name of the device ([`<Name>`][Tag::Name])
+    pub name: String,
+    /// Unit ID, i.e., the device's serial number ([`<UnitId>`][Tag::UnitId])
+    pub unit_id: Option<u32>,
+    /// Product ID ([`<ProductID>`][Tag::ProductID])
+    pub product_id: Option<u16>,
+    /// Firmware version ([`<Version>`][Tag::Version])
+    pub version: Option<Version>,
+}
+
+/// the application that wrote a TCX file ([`<Author>`][Tag::Author])
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Application {
+    /// Name of the application ([`<Name>`][Tag::Name])
+    pub name: String,
+    /// Version of the application's build ([`<Build>`][Tag::Build]&#173;[`<Version>`][Tag::Version])
+    pub build: Option<Version>,
+    /// Language ID ([`<LangID>`][Tag::LangID])
+    pub lang_id: Option<String>,
+    /// Part number ([`<PartNumber>`][Tag::PartNumber])
+    pub part_number: Option<String>,
+}
+
+/// Type of a [`CoursePoint`] ([`<PointType>`][Tag::PointType])
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PointType {
+    Generic,
+    Summit,
+    Valley,
+    Water,
+    Food,
+    Danger,
+    Left,
+    Right,
+    Straight,
+    FirstAid,
+    FourthCategory,
+    ThirdCategory,
+    SecondCategory,
+    FirstCategory,
+    HorsCategory,
+    Sprint,
+}
+
+impl AsRef<str> for PointType {
+    /// Get the value as used in TCX files (which is not necessarily the variant's name)
+    fn as_ref(&self) -> &str {
+        match self {
+            PointType::Generic => "Generic",
+            PointType::Summit => "Summit",
+            PointType::Valley => "Valley",
+            PointType::Water => "Water",
+            PointType::Food => "Food",
+            PointType::Danger => "Danger",
+            PointType::Left => "Left",
+            PointType::Right => "Right",
+            PointType::Straight => "Straight",
+            PointType::FirstAid => "First Aid",
+            PointType::FourthCategory => "4th Category",
+            PointType::ThirdCategory => "3rd Category",
+            PointType::SecondCategory => "2nd Category",
+            PointType::FirstCategory => "1st Category",
+            PointType::HorsCategory => "Hors Category",
+            PointType::Sprint => "Sprint",
+        }
+    }
+}
+
+impl FromStr for PointType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Generic" => Ok(PointType::Generic),
+            "Summit" => Ok(PointType::Summit),
+            "Valley" => Ok(PointType::Valley),
+            "Water" => Ok(PointType::Water),
+            "Food" => Ok(PointType::Food),
+            "Danger" => Ok(PointType::Danger),
+            "Left" => Ok(PointType::Left),
+            "Right" => Ok(PointType::Right),
+            "Straight" => Ok(PointType::Straight),
+            "First Aid" => Ok(PointType::FirstAid),
+            "4th Category" => Ok(PointType::FourthCategory),
+            "3rd Category" => Ok(PointType::ThirdCategory),
+            "2nd Category" => Ok(PointType::SecondCategory),
+            "1st Category" => Ok(PointType::FirstCategory),
+            "Hors Category" => Ok(PointType::HorsCategory),
+            "Sprint" => Ok(PointType::Sprint),
+            _ => Err(format!("'{}' is not a valid PointType", s)),
+        }
+    }
+}
+
+/// a geographic position ([`<Position>`][Tag::Position])
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    /// Latitude ([`<LatitudeDegrees>`][Tag::LatitudeDegrees])
+    #[cfg_attr(feature = "serde", serde(with = "serde_nan::float"))]
+    pub latitude: f64,
+    /// Longitude ([`<LongitudeDegrees>`][Tag::LongitudeDegrees])
+    #[cfg_attr(feature = "serde", serde(with = "serde_nan::float"))]
+    pub longitude: f64,
+}
+
 /// a track point
+///
+/// With the `serde` feature, track points and the other types of the TCX hierarchy implement `Serialize` and
+/// `Deserialize`. Times are RFC 3339 strings, missing values are `null` and so are NaN values in JSON. NaN fields are
+/// read back as `None`, NaN [`extensions`][Trackpoint::extensions] and positions as NaN.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "serde")]
+/// # {
+/// # use tcx::*;
+///
+/// let point = Trackpoint {
+///     time: "2022-12-31T12:00:00Z".parse().unwrap(),
+///     heartrate: Some(120.0),
+///     power: Some(f64::NAN),
+///     ..Default::default()
+/// };
+///
+/// let json = serde_json::to_string(&point).unwrap();
+/// assert!(json.starts_with(r#"{"time":"2022-12-31T12:00:00Z","latitude":null,"#));
+/// assert!(json.contains(r#""heartrate":120.0,"#));
+/// assert!(json.contains(r#""power":null,"#));
+///
+/// let parsed: Trackpoint = serde_json::from_str(&json).unwrap();
+/// assert_eq!(parsed.heartrate, Some(120.0));
+/// assert_eq!(parsed.power, None);
+/// # }
+/// ```
 #[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Trackpoint {
     /// Timestamp when the trackpoint was recorded ([`<Time>`][Tag::Time])
     pub time: DateTime<Utc>,
@@ -91,14 +504,37 @@ pub struct Trackpoint {
     pub altitude: Option<f64>,
     /// Distance travelled in track until this track point ([`<DistanceMeters>`][Tag::DistanceMeters], see [`TrkPtField::Distance`])
     pub distance: Option<f64>,
-    /// Instantaneous heart rate ([`<HeartRateBpm>`][Tag::HeartRateBpm]&#173;[`<Value>`][Tag::Value], see [`TrkPtField::Heartrate`])
+    /// Instantaneous heart rate ([`<HeartRateBpm>`][Tag::HeartRateBpm]&#173;[`<Value>`][Tag::Value] or [`<Extensions>`][Tag::Extensions]&#173;[`<TrackPointExtension>`][Tag::TrackPointExtension]&#173;[`<hr>`][Tag::Hr], see [`TrkPtField::Heartrate`])
     pub heartrate: Option<f64>,
-    /// Instantaneous cadence ([`<Cadence>`][Tag::Cadence] or [`<Extensions>`][Tag::Extensions]&#173;[`<TPX>`][Tag::TPX]&#173;[`<RunCadence>`][Tag::RunCadence], see [`TrkPtField::Cadence`])
+    /// Instantaneous cadence ([`<Cadence>`][Tag::Cadence], [`<Extensions>`][Tag::Extensions]&#173;[`<TPX>`][Tag::TPX]&#173;[`<RunCadence>`][Tag::RunCadence] or [`<Extensions>`][Tag::Extensions]&#173;[`<TrackPointExtension>`][Tag::TrackPointExtension]&#173;[`<cad>`][Tag::Cad], see [`TrkPtField::Cadence`])
     pub cadence: Option<f64>,
-    /// Instantaneous speed ([`<Extensions>`][Tag::Extensions]&#173;[`<TPX>`][Tag::TPX]&#173;[`<Speed>`][Tag::Speed], see [`TrkPtField::Speed`])
+    /// Instantaneous speed ([`<Extensions>`][Tag::Extensions]&#173;[`<TPX>`][Tag::TPX]&#173;[`<Speed>`][Tag::Speed] or [`<Extensions>`][Tag::Extensions]&#173;[`<TrackPointExtension>`][Tag::TrackPointExtension]&#173;[`<speed>`][Tag::TpxV2Speed], see [`TrkPtField::Speed`])
     pub speed: Option<f64>,
     /// Instantaneous power ([`<Extensions>`][Tag::Extensions]&#173;[`<TPX>`][Tag::TPX]&#173;[`<Watts>`][Tag::Watts], see [`TrkPtField::Power`])
     pub power: Option<f64>,
+    /// Air temperature in °C ([`<Extensions>`][Tag::Extensions]&#173;[`<TrackPointExtension>`][Tag::TrackPointExtension]&#173;[`<atemp>`][Tag::AirTemperature], see [`TrkPtField::AirTemperature`])
+    pub air_temperature: Option<f64>,
+    /// Water temperature in °C ([`<Extensions>`][Tag::Extensions]&#173;[`<TrackPointExtension>`][Tag::TrackPointExtension]&#173;[`<wtemp>`][Tag::WaterTemperature], see [`TrkPtField::WaterTemperature`])
+    pub water_temperature: Option<f64>,
+    /// Depth in meters ([`<Extensions>`][Tag::Extensions]&#173;[`<TrackPointExtension>`][Tag::TrackPointExtension]&#173;[`<depth>`][Tag::Depth], see [`TrkPtField::Depth`])
+    pub depth: Option<f64>,
+    /// Course in degrees from true north ([`<Extensions>`][Tag::Extensions]&#173;[`<TrackPointExtension>`][Tag::TrackPointExtension]&#173;[`<course>`][Tag::TpxV2Course], see [`TrkPtField::Course`])
+    pub course: Option<f64>,
+    /// Bearing to the next point in degrees from true north ([`<Extensions>`][Tag::Extensions]&#173;[`<TrackPointExtension>`][Tag::TrackPointExtension]&#173;[`<bearing>`][Tag::Bearing], see [`TrkPtField::Bearing`])
+    pub bearing: Option<f64>,
+    /// Numeric values in [`<Extensions>`][Tag::Extensions] not represented by any [`TrkPtField`]
+    ///
+    /// Keys are the paths of the values' elements relative to [`<Extensions>`][Tag::Extensions], elements are given in
+    /// Clark notation `{namespace}name` separated by `/`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "BTreeMap::is_empty",
+            with = "serde_nan::map"
+        )
+    )]
+    pub extensions: BTreeMap<String, f64>,
 }
 
 impl Index<&TrkPtField> for Trackpoint {
@@ -114,6 +550,11 @@ impl Index<&TrkPtField> for Trackpoint {
             TrkPtField::Cadence => &self.cadence,
             TrkPtField::Speed => &self.speed,
             TrkPtField::Power => &self.power,
+            TrkPtField::AirTemperature => &self.air_temperature,
+            TrkPtField::WaterTemperature => &self.water_temperature,
+            TrkPtField::Depth => &self.depth,
+            TrkPtField::Course => &self.course,
+            TrkPtField::Bearing => &self.bearing,
         }
     }
 }
@@ -129,23 +570,116 @@ impl IndexMut<&TrkPtField> for Trackpoint {
             TrkPtField::Cadence => &mut self.cadence,
             TrkPtField::Speed => &mut self.speed,
             TrkPtField::Power => &mut self.power,
+            TrkPtField::AirTemperature => &mut self.air_temperature,
+            TrkPtField::WaterTemperature => &mut self.water_temperature,
+            TrkPtField::Depth => &mut self.depth,
+            TrkPtField::Course => &mut self.course,
+            TrkPtField::Bearing => &mut self.bearing,
         }
     }
 }
 
+/// Access to TCX elements by [`Tag`]s
+///
+/// Matching is strict about namespaces, i.e., an element only matches a tag if it has the tag's name and is in one of
+/// the tag's [`namespaces`][Tag::namespaces]. The `_matching` variants take a [`NamespaceMatching`] to fall back to
+/// matching by name in any namespace.
 pub trait TcxElement {
-    /// Check whether a given `TcxElement` is a `tag` ignoring name spaces
+    /// Check whether a given `TcxElement` is a `tag` including its namespace
     fn is_tag(&self, tag: Tag) -> bool;
 
+    /// Check whether a given `TcxElement` has the `tag`'s name ignoring name spaces
+    fn has_name(&self, tag: Tag) -> bool;
+
+    /// Get child element
+    ///
+    /// The function will descend the hiearchy given by the `tags` slice.
+    fn child(&self, tags: &[Tag]) -> Option<&Self>;
+
+    /// Get child element using the given [`NamespaceMatching`], see [`TcxElement::child`]
+    fn child_matching(&self, tags: &[Tag], matching: NamespaceMatching) -> Option<&Self>;
+
+    /// Iterate over child elements which are a `tag`
+    fn children_tagged(&self, tag: Tag) -> impl Iterator<Item = &Self>;
+
+    /// Iterate over child elements which are a `tag` using the given [`NamespaceMatching`]
+    ///
+    /// With [`NamespaceMatching::Permissive`], all children with the `tag`'s name are returned if no child matches
+    /// strictly.
+    fn children_matching(
+        &self,
+        tag: Tag,
+        matching: NamespaceMatching,
+    ) -> impl Iterator<Item = &Self>;
+
     /// Get text of child paresd into `T`
     ///
     /// The function will descend the hiearchy given by the `tags` slice.
-    fn child_value<T: FromStr>(&self, tags: &[Tag]) -> Result<Option<T>, <T as FromStr>::Err>;
+    fn child_value<T: FromStr>(&self, tags: &[Tag]) -> Result<Option<T>, TcxError>
+    where
+        T::Err: IntoTcxError;
+
+    /// Get text of child parsed into `T` using the given [`NamespaceMatching`], see [`TcxElement::child_value`]
+    fn child_value_matching<T: FromStr>(
+        &self,
+        tags: &[Tag],
+        matching: NamespaceMatching,
+    ) -> Result<Option<T>, TcxError>
+    where
+        T::Err: IntoTcxError;
+
+    /// Get value of attribute parsed into `T`
+    fn attr_value<T: FromStr>(&self, tag: Tag) -> Result<Option<T>, TcxError>
+    where
+        T::Err: IntoTcxError;
 }
 
 impl TcxElement for Element {
     fn is_tag(&self, tag: Tag) -> bool {
-        self.is(tag, NSChoice::Any)
+        self.has_name(tag)
+            && Namespace::from_uri(&self.ns()).is_some_and(|ns| tag.namespaces().contains(&ns))
+    }
+
+    fn has_name(&self, tag: Tag) -> bool {
+        self.name() == tag.as_ref()
+    }
+
+    fn child(&self, tags: &[Tag]) -> Option<&Self> {
+        self.child_matching(tags, NamespaceMatching::Strict)
+    }
+
+    fn child_matching(&self, tags: &[Tag], matching: NamespaceMatching) -> Option<&Self> {
+        let mut e = Some(self);
+        for tag in tags {
+            e = e.and_then(|e| e.children_matching(*tag, matching).next());
+        }
+        e
+    }
+
+    fn children_tagged(&self, tag: Tag) -> impl Iterator<Item = &Self> {
+        self.children_matching(tag, NamespaceMatching::Strict)
+    }
+
+    fn children_matching(
+        &self,
+        tag: Tag,
+        matching: NamespaceMatching,
+    ) -> impl Iterator<Item = &Self> {
+        let strict = match matching {
+            NamespaceMatching::Strict => true,
+            NamespaceMatching::Permissive => self.children().any(|c| c.is_tag(tag)),
+        };
+        self.children().filter(move |c| match strict {
+            true => c.is_tag(tag),
+            false => c.has_name(tag),
+        })
+    }
+
+    fn child_value<T: FromStr>(&self, tags: &[Tag]) -> Result<Option<T>, TcxError>
+    where
+        T::Err: IntoTcxError,
+    {
+        self.child_value_matching(tags, NamespaceMatching::Strict)
     }
 
     /// # Examples
@@ -153,58 +687,926 @@ impl TcxElement for Element {
     /// # use tcx::*;
     /// use minidom::Element;
     ///
-    /// let doc = r#"<Root xmlns="arbitrary">
+    /// let doc = r#"<Root xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
     ///   <Extensions>
-    ///     <TPX>
+    ///     <TPX xmlns="urn:vendor">
     ///       <Speed>42.0</Speed>
     ///     </TPX>
     ///   </Extensions>
     /// </Root>"#;
+    /// let root = doc.parse::<Element>().unwrap();
+    /// let tags = TrkPtField::Speed.get_tags()[0];
     ///
-    /// let val: f64 = doc.parse::<Element>().unwrap()
-    ///     .child_value(TrkPtField::Speed.get_tags()[0])
-    ///     .expect("Parse error").expect("Missing node");
-    /// assert_eq!(val, 42.0);
+    /// let val: Option<f64> = root.child_value_matching(tags, NamespaceMatching::Strict).unwrap();
+    /// assert_eq!(val, None);
+    /// let val: Option<f64> = root.child_value_matching(tags, NamespaceMatching::Permissive).unwrap();
+    /// assert_eq!(val, Some(42.0));
     /// ```
-    fn child_value<T: FromStr>(&self, tags: &[Tag]) -> Result<Option<T>, <T as FromStr>::Err> {
-        let mut e = Some(self);
-        for tag in tags {
-            e = e.map(|e| e.get_child(*tag, NSChoice::Any)).flatten();
+    fn child_value_matching<T: FromStr>(
+        &self,
+        tags: &[Tag],
+        matching: NamespaceMatching,
+    ) -> Result<Option<T>, TcxError>
+    where
+        T::Err: IntoTcxError,
+    {
+        self.child_matching(tags, matching)
+            .map(|e| {
+                let text = e.text();
+                text.parse().map_err(|err: T::Err| {
+                    err.into_tcx_error(
+                        ErrorContext {
+                            tags: tags.to_vec(),
+                            ..Default::default()
+                        },
+                        &text,
+                    )
+                })
+            })
+            .transpose()
+    }
+
+    fn attr_value<T: FromStr>(&self, tag: Tag) -> Result<Option<T>, TcxError>
+    where
+        T::Err: IntoTcxError,
+    {
+        self.attr(tag.as_ref())
+            .map(|text| {
+                text.parse().map_err(|err: T::Err| {
+                    err.into_tcx_error(
+                        ErrorContext {
+                            tags: vec![tag],
+                            ..Default::default()
+                        },
+                        text,
+                    )
+                })
+            })
+            .transpose()
+    }
+}
+
+/// Parse all `elements` using `parse`
+///
+/// Track point indices of errors and warnings are shifted by `offset`, which is then advanced by the number of
+/// [`<Trackpoint>`][Tag::Trackpoint]s in each element. This way, indices count from the first track point of the
+/// parent in the order of the `trackpoints` iterators.
+fn parse_all<'a, T>(
+    elements: impl Iterator<Item = &'a Element>,
+    parse: fn(&Element, &mut ParseContext) -> Result<T, TcxError>,
+    context: &mut ParseContext,
+    offset: &mut usize,
+) -> Result<Vec<T>, TcxError> {
+    elements
+        .map(|e| {
+            let shift = *offset;
+            let since = context.warnings.len();
+            let result = parse(e, context).map_err(|err| err.shift_trackpoint(shift))?;
+            context.update_warnings(since, |err| err.shift_trackpoint(shift));
+            *offset += count_trackpoints(e);
+            Ok(result)
+        })
+        .collect()
+}
+
+/// Count the [`<Trackpoint>`][Tag::Trackpoint]s nested in `element`
+fn count_trackpoints(element: &Element) -> usize {
+    if element.has_name(Tag::Trackpoint) {
+        1
+    } else {
+        element.children().map(count_trackpoints).sum()
+    }
+}
+
+/// a track, i.e., a sequence of [`Trackpoint`]s ([`<Track>`][Tag::Track])
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Track {
+    /// The track's points ([`<Trackpoint>`][Tag::Trackpoint])
+    pub trackpoints: Vec<Trackpoint>,
+}
+
+/// a lap of an activity ([`<Lap>`][Tag::Lap])
+///
+/// The summary values are the ones reported by the recording device, they are not calculated from the track points.
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Lap {
+    /// Timestamp when the lap was started (attribute [`StartTime`][Tag::StartTime])
+    pub start_time: Option<DateTime<Utc>>,
+    /// Total duration of the lap in seconds ([`<TotalTimeSeconds>`][Tag::TotalTimeSeconds], see [`LapField::TotalTime`])
+    pub total_time: Option<f64>,
+    /// Distance travelled in the lap ([`<DistanceMeters>`][Tag::DistanceMeters], see [`LapField::Distance`])
+    pub distance: Option<f64>,
+    /// Maximum speed ([`<MaximumSpeed>`][Tag::MaximumSpeed], see [`LapField::MaximumSpeed`])
+    pub maximum_speed: Option<f64>,
+    /// Calories burnt ([`<Calories>`][Tag::Calories], see [`LapField::Calories`])
+    pub calories: Option<f64>,
+    /// Average heart rate ([`<AverageHeartRateBpm>`][Tag::AverageHeartRateBpm]&#173;[`<Value>`][Tag::Value], see [`LapField::AverageHeartrate`])
+    pub average_heartrate: Option<f64>,
+    /// Maximum heart rate ([`<MaximumHeartRateBpm>`][Tag::MaximumHeartRateBpm]&#173;[`<Value>`][Tag::Value], see [`LapField::MaximumHeartrate`])
+    pub maximum_heartrate: Option<f64>,
+    /// Average cadence ([`<Cadence>`][Tag::Cadence], see [`LapField::Cadence`])
+    pub cadence: Option<f64>,
+    /// Average speed ([`<Extensions>`][Tag::Extensions]&#173;[`<LX>`][Tag::LX]&#173;[`<AvgSpeed>`][Tag::AvgSpeed], see [`LapField::AverageSpeed`])
+    pub average_speed: Option<f64>,
+    /// Average power ([`<Extensions>`][Tag::Extensions]&#173;[`<LX>`][Tag::LX]&#173;[`<AvgWatts>`][Tag::AvgWatts], see [`LapField::AveragePower`])
+    pub average_power: Option<f64>,
+    /// Maximum power ([`<Extensions>`][Tag::Extensions]&#173;[`<LX>`][Tag::LX]&#173;[`<MaxWatts>`][Tag::MaxWatts], see [`LapField::MaximumPower`])
+    pub maximum_power: Option<f64>,
+    /// Intensity ([`<Intensity>`][Tag::Intensity])
+    pub intensity: Option<Intensity>,
+    /// What caused the lap to end ([`<TriggerMethod>`][Tag::TriggerMethod])
+    pub trigger_method: Option<TriggerMethod>,
+    /// The lap's tracks ([`<Track>`][Tag::Track])
+    pub tracks: Vec<Track>,
+}
+
+impl Index<&LapField> for Lap {
+    type Output = Option<f64>;
+
+    fn index(&self, index: &LapField) -> &Self::Output {
+        match index {
+            LapField::TotalTime => &self.total_time,
+            LapField::Distance => &self.distance,
+            LapField::MaximumSpeed => &self.maximum_speed,
+            LapField::Calories => &self.calories,
+            LapField::AverageHeartrate => &self.average_heartrate,
+            LapField::MaximumHeartrate => &self.maximum_heartrate,
+            LapField::Cadence => &self.cadence,
+            LapField::AverageSpeed => &self.average_speed,
+            LapField::AveragePower => &self.average_power,
+            LapField::MaximumPower => &self.maximum_power,
         }
-        e.map(|e| e.text().parse()).transpose()
     }
 }
 
-impl Trackpoint {
-    /// Read track points from TCX element flattening any structure
+impl IndexMut<&LapField> for Lap {
+    fn index_mut(&mut self, index: &LapField) -> &mut Self::Output {
+        match index {
+            LapField::TotalTime => &mut self.total_time,
+            LapField::Distance => &mut self.distance,
+            LapField::MaximumSpeed => &mut self.maximum_speed,
+            LapField::Calories => &mut self.calories,
+            LapField::AverageHeartrate => &mut self.average_heartrate,
+            LapField::MaximumHeartrate => &mut self.maximum_heartrate,
+            LapField::Cadence => &mut self.cadence,
+            LapField::AverageSpeed => &mut self.average_speed,
+            LapField::AveragePower => &mut self.average_power,
+            LapField::MaximumPower => &mut self.maximum_power,
+        }
+    }
+}
+
+/// an activity ([`<Activity>`][Tag::Activity])
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Activity {
+    /// The activity's sport (attribute [`Sport`][Tag::Sport])
+    pub sport: Option<Sport>,
+    /// The activity's ID, which is the timestamp of its start ([`<Id>`][Tag::Id])
+    pub id: Option<DateTime<Utc>>,
+    /// Notes ([`<Notes>`][Tag::Notes])
+    pub notes: Option<String>,
+    /// The recording device ([`<Creator>`][Tag::Creator])
+    pub creator: Option<Device>,
+    /// The activity's laps ([`<Lap>`][Tag::Lap])
+    pub laps: Vec<Lap>,
+}
+
+/// a leg of a [`MultiSportSession`] after the first one ([`<NextSport>`][Tag::NextSport])
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NextSport {
+    /// The transition preceding the leg ([`<Transition>`][Tag::Transition])
+    pub transition: Option<Lap>,
+    /// The leg ([`<Activity>`][Tag::Activity])
+    pub activity: Activity,
+}
+
+/// a multi sport session, e.g., a triathlon ([`<MultiSportSession>`][Tag::MultiSportSession])
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MultiSportSession {
+    /// The session's ID, which is the timestamp of its start ([`<Id>`][Tag::Id])
+    pub id: Option<DateTime<Utc>>,
+    /// The first leg ([`<FirstSport>`][Tag::FirstSport]&#173;[`<Activity>`][Tag::Activity])
+    pub first_sport: Activity,
+    /// All further legs ([`<NextSport>`][Tag::NextSport])
+    pub next_sports: Vec<NextSport>,
+    /// Notes ([`<Notes>`][Tag::Notes])
+    pub notes: Option<String>,
+}
+
+/// a segment of a [`MultiSportSession`], see [`MultiSportSession::segments`]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Segment<'a> {
+    /// A leg of the session
+    Sport(&'a Activity),
+    /// A transition between two legs
+    Transition(&'a Lap),
+}
+
+/// a lap of a course ([`<Lap>`][Tag::Lap] in a [`<Course>`][Tag::Course])
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CourseLap {
+    /// Total duration of the lap in seconds ([`<TotalTimeSeconds>`][Tag::TotalTimeSeconds])
+    pub total_time: Option<f64>,
+    /// Length of the lap ([`<DistanceMeters>`][Tag::DistanceMeters])
+    pub distance: Option<f64>,
+    /// Position where the lap begins ([`<BeginPosition>`][Tag::BeginPosition])
+    pub begin_position: Option<Position>,
+    /// Altitude where the lap begins ([`<BeginAltitudeMeters>`][Tag::BeginAltitudeMeters])
+    pub begin_altitude: Option<f64>,
+    /// Position where the lap ends ([`<EndPosition>`][Tag::EndPosition])
+    pub end_position: Option<Position>,
+    /// Altitude where the lap ends ([`<EndAltitudeMeters>`][Tag::EndAltitudeMeters])
+    pub end_altitude: Option<f64>,
+    /// Average heart rate ([`<AverageHeartRateBpm>`][Tag::AverageHeartRateBpm]&#173;[`<Value>`][Tag::Value])
+    pub average_heartrate: Option<f64>,
+    /// Maximum heart rate ([`<MaximumHeartRateBpm>`][Tag::MaximumHeartRateBpm]&#173;[`<Value>`][Tag::Value])
+    pub maximum_heartrate: Option<f64>,
+    /// Intensity ([`<Intensity>`][Tag::Intensity])
+    pub intensity: Option<Intensity>,
+    /// Average cadence ([`<Cadence>`][Tag::Cadence])
+    pub cadence: Option<f64>,
+}
+
+/// a point of interest on a course ([`<CoursePoint>`][Tag::CoursePoint])
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoursePoint {
+    /// Name of the point ([`<Name>`][Tag::Name])
+    pub name: String,
+    /// Timestamp of the point ([`<Time>`][Tag::Time])
+    pub time: DateTime<Utc>,
+    /// Position of the point ([`<Position>`][Tag::Position])
+    pub position: Position,
+    /// Altitude of the point ([`<AltitudeMeters>`][Tag::AltitudeMeters])
+    pub altitude: Option<f64>,
+    /// Type of the point ([`<PointType>`][Tag::PointType])
+    pub point_type: Option<PointType>,
+    /// Notes ([`<Notes>`][Tag::Notes])
+    pub notes: Option<String>,
+}
+
+/// a course ([`<Course>`][Tag::Course])
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Course {
+    /// Name of the course ([`<Name>`][Tag::Name])
+    pub name: String,
+    /// The course's laps ([`<Lap>`][Tag::Lap])
+    pub laps: Vec<CourseLap>,
+    /// The course's tracks ([`<Track>`][Tag::Track])
+    pub tracks: Vec<Track>,
+    /// Notes ([`<Notes>`][Tag::Notes])
+    pub notes: Option<String>,
+    /// Points of interest ([`<CoursePoint>`][Tag::CoursePoint])
+    pub course_points: Vec<CoursePoint>,
+    /// The device that created the course ([`<Creator>`][Tag::Creator])
+    pub creator: Option<Device>,
+}
+
+/// the root of a TCX document ([`<TrainingCenterDatabase>`][Tag::TrainingCenterDatabase])
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tcx {
+    /// The application that wrote the file ([`<Author>`][Tag::Author])
+    pub author: Option<Application>,
+    /// Activities ([`<Activities>`][Tag::Activities]&#173;[`<Activity>`][Tag::Activity])
+    pub activities: Vec<Activity>,
+    /// Multi sport sessions ([`<Activities>`][Tag::Activities]&#173;[`<MultiSportSession>`][Tag::MultiSportSession])
+    pub multi_sport_sessions: Vec<MultiSportSession>,
+    /// Courses ([`<Courses>`][Tag::Courses]&#173;[`<Course>`][Tag::Course])
+    pub courses: Vec<Course>,
+    /// Workouts ([`<Workouts>`][Tag::Workouts]&#173;[`<Workout>`][Tag::Workout])
+    pub workouts: Vec<Workout>,
+    /// Folders ([`<Folders>`][Tag::Folders])
+    pub folders: Option<Folders>,
+}
+
+impl Version {
+    /// Parse a [`<Version>`][Tag::Version] element
+    pub fn parse(version: &Element) -> Result<Self, TcxError> {
+        Version::parse_with(version, &mut ParseContext::default())
+    }
+
+    /// Parse a [`<Version>`][Tag::Version] element using the given [`ParseContext`]
+    pub fn parse_with(version: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        Ok(Version {
+            version_major: context
+                .child_value(version, &[Tag::VersionMajor])?
+                .ok_or_else(|| TcxError::missing(&[Tag::VersionMajor]))?,
+            version_minor: context
+                .child_value(version, &[Tag::VersionMinor])?
+                .ok_or_else(|| TcxError::missing(&[Tag::VersionMinor]))?,
+            build_major: context.child_value(version, &[Tag::BuildMajor])?,
+            build_minor: context.child_value(version, &[Tag::BuildMinor])?,
+        })
+    }
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.version_major, self.version_minor)?;
+        if let Some(build_major) = self.build_major {
+            write!(f, ".{}", build_major)?;
+            if let Some(build_minor) = self.build_minor {
+                write!(f, ".{}", build_minor)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Device {
+    /// Parse a [`<Creator>`][Tag::Creator] element
     ///
-    /// This function assumes that [`<Trackpoint>`][Tag::Trackpoint]s are nested in [`<Track>`][Tag::Track]s, [`<Track>`][Tag::Track]s
-    /// are either nested in [`<Lap>`][Tag::Lap]s which are nested in [`<Activity>`][Tag::Activity]s which are nested in
-    /// [`<Activities>`][Tag::Activities]' or they are nested in [`Course`][Tag::Course]s which are nested in [`Courses`][Tag::Courses]'.
-    pub fn from_tcx(tcx: &Element, filter: fn(&Self) -> bool) -> Result<Vec<Self>, Box<dyn Error>> {
-        // traverse document
+    /// # Examples
+    /// ```
+    /// # use tcx::*;
+    ///
+    /// let doc = r#"<Creator xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+    ///   <Name>Edge 530</Name>
+    ///   <UnitId>3333333333</UnitId>
+    ///   <ProductID>3121</ProductID>
+    ///   <Version>
+    ///     <VersionMajor>9</VersionMajor>
+    ///     <VersionMinor>75</VersionMinor>
+    ///     <BuildMajor>0</BuildMajor>
+    ///     <BuildMinor>0</BuildMinor>
+    ///   </Version>
+    /// </Creator>"#;
+    ///
+    /// let device = Device::parse(&doc.parse().unwrap()).unwrap();
+    ///
+    /// assert_eq!(device.name, "Edge 530");
+    /// assert_eq!(device.unit_id, Some(3333333333));
+    /// assert_eq!(device.product_id, Some(3121));
+    /// assert_eq!(device.version.unwrap().to_string(), "9.75.0.0");
+    /// ```
+    pub fn parse(device: &Element) -> Result<Self, TcxError> {
+        Device::parse_with(device, &mut ParseContext::default())
+    }
+
+    /// Parse a [`<Creator>`][Tag::Creator] element using the given [`ParseContext`]
+    pub fn parse_with(device: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        Ok(Device {
+            name: context
+                .child_value(device, &[Tag::Name])?
+                .unwrap_or_default(),
+            unit_id: context.child_value(device, &[Tag::UnitId])?,
+            product_id: context.child_value(device, &[Tag::ProductID])?,
+            version: context
+                .child(device, &[Tag::Version])
+                .map(|e| Version::parse_with(e, context))
+                .transpose()?,
+        })
+    }
+}
+
+impl Application {
+    /// Parse an [`<Author>`][Tag::Author] element
+    pub fn parse(application: &Element) -> Result<Self, TcxError> {
+        Application::parse_with(application, &mut ParseContext::default())
+    }
+
+    /// Parse an [`<Author>`][Tag::Author] element using the given [`ParseContext`]
+    pub fn parse_with(application: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        Ok(Application {
+            name: context
+                .child_value(application, &[Tag::Name])?
+                .unwrap_or_default(),
+            build: context
+                .child(application, &[Tag::Build, Tag::Version])
+                .map(|e| Version::parse_with(e, context))
+                .transpose()?,
+            lang_id: context.child_value(application, &[Tag::LangID])?,
+            part_number: context.child_value(application, &[Tag::PartNumber])?,
+        })
+    }
+}
+
+impl Track {
+    /// Parse a [`<Track>`][Tag::Track] element
+    pub fn parse(track: &Element) -> Result<Self, TcxError> {
+        Track::parse_with(track, &mut ParseContext::default())
+    }
+
+    /// Parse a [`<Track>`][Tag::Track] element using the given [`ParseContext`]
+    pub fn parse_with(track: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        let mut trackpoints = Vec::new();
+        for (k, e) in context.children_tagged(track, Tag::Trackpoint).enumerate() {
+            let since = context.warnings.len();
+            match Trackpoint::parse_with(e, context) {
+                Ok(point) => trackpoints.push(point),
+                Err(err) => context.recover(err.with_trackpoint(k), Recovery::TrackpointSkipped)?,
+            }
+            context.update_warnings(since, |err| err.with_trackpoint(k));
+        }
+
+        Ok(Track { trackpoints })
+    }
+}
+
+impl Lap {
+    /// Parse a [`<Lap>`][Tag::Lap] element including its summary values
+    ///
+    /// # Examples
+    /// ```
+    /// # use tcx::*;
+    ///
+    /// let doc = r#"<Lap xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2" StartTime="2022-12-31T12:00:00Z">
+    ///   <TotalTimeSeconds>600.0</TotalTimeSeconds>
+    ///   <DistanceMeters>5000.0</DistanceMeters>
+    ///   <Calories>150</Calories>
+    ///   <AverageHeartRateBpm><Value>140</Value></AverageHeartRateBpm>
+    ///   <Intensity>Active</Intensity>
+    ///   <TriggerMethod>Distance</TriggerMethod>
+    ///   <Extensions>
+    ///     <LX xmlns="http://www.garmin.com/xmlschemas/ActivityExtension/v2">
+    ///       <AvgWatts>210</AvgWatts>
+    ///     </LX>
+    ///   </Extensions>
+    /// </Lap>"#;
+    ///
+    /// let lap = Lap::parse(&doc.parse().unwrap()).unwrap();
+    ///
+    /// assert_eq!(lap.start_time, Some("2022-12-31T12:00:00Z".parse().unwrap()));
+    /// assert_eq!(lap.total_time, Some(600.0));
+    /// assert_eq!(lap.distance, Some(5000.0));
+    /// assert_eq!(lap.calories, Some(150.0));
+    /// assert_eq!(lap.average_heartrate, Some(140.0));
+    /// assert_eq!(lap.maximum_heartrate, None);
+    /// assert_eq!(lap.average_power, Some(210.0));
+    /// assert_eq!(lap.intensity, Some(Intensity::Active));
+    /// assert_eq!(lap.trigger_method, Some(TriggerMethod::Distance));
+    /// assert!(lap.tracks.is_empty());
+    /// ```
+    pub fn parse(lap: &Element) -> Result<Self, TcxError> {
+        Lap::parse_with(lap, &mut ParseContext::default())
+    }
+
+    /// Parse a [`<Lap>`][Tag::Lap] element using the given [`ParseContext`]
+    pub fn parse_with(lap: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        let tracks = parse_all(
+            context.children_tagged(lap, Tag::Track),
+            Track::parse_with,
+            context,
+            &mut 0,
+        )?;
+
+        let mut result = Lap {
+            start_time: context.attr_time(lap, Tag::StartTime)?,
+            intensity: context.child_value(lap, &[Tag::Intensity])?,
+            trigger_method: context.child_value(lap, &[Tag::TriggerMethod])?,
+            tracks,
+            ..Default::default()
+        };
+
+        for field in &LAP_FIELD {
+            for tags in field.get_tags() {
+                if let Some(val) = context.child_value(lap, tags)? {
+                    result[field] = Some(val);
+                    break;
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Iterate over all track points in the lap
+    pub fn trackpoints(&self) -> impl Iterator<Item = &Trackpoint> {
+        self.tracks.iter().flat_map(|t| t.trackpoints.iter())
+    }
+}
 
-        let mut points = [tcx]
+impl Activity {
+    /// Parse an [`<Activity>`][Tag::Activity] element
+    pub fn parse(activity: &Element) -> Result<Self, TcxError> {
+        Activity::parse_with(activity, &mut ParseContext::default())
+    }
+
+    /// Parse an [`<Activity>`][Tag::Activity] element using the given [`ParseContext`]
+    pub fn parse_with(activity: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        let laps = parse_all(
+            context.children_tagged(activity, Tag::Lap),
+            Lap::parse_with,
+            context,
+            &mut 0,
+        )?;
+
+        Ok(Activity {
+            sport: context.attr_value(activity, Tag::Sport)?,
+            id: context.child_time(activity, &[Tag::Id])?,
+            notes: context.child_value(activity, &[Tag::Notes])?,
+            creator: context
+                .child(activity, &[Tag::Creator])
+                .map(|e| Device::parse_with(e, context))
+                .transpose()?,
+            laps,
+        })
+    }
+
+    /// Iterate over all track points in the activity
+    pub fn trackpoints(&self) -> impl Iterator<Item = &Trackpoint> {
+        self.laps.iter().flat_map(|l| l.trackpoints())
+    }
+}
+
+impl NextSport {
+    /// Parse a [`<NextSport>`][Tag::NextSport] element
+    pub fn parse(next_sport: &Element) -> Result<Self, TcxError> {
+        NextSport::parse_with(next_sport, &mut ParseContext::default())
+    }
+
+    /// Parse a [`<NextSport>`][Tag::NextSport] element using the given [`ParseContext`]
+    pub fn parse_with(next_sport: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        let mut offset = 0;
+
+        let transition = parse_all(
+            context.children_tagged(next_sport, Tag::Transition).take(1),
+            Lap::parse_with,
+            context,
+            &mut offset,
+        )?
+        .pop();
+
+        let activity = parse_all(
+            context.children_tagged(next_sport, Tag::Activity).take(1),
+            Activity::parse_with,
+            context,
+            &mut offset,
+        )?
+        .pop()
+        .ok_or_else(|| TcxError::missing(&[Tag::Activity]))?;
+
+        Ok(NextSport {
+            transition,
+            activity,
+        })
+    }
+}
+
+impl MultiSportSession {
+    /// Parse a [`<MultiSportSession>`][Tag::MultiSportSession] element
+    pub fn parse(session: &Element) -> Result<Self, TcxError> {
+        MultiSportSession::parse_with(session, &mut ParseContext::default())
+    }
+
+    /// Parse a [`<MultiSportSession>`][Tag::MultiSportSession] element using the given [`ParseContext`]
+    pub fn parse_with(session: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        let mut offset = 0;
+
+        let first_sport = parse_all(
+            context
+                .child(session, &[Tag::FirstSport, Tag::Activity])
+                .into_iter(),
+            Activity::parse_with,
+            context,
+            &mut offset,
+        )?
+        .pop()
+        .ok_or_else(|| TcxError::missing(&[Tag::FirstSport, Tag::Activity]))?;
+
+        let next_sports = parse_all(
+            context.children_tagged(session, Tag::NextSport),
+            NextSport::parse_with,
+            context,
+            &mut offset,
+        )?;
+
+        Ok(MultiSportSession {
+            id: context.child_time(session, &[Tag::Id])?,
+            first_sport,
+            next_sports,
+            notes: context.child_value(session, &[Tag::Notes])?,
+        })
+    }
+
+    /// Iterate over the session's legs and transitions in chronological order
+    ///
+    /// # Examples
+    /// ```
+    /// # use tcx::*;
+    ///
+    /// let doc = r#"<MultiSportSession xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+    ///   <Id>2022-12-31T12:00:00Z</Id>
+    ///   <FirstSport><Activity Sport="Other"/></FirstSport>
+    ///   <NextSport>
+    ///     <Transition/>
+    ///     <Activity Sport="Biking"/>
+    ///   </NextSport>
+    ///   <NextSport>
+    ///     <Activity Sport="Running"/>
+    ///   </NextSport>
+    /// </MultiSportSession>"#;
+    ///
+    /// let session = MultiSportSession::parse(&doc.parse().unwrap()).unwrap();
+    /// let segments = session.segments().collect::<Vec<_>>();
+    ///
+    /// assert_eq!(segments.len(), 4);
+    /// assert!(matches!(segments[0], Segment::Sport(a) if a.sport == Some(Sport::Other)));
+    /// assert!(matches!(segments[1], Segment::Transition(_)));
+    /// assert!(matches!(segments[2], Segment::Sport(a) if a.sport == Some(Sport::Biking)));
+    /// assert!(matches!(segments[3], Segment::Sport(a) if a.sport == Some(Sport::Running)));
+    /// ```
+    pub fn segments(&self) -> impl Iterator<Item = Segment<'_>> {
+        [Segment::Sport(&self.first_sport)]
+            .into_iter()
+            .chain(self.next_sports.iter().flat_map(|n| {
+                n.transition
+                    .iter()
+                    .map(Segment::Transition)
+                    .chain([Segment::Sport(&n.activity)])
+            }))
+    }
+
+    /// Iterate over all track points in the session including transitions
+    pub fn trackpoints(&self) -> impl Iterator<Item = &Trackpoint> {
+        self.segments().flat_map(|s| s.trackpoints())
+    }
+}
+
+impl<'a> Segment<'a> {
+    /// Iterate over all track points in the segment
+    pub fn trackpoints(&self) -> Box<dyn Iterator<Item = &'a Trackpoint> + 'a> {
+        match *self {
+            Segment::Sport(activity) => Box::new(activity.trackpoints()),
+            Segment::Transition(lap) => Box::new(lap.trackpoints()),
+        }
+    }
+}
+
+impl Position {
+    /// Parse a [`<Position>`][Tag::Position] element (or any element of the same type, e.g.,
+    /// [`<BeginPosition>`][Tag::BeginPosition])
+    pub fn parse(position: &Element) -> Result<Self, TcxError> {
+        Position::parse_with(position, &mut ParseContext::default())
+    }
+
+    /// Parse a [`<Position>`][Tag::Position] element (or any element of the same type, e.g., using the given [`ParseContext`]
+    pub fn parse_with(position: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        Ok(Position {
+            latitude: context
+                .child_value(position, &[Tag::LatitudeDegrees])?
+                .ok_or_else(|| TcxError::missing(&[Tag::LatitudeDegrees]))?,
+            longitude: context
+                .child_value(position, &[Tag::LongitudeDegrees])?
+                .ok_or_else(|| TcxError::missing(&[Tag::LongitudeDegrees]))?,
+        })
+    }
+}
+
+impl CourseLap {
+    /// Parse a [`<Lap>`][Tag::Lap] element of a course
+    pub fn parse(lap: &Element) -> Result<Self, TcxError> {
+        CourseLap::parse_with(lap, &mut ParseContext::default())
+    }
+
+    /// Parse a [`<Lap>`][Tag::Lap] element of a course using the given [`ParseContext`]
+    pub fn parse_with(lap: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        Ok(CourseLap {
+            total_time: context.child_value(lap, &[Tag::TotalTimeSeconds])?,
+            distance: context.child_value(lap, &[Tag::DistanceMeters])?,
+            begin_position: context
+                .child(lap, &[Tag::BeginPosition])
+                .map(|e| Position::parse_with(e, context))
+                .transpose()?,
+            begin_altitude: context.child_value(lap, &[Tag::BeginAltitudeMeters])?,
+            end_position: context
+                .child(lap, &[Tag::EndPosition])
+                .map(|e| Position::parse_with(e, context))
+                .transpose()?,
+            end_altitude: context.child_value(lap, &[Tag::EndAltitudeMeters])?,
+            average_heartrate: context.child_value(lap, &[Tag::AverageHeartRateBpm, Tag::Value])?,
+            maximum_heartrate: context.child_value(lap, &[Tag::MaximumHeartRateBpm, Tag::Value])?,
+            intensity: context.child_value(lap, &[Tag::Intensity])?,
+            cadence: context.child_value(lap, &[Tag::Cadence])?,
+        })
+    }
+}
+
+impl CoursePoint {
+    /// Parse a [`<CoursePoint>`][Tag::CoursePoint] element
+    ///
+    /// # Examples
+    /// ```
+    /// # use tcx::*;
+    ///
+    /// let doc = r#"<CoursePoint xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+    ///   <Name>Col</Name>
+    ///   <Time>2022-12-31T12:00:00Z</Time>
+    ///   <Position>
+    ///     <LatitudeDegrees>45.0</LatitudeDegrees>
+    ///     <LongitudeDegrees>6.5</LongitudeDegrees>
+    ///   </Position>
+    ///   <PointType>Hors Category</PointType>
+    /// </CoursePoint>"#;
+    ///
+    /// let point = CoursePoint::parse(&doc.parse().unwrap()).unwrap();
+    ///
+    /// assert_eq!(point.name, "Col");
+    /// assert_eq!(point.position, Position { latitude: 45.0, longitude: 6.5 });
+    /// assert_eq!(point.altitude, None);
+    /// assert_eq!(point.point_type, Some(PointType::HorsCategory));
+    /// ```
+    pub fn parse(point: &Element) -> Result<Self, TcxError> {
+        CoursePoint::parse_with(point, &mut ParseContext::default())
+    }
+
+    /// Parse a [`<CoursePoint>`][Tag::CoursePoint] element using the given [`ParseContext`]
+    pub fn parse_with(point: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        Ok(CoursePoint {
+            name: context
+                .child_value(point, &[Tag::Name])?
+                .unwrap_or_default(),
+            time: context
+                .child_time(point, &[Tag::Time])?
+                .ok_or_else(|| TcxError::missing(&[Tag::Time]))?,
+            position: Position::parse_with(
+                context
+                    .child(point, &[Tag::Position])
+                    .ok_or_else(|| TcxError::missing(&[Tag::Position]))?,
+                context,
+            )?,
+            altitude: context.child_value(point, &[Tag::AltitudeMeters])?,
+            point_type: context.child_value(point, &[Tag::PointType])?,
+            notes: context.child_value(point, &[Tag::Notes])?,
+        })
+    }
+}
+
+impl Course {
+    /// Parse a [`<Course>`][Tag::Course] element
+    pub fn parse(course: &Element) -> Result<Self, TcxError> {
+        Course::parse_with(course, &mut ParseContext::default())
+    }
+
+    /// Parse a [`<Course>`][Tag::Course] element using the given [`ParseContext`]
+    pub fn parse_with(course: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        let laps = context
+            .children_tagged(course, Tag::Lap)
+            .map(|e| CourseLap::parse_with(e, context))
+            .collect::<Result<_, _>>()?;
+
+        let tracks = parse_all(
+            context.children_tagged(course, Tag::Track),
+            Track::parse_with,
+            context,
+            &mut 0,
+        )?;
+
+        let course_points = context
+            .children_tagged(course, Tag::CoursePoint)
+            .map(|e| CoursePoint::parse_with(e, context))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Course {
+            name: context
+                .child_value(course, &[Tag::Name])?
+                .unwrap_or_default(),
+            laps,
+            tracks,
+            notes: context.child_value(course, &[Tag::Notes])?,
+            course_points,
+            creator: context
+                .child(course, &[Tag::Creator])
+                .map(|e| Device::parse_with(e, context))
+                .transpose()?,
+        })
+    }
+
+    /// Iterate over all track points in the course
+    pub fn trackpoints(&self) -> impl Iterator<Item = &Trackpoint> {
+        self.tracks.iter().flat_map(|t| t.trackpoints.iter())
+    }
+}
+
+impl Tcx {
+    /// Parse a TCX document's root element
+    ///
+    /// [`<Activity>`][Tag::Activity]s and [`<MultiSportSession>`][Tag::MultiSportSession]s are expected in
+    /// [`<Activities>`][Tag::Activities], [`<Course>`][Tag::Course]s are expected in [`<Courses>`][Tag::Courses] and
+    /// [`<Workout>`][Tag::Workout]s are expected in [`<Workouts>`][Tag::Workouts].
+    pub fn parse(tcx: &Element) -> Result<Self, TcxError> {
+        Tcx::parse_with(tcx, &mut ParseContext::default())
+    }
+
+    /// Parse a TCX document's root element using the given [`ParseContext`]
+    pub fn parse_with(tcx: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        // track point offset for errors, see `Tcx::trackpoints` for the order
+        let mut offset = 0;
+        let namespaces = context.namespaces;
+
+        let activities = parse_all(
+            context
+                .children_tagged(tcx, Tag::Activities)
+                .flat_map(|e| e.children_matching(Tag::Activity, namespaces)),
+            Activity::parse_with,
+            context,
+            &mut offset,
+        )?;
+
+        let multi_sport_sessions = parse_all(
+            context
+                .children_tagged(tcx, Tag::Activities)
+                .flat_map(|e| e.children_matching(Tag::MultiSportSession, namespaces)),
+            MultiSportSession::parse_with,
+            context,
+            &mut offset,
+        )?;
+
+        let courses = parse_all(
+            context
+                .children_tagged(tcx, Tag::Courses)
+                .flat_map(|e| e.children_matching(Tag::Course, namespaces)),
+            Course::parse_with,
+            context,
+            &mut offset,
+        )?;
+
+        let workouts = context
+            .children_tagged(tcx, Tag::Workouts)
+            .flat_map(|e| e.children_matching(Tag::Workout, namespaces))
+            .map(|e| Workout::parse_with(e, context))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Tcx {
+            author: context
+                .child(tcx, &[Tag::Author])
+                .map(|e| Application::parse_with(e, context))
+                .transpose()?,
+            activities,
+            multi_sport_sessions,
+            courses,
+            workouts,
+            folders: context
+                .child(tcx, &[Tag::Folders])
+                .map(|e| Folders::parse_with(e, context))
+                .transpose()?,
+        })
+    }
+
+    /// Iterate over all track points of all activities followed by all track points of all multi sport sessions and
+    /// all track points of all courses
+    pub fn trackpoints(&self) -> impl Iterator<Item = &Trackpoint> {
+        self.activities
             .iter()
-            .flat_map(|e| e.children().filter(|e| e.is_tag(Tag::Activities)))
-            .flat_map(|e| e.children().filter(|e| e.is_tag(Tag::Activity)))
-            .flat_map(|e| e.children().filter(|e| e.is_tag(Tag::Lap)))
+            .flat_map(|a| a.trackpoints())
             .chain(
-                [tcx]
+                self.multi_sport_sessions
                     .iter()
-                    .flat_map(|e| e.children().filter(|e| e.is_tag(Tag::Courses)))
-                    .flat_map(|e| e.children().filter(|e| e.is_tag(Tag::Course))),
+                    .flat_map(|s| s.trackpoints()),
             )
-            .flat_map(|e| e.children().filter(|e| e.is_tag(Tag::Track)))
-            .flat_map(|e| e.children().filter(|e| e.is_tag(Tag::Trackpoint)))
-            .map(|trackpoint| Trackpoint::parse(trackpoint))
-            .filter(|t| t.as_ref().map_or(true, filter))
-            .collect::<Result<Vec<_>, _>>()?;
+            .chain(self.courses.iter().flat_map(|c| c.trackpoints()))
+    }
+}
+
+impl Trackpoint {
+    /// Read track points from TCX element flattening any structure
+    ///
+    /// This function assumes that [`<Trackpoint>`][Tag::Trackpoint]s are nested in [`<Track>`][Tag::Track]s, [`<Track>`][Tag::Track]s
+    /// are either nested in [`<Lap>`][Tag::Lap]s which are nested in [`<Activity>`][Tag::Activity]s which are nested in
+    /// [`<Activities>`][Tag::Activities]' (possibly via a [`<MultiSportSession>`][Tag::MultiSportSession]) or they are
+    /// nested in [`Course`][Tag::Course]s which are nested in [`Courses`][Tag::Courses]'.
+    ///
+    /// Use [`Tcx::parse`] to keep the structure.
+    pub fn from_tcx(tcx: &Element, filter: fn(&Self) -> bool) -> Result<Vec<Self>, TcxError> {
+        Trackpoint::from_tcx_with(tcx, filter, &mut ParseContext::default())
+    }
+
+    /// Read track points from TCX element flattening any structure using the given [`ParseContext`]
+    ///
+    /// See [`Trackpoint::from_tcx`].
+    pub fn from_tcx_with(
+        tcx: &Element,
+        filter: fn(&Self) -> bool,
+        context: &mut ParseContext,
+    ) -> Result<Vec<Self>, TcxError> {
+        Ok(Trackpoint::collect(
+            Tcx::parse_with(tcx, context)?.trackpoints(),
+            filter,
+        ))
+    }
+
+    /// Clone track points that pass the `filter` into a vector and remove consecutive duplicates
+    ///
+    /// Duplicates typically occur at lap boundaries where the last point of a lap is repeated as the first point of the
+    /// next lap.
+    pub fn collect<'a>(
+        points: impl Iterator<Item = &'a Trackpoint>,
+        filter: fn(&Self) -> bool,
+    ) -> Vec<Self> {
+        let mut points = points.filter(|t| filter(t)).cloned().collect::<Vec<_>>();
 
         // remove duplicates
         points.dedup();
 
-        Ok(points)
+        points
     }
 
     /// Parse a single trackpoint for a [`<Trackpoint>`][Tag::Trackpoint]
@@ -213,7 +1615,7 @@ impl Trackpoint {
     /// ```
     /// # use tcx::*;
     ///
-    /// let doc = r#"<Trackpoint xmlns="arbitrary">
+    /// let doc = r#"<Trackpoint xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
     ///   <Time>2022-12-31 23:59:59 UTC</Time>
     ///   <Position>
     ///     <LongitudeDegrees>9.0</LongitudeDegrees>
@@ -222,7 +1624,7 @@ impl Trackpoint {
     ///   <AltitudeMeters>450.0</AltitudeMeters>
     ///   <HeartRateBpm><Value>100</Value></HeartRateBpm>
     ///   <Extensions>
-    ///     <TPX>
+    ///     <TPX xmlns="http://www.garmin.com/xmlschemas/ActivityExtension/v2">
     ///       <Watts>250</Watts>
     ///       <RunCadence>90</RunCadence>
     ///     </TPX>
@@ -241,10 +1643,17 @@ impl Trackpoint {
     /// assert_eq!(trackpoint.power, Some(250.0));
     /// assert_eq!(trackpoint.cadence, Some(90.0));
     /// ```
-    pub fn parse(trackpoint: &Element) -> Result<Self, Box<dyn Error>> {
-        let time = trackpoint
-            .child_value(&[Tag::Time])?
-            .ok_or_else(|| format!("Missing time in {:?}", trackpoint))?;
+    pub fn parse(trackpoint: &Element) -> Result<Self, TcxError> {
+        Trackpoint::parse_with(trackpoint, &mut ParseContext::default())
+    }
+
+    /// Parse a single trackpoint for a [`<Trackpoint>`][Tag::Trackpoint] using the given [`ParseContext`]
+    ///
+    /// Invalid fields are dropped in [`ParseMode::Lenient`]. A missing or invalid time is an error in any mode.
+    pub fn parse_with(trackpoint: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        let time = context
+            .child_time(trackpoint, &[Tag::Time])?
+            .ok_or_else(|| TcxError::missing(&[Tag::Time]))?;
         let mut point = Trackpoint {
             time,
             ..Default::default()
@@ -252,25 +1661,81 @@ impl Trackpoint {
 
         for field in &TRK_PT_FIELD {
             for tags in field.get_tags() {
-                if let Some(val) = trackpoint.child_value(tags)? {
-                    point[field] = Some(val);
-                    break;
+                match context.child_value(trackpoint, tags) {
+                    Ok(Some(val)) => {
+                        point[field] = Some(val);
+                        break;
+                    }
+                    Ok(None) => (),
+                    Err(e) => context.recover(e.with_field(*field), Recovery::FieldDropped)?,
                 }
             }
         }
 
+        point.extensions = unknown_extensions(trackpoint, context.namespaces);
+
         Ok(point)
     }
 }
 
+/// Get the numeric values in a [`<Trackpoint>`][Tag::Trackpoint]'s [`<Extensions>`][Tag::Extensions] which are not
+/// represented by any [`TrkPtField`], see [`Trackpoint::extensions`]
+///
+/// Elements only count as represented if they match the field's tags with the given [`NamespaceMatching`], i.e.,
+/// third party elements with a known name are collected in strict mode.
+fn unknown_extensions(trackpoint: &Element, matching: NamespaceMatching) -> BTreeMap<String, f64> {
+    let mut map = BTreeMap::new();
+    if let Some(extensions) = trackpoint.child_matching(&[Tag::Extensions], matching) {
+        // elements represented by a field
+        let known = TRK_PT_FIELD
+            .iter()
+            .flat_map(|field| field.get_tags().iter())
+            .filter_map(|tags| trackpoint.child_matching(tags, matching))
+            .collect::<Vec<_>>();
+        collect_extensions(extensions, "", &known, &mut map);
+    }
+    map
+}
+
+/// Collect numeric leaves nested in `element` which are not `known`, i.e., not represented by any [`TrkPtField`]
+///
+/// The `key` is the path of `element` in Clark notation, see [`Trackpoint::extensions`].
+fn collect_extensions(
+    element: &Element,
+    key: &str,
+    known: &[&Element],
+    extensions: &mut BTreeMap<String, f64>,
+) {
+    for child in element.children() {
+        let name = match child.ns().as_str() {
+            "" => child.name().to_string(),
+            ns => format!("{{{}}}{}", ns, child.name()),
+        };
+        let key = match key {
+            "" => name,
+            key => format!("{}/{}", key, name),
+        };
+
+        if child.children().next().is_some() {
+            collect_extensions(child, &key, known, extensions);
+        } else if !known.iter().any(|k| std::ptr::eq(*k, child)) {
+            if let Ok(value) = child.text().trim().parse() {
+                extensions.insert(key, value);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use chrono::Duration;
+    use std::collections::HashMap;
+
+    use chrono::{Duration, FixedOffset};
 
     use super::*;
 
     const TIME_0: &str = "2022-12-31 12:00:00 UTC";
-    const DOC: &str = r#"<TCX xmlns="TCX">
+    const DOC: &str = r#"<TCX xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
       <Activities>
         <Activity>
           <Lap>
@@ -331,6 +1796,439 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tcx_parse() {
+        let doc = format!(
+            "{}{}",
+            DOC.strip_suffix("</TCX>").unwrap(),
+            r#"<Courses>
+                <Course>
+                  <Track>
+                    <Trackpoint><Time>2022-12-31 13:00:00 UTC</Time></Trackpoint>
+                  </Track>
+                </Course>
+              </Courses>
+            </TCX>"#
+        );
+        let tcx = Tcx::parse(&doc.parse().unwrap()).unwrap();
+
+        assert_eq!(1, tcx.activities.len());
+        assert_eq!(2, tcx.activities[0].laps.len());
+        for lap in &tcx.activities[0].laps {
+            assert_eq!(1, lap.tracks.len());
+            assert_eq!(3, lap.tracks[0].trackpoints.len());
+        }
+        assert_eq!(
+            Some(7.2),
+            tcx.activities[0].laps[1].tracks[0].trackpoints[0].distance
+        );
+
+        assert_eq!(1, tcx.courses.len());
+        assert_eq!(1, tcx.courses[0].trackpoints().count());
+        assert_eq!(7, tcx.trackpoints().count());
+    }
+
+    #[test]
+    fn test_activity_metadata() {
+        let doc = r#"<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+          <Activities>
+            <Activity Sport="Biking">
+              <Id>2022-12-31T12:00:00Z</Id>
+              <Notes>Evening ride</Notes>
+              <Creator>
+                <Name>Edge 530</Name>
+                <UnitId>3333333333</UnitId>
+              </Creator>
+            </Activity>
+          </Activities>
+          <Author>
+            <Name>Connect Api</Name>
+            <Build>
+              <Version>
+                <VersionMajor>0</VersionMajor>
+                <VersionMinor>0</VersionMinor>
+              </Version>
+            </Build>
+            <LangID>en</LangID>
+            <PartNumber>006-D2449-00</PartNumber>
+          </Author>
+        </TrainingCenterDatabase>"#;
+        let tcx = Tcx::parse(&doc.parse().unwrap()).unwrap();
+
+        let activity = &tcx.activities[0];
+        assert_eq!(Some(Sport::Biking), activity.sport);
+        assert_eq!(Some(TIME_0.parse::<DateTime<Utc>>().unwrap()), activity.id);
+        assert_eq!(Some("Evening ride"), activity.notes.as_deref());
+        let creator = activity.creator.as_ref().unwrap();
+        assert_eq!("Edge 530", creator.name);
+        assert_eq!(Some(3333333333), creator.unit_id);
+        assert!(creator.version.is_none());
+
+        let author = tcx.author.unwrap();
+        assert_eq!("Connect Api", author.name);
+        assert_eq!(Some(Version::default()), author.build);
+        assert_eq!(Some("en"), author.lang_id.as_deref());
+        assert_eq!(Some("006-D2449-00"), author.part_number.as_deref());
+    }
+
+    #[test]
+    fn test_course_parse() {
+        let doc = r#"<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+          <Courses>
+            <Course>
+              <Name>Loop</Name>
+              <Lap>
+                <TotalTimeSeconds>3600</TotalTimeSeconds>
+                <DistanceMeters>30000</DistanceMeters>
+                <BeginPosition>
+                  <LatitudeDegrees>48.0</LatitudeDegrees>
+                  <LongitudeDegrees>9.0</LongitudeDegrees>
+                </BeginPosition>
+                <EndPosition>
+                  <LatitudeDegrees>48.1</LatitudeDegrees>
+                  <LongitudeDegrees>9.1</LongitudeDegrees>
+                </EndPosition>
+                <Intensity>Active</Intensity>
+              </Lap>
+              <Track>
+                <Trackpoint><Time>2022-12-31T12:00:00Z</Time></Trackpoint>
+              </Track>
+              <Notes>Flat</Notes>
+              <CoursePoint>
+                <Name>Fountain</Name>
+                <Time>2022-12-31T12:30:00Z</Time>
+                <Position>
+                  <LatitudeDegrees>48.05</LatitudeDegrees>
+                  <LongitudeDegrees>9.05</LongitudeDegrees>
+                </Position>
+                <PointType>Water</PointType>
+              </CoursePoint>
+            </Course>
+          </Courses>
+        </TrainingCenterDatabase>"#;
+        let tcx = Tcx::parse(&doc.parse().unwrap()).unwrap();
+
+        let course = &tcx.courses[0];
+        assert_eq!("Loop", course.name);
+        assert_eq!(Some("Flat"), course.notes.as_deref());
+        assert_eq!(1, course.trackpoints().count());
+
+        let lap = &course.laps[0];
+        assert_eq!(Some(30000.0), lap.distance);
+        assert_eq!(
+            Some(Position {
+                latitude: 48.0,
+                longitude: 9.0
+            }),
+            lap.begin_position
+        );
+        assert_eq!(
+            Some(Position {
+                latitude: 48.1,
+                longitude: 9.1
+            }),
+            lap.end_position
+        );
+        assert_eq!(Some(Intensity::Active), lap.intensity);
+
+        assert_eq!(1, course.course_points.len());
+        assert_eq!("Fountain", course.course_points[0].name);
+        assert_eq!(Some(PointType::Water), course.course_points[0].point_type);
+    }
+
+    #[test]
+    fn test_multi_sport_session_parse() {
+        let doc = r#"<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+          <Activities>
+            <MultiSportSession>
+              <Id>2022-12-31T12:00:00Z</Id>
+              <FirstSport>
+                <Activity Sport="Other">
+                  <Lap><Track>
+                    <Trackpoint><Time>2022-12-31T12:00:00Z</Time></Trackpoint>
+                  </Track></Lap>
+                </Activity>
+              </FirstSport>
+              <NextSport>
+                <Transition><Track>
+                  <Trackpoint><Time>2022-12-31T12:00:01Z</Time></Trackpoint>
+                </Track></Transition>
+                <Activity Sport="Biking">
+                  <Lap><Track>
+                    <Trackpoint><Time>2022-12-31T12:00:02Z</Time></Trackpoint>
+                  </Track></Lap>
+                </Activity>
+              </NextSport>
+            </MultiSportSession>
+          </Activities>
+        </TrainingCenterDatabase>"#;
+        let tcx = Tcx::parse(&doc.parse().unwrap()).unwrap();
+
+        assert!(tcx.activities.is_empty());
+        assert_eq!(1, tcx.multi_sport_sessions.len());
+        let session = &tcx.multi_sport_sessions[0];
+        assert_eq!(Some(TIME_0.parse().unwrap()), session.id);
+        assert_eq!(Some(Sport::Other), session.first_sport.sport);
+        assert_eq!(1, session.next_sports.len());
+        assert!(session.next_sports[0].transition.is_some());
+        assert_eq!(Some(Sport::Biking), session.next_sports[0].activity.sport);
+
+        let counts = session
+            .segments()
+            .map(|s| s.trackpoints().count())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![1, 1, 1], counts);
+        assert_eq!(3, tcx.trackpoints().count());
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let doc = r#"<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+          <Activities>
+            <Activity>
+              <Lap><Track>
+                <Trackpoint><Time>2022-12-31T12:00:00Z</Time></Trackpoint>
+              </Track></Lap>
+              <Lap><Track>
+                <Trackpoint><Time>2022-12-31T12:00:01Z</Time></Trackpoint>
+              </Track><Track>
+                <Trackpoint>
+                  <Time>2022-12-31T12:00:02Z</Time>
+                  <HeartRateBpm><Value>1x0</Value></HeartRateBpm>
+                </Trackpoint>
+              </Track></Lap>
+            </Activity>
+          </Activities>
+        </TrainingCenterDatabase>"#;
+
+        let err = Tcx::parse(&doc.parse().unwrap()).unwrap_err();
+        match err {
+            TcxError::InvalidNumber { context, text } => {
+                assert_eq!("1x0", text);
+                assert_eq!(Some(2), context.trackpoint);
+                assert_eq!(Some(TrkPtField::Heartrate), context.field);
+                assert_eq!(vec![Tag::HeartRateBpm, Tag::Value], context.tags);
+            }
+            err => panic!("Unexpected error: {}", err),
+        }
+
+        let doc = r#"<Trackpoint xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2"><DistanceMeters>0</DistanceMeters></Trackpoint>"#;
+        let err = Trackpoint::parse(&doc.parse().unwrap()).unwrap_err();
+        assert!(matches!(err, TcxError::MissingField { .. }));
+        assert_eq!("Missing <Time>", err.to_string());
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        let doc = r#"<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+          <Activities>
+            <Activity Sport="Swimming">
+              <Lap><Track>
+                <Trackpoint><Time>2022-12-31T12:00:00Z</Time></Trackpoint>
+                <Trackpoint><Time>garbled</Time></Trackpoint>
+              </Track></Lap>
+            </Activity>
+          </Activities>
+          <Courses>
+            <Course>
+              <Track>
+                <Trackpoint>
+                  <Time>2022-12-31T12:00:02Z</Time>
+                  <Extensions>
+                    <TPX xmlns="http://www.garmin.com/xmlschemas/ActivityExtension/v2"><Watts>2S0</Watts></TPX>
+                  </Extensions>
+                </Trackpoint>
+              </Track>
+            </Course>
+          </Courses>
+        </TrainingCenterDatabase>"#;
+        let element = doc.parse().unwrap();
+
+        assert!(Tcx::parse(&element).is_err());
+
+        let mut context = ParseContext::new(ParseMode::Lenient);
+        let tcx = Tcx::parse_with(&element, &mut context).unwrap();
+        assert_eq!(2, tcx.trackpoints().count());
+        assert_eq!(None, tcx.courses[0].tracks[0].trackpoints[0].power);
+        assert_eq!(None, tcx.activities[0].sport);
+
+        let warnings = context
+            .warnings
+            .iter()
+            .map(|w| {
+                let context = w.error.context().unwrap();
+                (w.recovery, context.trackpoint, context.field)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (Recovery::TrackpointSkipped, Some(1), None),
+                (Recovery::FieldDropped, None, None),
+                (Recovery::FieldDropped, Some(2), Some(TrkPtField::Power))
+            ],
+            warnings
+        );
+    }
+
+    #[test]
+    fn test_parse_timezones() {
+        let doc = r#"<Track xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+          <Trackpoint><Time>2022-12-31T12:00:00Z</Time></Trackpoint>
+          <Trackpoint><Time>2022-12-31T13:00:01+01:00</Time></Trackpoint>
+          <Trackpoint><Time>2022-12-31T13:00:02</Time></Trackpoint>
+        </Track>"#;
+        let plus_1 = FixedOffset::east_opt(3600).unwrap();
+        let mut context = ParseContext {
+            timezone: FallbackTimezone::Offset(plus_1),
+            ..Default::default()
+        };
+        let track = Track::parse_with(&doc.parse().unwrap(), &mut context).unwrap();
+
+        let seconds = track
+            .trackpoints
+            .iter()
+            .map(|p| {
+                p.time
+                    .signed_duration_since(TIME_0.parse::<DateTime<Utc>>().unwrap())
+            })
+            .map(|d| d.num_seconds())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![0, 1, 2], seconds);
+        assert_eq!(
+            HashMap::from([
+                (TimeInterpretation::Utc, 1),
+                (TimeInterpretation::Offset(plus_1), 1),
+                (TimeInterpretation::Fallback(plus_1), 1),
+            ]),
+            context.interpretations
+        );
+    }
+
+    #[test]
+    fn test_trackpoint_extension() {
+        for ns in [
+            "http://www.garmin.com/xmlschemas/TrackPointExtension/v1",
+            "http://www.garmin.com/xmlschemas/TrackPointExtension/v2",
+        ] {
+            let doc = format!(
+                r#"<Trackpoint xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2" xmlns:ns3="{}">
+                  <Time>2022-12-31T12:00:00Z</Time>
+                  <Extensions>
+                    <ns3:TrackPointExtension>
+                      <ns3:atemp>21.5</ns3:atemp>
+                      <ns3:wtemp>18.0</ns3:wtemp>
+                      <ns3:depth>1.5</ns3:depth>
+                      <ns3:hr>120</ns3:hr>
+                      <ns3:cad>85</ns3:cad>
+                      <ns3:speed>2.5</ns3:speed>
+                      <ns3:course>90.0</ns3:course>
+                      <ns3:bearing>92.5</ns3:bearing>
+                    </ns3:TrackPointExtension>
+                  </Extensions>
+                </Trackpoint>"#,
+                ns
+            );
+            let point = Trackpoint::parse(&doc.parse().unwrap()).unwrap();
+
+            assert_eq!(Some(21.5), point[&TrkPtField::AirTemperature]);
+            assert_eq!(Some(18.0), point[&TrkPtField::WaterTemperature]);
+            assert_eq!(Some(1.5), point[&TrkPtField::Depth]);
+            assert_eq!(Some(120.0), point[&TrkPtField::Heartrate]);
+            assert_eq!(Some(85.0), point[&TrkPtField::Cadence]);
+            // only defined in v2
+            let v2 = ns.ends_with("v2");
+            assert_eq!(v2.then_some(2.5), point[&TrkPtField::Speed]);
+            assert_eq!(v2.then_some(90.0), point[&TrkPtField::Course]);
+            assert_eq!(v2.then_some(92.5), point[&TrkPtField::Bearing]);
+            assert_eq!(if v2 { 0 } else { 3 }, point.extensions.len());
+        }
+
+        // standard heart rate takes precedence
+        let doc = r#"<Trackpoint xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2" xmlns:ns3="http://www.garmin.com/xmlschemas/TrackPointExtension/v2">
+          <Time>2022-12-31T12:00:00Z</Time>
+          <HeartRateBpm><Value>100</Value></HeartRateBpm>
+          <Extensions><ns3:TrackPointExtension><ns3:hr>120</ns3:hr></ns3:TrackPointExtension></Extensions>
+        </Trackpoint>"#;
+        let point = Trackpoint::parse(&doc.parse().unwrap()).unwrap();
+        assert_eq!(Some(100.0), point.heartrate);
+        assert!(point.extensions.is_empty());
+
+        // variants of lower case elements
+        assert_eq!(Tag::TpxV2Speed.as_ref(), "speed");
+        assert_eq!(Tag::Speed.as_ref(), "Speed");
+    }
+
+    #[test]
+    fn test_trackpoint_unknown_extensions() {
+        let doc = r#"<Trackpoint xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2"
+            xmlns:ns3="http://www.garmin.com/xmlschemas/ActivityExtension/v2" xmlns:x="urn:vendor">
+          <Time>2022-12-31T12:00:00Z</Time>
+          <Extensions>
+            <ns3:TPX><ns3:Watts>250</ns3:Watts><ns3:LeftRightBalance>48.5</ns3:LeftRightBalance></ns3:TPX>
+            <x:Stryd><x:FormPower>60</x:FormPower><x:Label>easy</x:Label></x:Stryd>
+          </Extensions>
+        </Trackpoint>"#;
+        let point = Trackpoint::parse(&doc.parse().unwrap()).unwrap();
+
+        assert_eq!(Some(250.0), point.power);
+        assert_eq!(
+            BTreeMap::from([
+                (
+                    "{http://www.garmin.com/xmlschemas/ActivityExtension/v2}TPX/\
+                    {http://www.garmin.com/xmlschemas/ActivityExtension/v2}LeftRightBalance"
+                        .to_string(),
+                    48.5
+                ),
+                ("{urn:vendor}Stryd/{urn:vendor}FormPower".to_string(), 60.0),
+            ]),
+            point.extensions
+        );
+    }
+
+    #[test]
+    fn test_namespaces() {
+        let doc = r#"<Trackpoint xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2"
+            xmlns:ns3="http://www.garmin.com/xmlschemas/ActivityExtension/v2" xmlns:x="urn:vendor">
+          <Time>2022-12-31T12:00:00Z</Time>
+          <Extensions>
+            <x:TPX><x:Speed>42.0</x:Speed></x:TPX>
+            <ns3:TPX><ns3:Speed>7.5</ns3:Speed></ns3:TPX>
+          </Extensions>
+        </Trackpoint>"#;
+        let point = Trackpoint::parse(&doc.parse().unwrap()).unwrap();
+
+        assert_eq!(Some(7.5), point.speed);
+        assert_eq!(
+            BTreeMap::from([("{urn:vendor}TPX/{urn:vendor}Speed".to_string(), 42.0)]),
+            point.extensions
+        );
+
+        // known names from other namespaces are extensions in strict mode
+        let doc = r#"<Trackpoint xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2"
+            xmlns:x="urn:vendor">
+          <Time>2022-12-31T12:00:00Z</Time>
+          <Extensions><x:TPX><x:Speed>42.0</x:Speed></x:TPX></Extensions>
+        </Trackpoint>"#;
+        let element = doc.parse().unwrap();
+        let point = Trackpoint::parse(&element).unwrap();
+
+        assert_eq!(None, point.speed);
+        assert_eq!(
+            BTreeMap::from([("{urn:vendor}TPX/{urn:vendor}Speed".to_string(), 42.0)]),
+            point.extensions
+        );
+
+        // and fields if no element matches strictly in permissive mode
+        let mut context = ParseContext {
+            namespaces: NamespaceMatching::Permissive,
+            ..Default::default()
+        };
+        let point = Trackpoint::parse_with(&element, &mut context).unwrap();
+
+        assert_eq!(Some(42.0), point.speed);
+        assert!(point.extensions.is_empty());
+    }
+
     #[test]
     fn test_trackpoint_index() {
         let trackpoint = Trackpoint {
@@ -360,4 +2258,46 @@ mod tests {
         assert_eq!(Some(8848.0), trackpoint.altitude);
         assert!(trackpoint.longitude.is_none());
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let tcx = Tcx::parse(&DOC.parse().unwrap()).unwrap();
+
+        let json = serde_json::to_string(&tcx).unwrap();
+        assert_eq!(tcx, serde_json::from_str(&json).unwrap());
+
+        // NaN is written as null and read back
+        let point = Trackpoint {
+            extensions: BTreeMap::from([
+                ("{urn:vendor}Slope".to_string(), f64::NAN),
+                ("{urn:vendor}Gear".to_string(), 3.0),
+            ]),
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&point).unwrap();
+        assert!(json.contains(r#""{urn:vendor}Slope":null"#));
+        let parsed: Trackpoint = serde_json::from_str(&json).unwrap();
+        assert!(parsed.extensions["{urn:vendor}Slope"].is_nan());
+        assert_eq!(3.0, parsed.extensions["{urn:vendor}Gear"]);
+
+        let position = Position {
+            latitude: f64::NAN,
+            longitude: 9.0,
+        };
+        let json = serde_json::to_string(&position).unwrap();
+        assert_eq!(r#"{"latitude":null,"longitude":9.0}"#, json);
+        let parsed: Position = serde_json::from_str(&json).unwrap();
+        assert!(parsed.latitude.is_nan());
+        assert_eq!(9.0, parsed.longitude);
+
+        assert_eq!(
+            r#""Heartrate""#,
+            serde_json::to_string(&TrkPtField::Heartrate).unwrap()
+        );
+        assert_eq!(
+            Tag::AltitudeMeters,
+            serde_json::from_str(r#""AltitudeMeters""#).unwrap()
+        );
+    }
 }
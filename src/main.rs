@@ -1,5 +1,9 @@
 use clap::Parser;
-use std::{error::Error, fs, io::Write};
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+};
 use tcx::*;
 
 /// Command Line Interface for TCX parser
@@ -7,11 +11,13 @@ mod cli {
     use super::GroupBy;
     use clap::Parser;
     use std::{path::PathBuf, str::FromStr};
+    use tcx::{FallbackTimezone, Gradient, TrkPtField, TRK_PT_FIELD};
 
     #[derive(Parser, Debug)]
     #[command(author, version, about, long_about = None)]
     pub struct Cli {
-        /// the TCX file to parse
+        /// the TCX file to parse, files with extension .gpx are read as GPX, .fit as FIT, may be a directory with
+        /// --parquet
         #[arg(name = "TCX-FILE")]
         pub path: PathBuf,
 
@@ -28,6 +34,99 @@ mod cli {
 
         #[arg(short, long, default_value_t = Grouping::Length(GroupBy::Duration, 600.0))]
         pub grouping: Grouping,
+
+        /// only use the activity with the given (zero based) index, use all activities and courses if not given
+        #[arg(short, long)]
+        pub activity: Option<usize>,
+
+        /// only use the course with the given (zero based) index
+        #[arg(short, long, conflicts_with_all = ["activity", "laps"])]
+        pub course: Option<usize>,
+
+        /// print a summary for each leg and transition of the multi sport session with the given (zero based) index
+        #[arg(short, long, conflicts_with_all = ["activity", "course", "laps"])]
+        pub session: Option<usize>,
+
+        /// append the average air temperature to each window, empty if the track points have none
+        #[arg(long)]
+        pub temperature: bool,
+
+        /// print device reported lap summaries next to the values calculated from the track points
+        #[arg(short, long)]
+        pub laps: bool,
+
+        /// drop invalid track point fields and skip track points without valid time instead of failing, print a
+        /// warning summary to stderr
+        #[arg(long)]
+        pub lenient: bool,
+
+        /// match elements by name if none is in the expected namespace, e.g., for files with wrong extension
+        /// namespaces
+        #[arg(long)]
+        pub permissive: bool,
+
+        /// timezone for timestamps without UTC designator or offset: utc, local or an offset like +02:00
+        #[arg(long, default_value_t = FallbackTimezone::Utc)]
+        pub timezone: FallbackTimezone,
+
+        /// validate the file against the TrainingCenterDatabase v2 structure, print violations and exit
+        #[arg(long, conflicts_with_all = ["activity", "course", "session", "laps", "debug"])]
+        pub validate: bool,
+
+        /// write all track points (of the selected activity or course) to the given GPX file and exit
+        #[arg(long, value_name = "GPX-FILE", conflicts_with_all = ["session", "laps", "debug", "validate"])]
+        pub gpx: Option<PathBuf>,
+
+        /// write all track points (of the selected activity or course) to the given FIT file and exit
+        #[arg(long, value_name = "FIT-FILE", conflicts_with_all = ["session", "laps", "debug", "validate", "gpx"])]
+        pub fit: Option<PathBuf>,
+
+        /// read TCX-FILE as CSV in the format written by -d csv, e.g., after fixing data in a spreadsheet
+        #[arg(long, conflicts_with_all = ["activity", "course", "session", "laps", "validate"])]
+        pub csv: bool,
+
+        /// write the track points (of the selected activity, course or session) to the given GeoJSON file and exit,
+        /// laps of activities and sessions are written as separate features
+        #[arg(long, value_name = "GEOJSON-FILE", conflicts_with_all = ["laps", "debug", "validate", "gpx", "fit"])]
+        pub geojson: Option<PathBuf>,
+
+        /// write one Point feature per track point instead of LineString features to the GeoJSON file
+        #[arg(long, requires = "geojson")]
+        pub geojson_points: bool,
+
+        /// write the track to the given KML file, or KMZ file if its extension is .kmz, colored by --kml-field with
+        /// placemarks at the end of each window
+        #[arg(long, value_name = "KML-FILE", conflicts_with_all = ["session", "laps", "debug", "validate", "gpx", "fit", "geojson"])]
+        pub kml: Option<PathBuf>,
+
+        /// track point field coloring the KML track, e.g., Heartrate, Power, Speed or Altitude
+        #[arg(long, default_value = "Heartrate", value_parser = parse_trk_pt_field, requires = "kml")]
+        pub kml_field: TrkPtField,
+
+        /// colors from low to high values of --kml-field as comma separated RRGGBB values
+        #[arg(long, default_value_t = Gradient::default(), requires = "kml")]
+        pub kml_gradient: Gradient,
+
+        /// write all track points of TCX-FILE, or of all TCX, GPX, FIT and CSV files in the directory TCX-FILE, to the
+        /// given Parquet file with file, activity and lap ids and exit, files are read as CSV by extension .csv
+        #[cfg(feature = "parquet")]
+        #[arg(long, value_name = "PARQUET-FILE", conflicts_with_all = ["activity", "course", "session", "laps", "debug", "validate", "gpx", "fit", "geojson", "kml", "csv"])]
+        pub parquet: Option<PathBuf>,
+    }
+
+    fn parse_trk_pt_field(s: &str) -> Result<TrkPtField, String> {
+        TRK_PT_FIELD
+            .iter()
+            .find(|field| field.as_ref().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| {
+                let fields = TRK_PT_FIELD.iter().map(|f| f.as_ref()).collect::<Vec<_>>();
+                format!(
+                    "'{}' is not a track point field, expected one of {}",
+                    s,
+                    fields.join(", ")
+                )
+            })
     }
 
     fn parse_f64_non_neg(s: &str) -> Result<f64, String> {
@@ -86,11 +185,11 @@ mod cli {
             match mode_s.to_lowercase().as_str() {
                 "length" => match value_s.parse() {
                     Ok(length) => Ok(Grouping::Length(group_by, length)),
-                    Err(e) => Err(format!("Parse error: {}", e.to_string())),
+                    Err(e) => Err(format!("Parse error: {}", e)),
                 },
                 "count" => match value_s.parse() {
                     Ok(count) => Ok(Grouping::Count(group_by, count)),
-                    Err(e) => Err(format!("Parse error: {}", e.to_string())),
+                    Err(e) => Err(format!("Parse error: {}", e)),
                 },
                 _ => Err(format!(
                     "'{}' is not a valid mode part. Expected {{length|count}}",
@@ -102,6 +201,7 @@ mod cli {
 
     #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
     pub enum Debug {
+        #[cfg(feature = "serde")]
         Json,
         Csv,
     }
@@ -111,7 +211,13 @@ mod cli {
 
         fn from_str(s: &str) -> Result<Self, Self::Err> {
             match s.to_lowercase().as_str() {
+                #[cfg(feature = "serde")]
                 "json" => Ok(Debug::Json),
+                #[cfg(not(feature = "serde"))]
+                "json" => Err(
+                    "JSON debug output requires the serde feature, build with --features serde"
+                        .to_string(),
+                ),
                 "csv" => Ok(Debug::Csv),
                 _ => Err(format!("'{}' is not a valid debug option.", s)),
             }
@@ -129,51 +235,49 @@ mod cli {
 mod debug {
     use super::cli::Debug;
     use super::*;
-    use std::fs::File;
+    use std::{collections::BTreeSet, fs::File};
 
     fn create_file(debug: &Debug) -> Result<File, std::io::Error> {
         match debug {
+            #[cfg(feature = "serde")]
             Debug::Json => File::create("debug.json"),
             Debug::Csv => File::create("debug.csv"),
         }
     }
 
-    fn debug_json(mut file: File, points: Vec<Trackpoint>) -> Result<(), Box<dyn Error>> {
-        // header
-        writeln!(file, "[")?;
-
-        // body
-        let mut first = true;
-        for point in points {
-            if first {
-                first = false;
-            } else {
-                writeln!(file, ",")?;
-            }
-
-            write!(file, "{{\"Time\": \"{}\"", point.time)?;
-            for field in &TRK_PT_FIELD {
-                write!(file, ", \"{}\": ", field.as_ref())?;
-                match point[field] {
-                    Some(v) => write!(file, "{}", v)?,
-                    None => write!(file, "null")?,
-                }
-            }
-            write!(file, "}}")?;
-        }
+    /// keys of all extension values in any point, each one is an extra column
+    fn extension_keys(points: &[Trackpoint]) -> BTreeSet<String> {
+        points
+            .iter()
+            .flat_map(|p| p.extensions.keys().cloned())
+            .collect()
+    }
 
-        // footer
-        writeln!(file, "]")?;
+    #[cfg(feature = "serde")]
+    fn debug_json(file: File, points: Vec<Trackpoint>) -> Result<(), Box<dyn Error>> {
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, &points)?;
+        writeln!(writer)?;
+        writer.flush()?;
 
         Ok(())
     }
 
     fn debug_csv(mut file: File, points: Vec<Trackpoint>) -> Result<(), Box<dyn Error>> {
+        let keys = extension_keys(&points);
+
         // header
         write!(file, "Time")?;
         for field in &TRK_PT_FIELD {
             write!(file, ",{}", field.as_ref())?;
         }
+        for key in &keys {
+            if key.contains([',', '"']) {
+                write!(file, ",\"{}\"", key.replace('"', "\"\""))?;
+            } else {
+                write!(file, ",{}", key)?;
+            }
+        }
         writeln!(file)?;
 
         // body
@@ -185,6 +289,12 @@ mod debug {
                     write!(file, "{}", v)?;
                 }
             }
+            for key in &keys {
+                write!(file, ",")?;
+                if let Some(v) = point.extensions.get(key) {
+                    write!(file, "{}", v)?;
+                }
+            }
             writeln!(file)?;
         }
 
@@ -198,6 +308,7 @@ mod debug {
         println!("Debugging, {} points to {:?}", points.len(), file);
 
         match debug {
+            #[cfg(feature = "serde")]
             Debug::Json => debug_json(file, points)?,
             Debug::Csv => debug_csv(file, points)?,
         }
@@ -235,6 +346,10 @@ struct Values {
     elevation: f64,
     power: f64,
     heartrate: f64,
+    /// time integral of air temperature, only over durations where it is known
+    temperature: f64,
+    /// duration with known air temperature
+    temperature_duration: f64,
 }
 
 impl Values {
@@ -246,6 +361,8 @@ impl Values {
             elevation: self.elevation + other.elevation,
             power: self.power + other.power,
             heartrate: self.heartrate + other.heartrate,
+            temperature: self.temperature + other.temperature,
+            temperature_duration: self.temperature_duration + other.temperature_duration,
         }
     }
 
@@ -257,6 +374,8 @@ impl Values {
             elevation: f * self.elevation,
             power: f * self.power,
             heartrate: f * self.heartrate,
+            temperature: f * self.temperature,
+            temperature_duration: f * self.temperature_duration,
         }
     }
 
@@ -268,6 +387,8 @@ impl Values {
             elevation: 0.0,
             power: 0.0,
             heartrate: 0.0,
+            temperature: 0.0,
+            temperature_duration: 0.0,
         }
     }
 
@@ -283,9 +404,17 @@ impl Values {
                     .expect("UNREACHABLE! Points w/o altitude filtered out"))
             .max(0.0),
             power: (n.power.unwrap_or(0.0) + m.power.unwrap_or(0.0)) / 2.0
-                * (GroupBy::Duration.delta(m, n) as f64),
+                * GroupBy::Duration.delta(m, n),
             heartrate: (n.heartrate.unwrap_or(0.0) + m.heartrate.unwrap_or(0.0)) / 2.0
-                * (GroupBy::Duration.delta(m, n) as f64),
+                * GroupBy::Duration.delta(m, n),
+            temperature: match (m.air_temperature, n.air_temperature) {
+                (Some(t_m), Some(t_n)) => (t_m + t_n) / 2.0 * GroupBy::Duration.delta(m, n),
+                _ => 0.0,
+            },
+            temperature_duration: match (m.air_temperature, n.air_temperature) {
+                (Some(_), Some(_)) => GroupBy::Duration.delta(m, n),
+                _ => 0.0,
+            },
         }
     }
 }
@@ -307,8 +436,8 @@ impl Qdh {
             let f = (group_length - self.distance) / inc_distance;
             self.increment(f * inc_distance, f * inc_elevation);
             self.flush();
-            inc_distance = (1.0 - f) * inc_distance;
-            inc_elevation = (1.0 - f) * inc_elevation;
+            inc_distance *= 1.0 - f;
+            inc_elevation *= 1.0 - f;
         }
 
         self.increment(inc_distance, inc_elevation);
@@ -342,12 +471,23 @@ impl Qdh {
     }
 }
 
-fn write_window(vals: &Values, qdh: &Qdh, pretty: bool) {
-    // group_length, distance, duration, elevation, power, heartrate
+fn write_window(vals: &Values, qdh: &Qdh, pretty: bool, temperature: bool) {
+    // average air temperature if requested, empty or - if unknown
+    let average =
+        (vals.temperature_duration > 0.0).then(|| vals.temperature / vals.temperature_duration);
+    let temperature = match (temperature, average) {
+        (false, _) => String::new(),
+        (true, Some(t)) if pretty => format!(", {:5.1}°C", t),
+        (true, None) if pretty => ", -".to_string(),
+        (true, Some(t)) => format!(",{:5.1}", t),
+        (true, None) => ",".to_string(),
+    };
+
+    // group_length, distance, duration, elevation, power, heartrate, temperature
     if pretty {
         // print human readable
         println!(
-            "{:6.2}W / {:6.2}bpm for {:8.2}s ({:7.3}km, {:5.2}km/h, {:4.0}m, {:5.1} m/km, QDH: {:6.1})",
+            "{:6.2}W / {:6.2}bpm for {:8.2}s ({:7.3}km, {:5.2}km/h, {:4.0}m, {:5.1} m/km, QDH: {:6.1}{})",
             vals.power / vals.duration,
             vals.heartrate / vals.duration,
             vals.duration,
@@ -355,12 +495,13 @@ fn write_window(vals: &Values, qdh: &Qdh, pretty: bool) {
             vals.distance / vals.duration * 3.6,
             vals.elevation,
             vals.elevation / vals.distance * 1000.0,
-            qdh.qdh
+            qdh.qdh,
+            temperature
         )
     } else {
         // print CSV style
         println!(
-            "{:6.2}{sep}{:6.2}{sep}{:8.2}{sep}{:7.3}{sep}{:5.2}{sep}{:4.0}{sep}{:5.1}{sep}{:6.1}",
+            "{:6.2}{sep}{:6.2}{sep}{:8.2}{sep}{:7.3}{sep}{:5.2}{sep}{:4.0}{sep}{:5.1}{sep}{:6.1}{}",
             vals.power / vals.duration,
             vals.heartrate / vals.duration,
             vals.duration,
@@ -369,28 +510,437 @@ fn write_window(vals: &Values, qdh: &Qdh, pretty: bool) {
             vals.elevation,
             vals.elevation / vals.distance * 1000.0,
             qdh.qdh,
+            temperature,
             sep = ','
         )
     }
 }
 
+fn write_laps(laps: &[&Lap], filter: fn(&Trackpoint) -> bool, pretty: bool) {
+    let fmt = |v: Option<f64>, width: usize, precision: usize| match v {
+        Some(v) => format!("{:width$.precision$}", v),
+        None if pretty => format!("{:>width$}", "-"),
+        None => String::new(),
+    };
+
+    for (k, lap) in laps.iter().enumerate() {
+        // values calculated from track points
+        let points = Trackpoint::collect(lap.trackpoints(), filter);
+        let vals = points
+            .iter()
+            .zip(points.iter().skip(1))
+            .fold(Values::zero(), |vals, (m, n)| {
+                vals.add(&Values::delta(m, n, GroupBy::Duration))
+            });
+
+        // time averages, not available for laps with less than two points
+        let average = |integral: f64| (vals.duration > 0.0).then(|| integral / vals.duration);
+
+        // reported and calculated values (duration, distance, heartrate, power)
+        let values = [
+            (lap.total_time, Some(vals.duration), 8, 2),
+            (
+                lap.distance.map(|d| d / 1000.0),
+                Some(vals.distance / 1000.0),
+                7,
+                3,
+            ),
+            (lap.average_heartrate, average(vals.heartrate), 6, 2),
+            (lap.average_power, average(vals.power), 6, 2),
+        ]
+        .map(|(reported, calculated, width, precision)| {
+            (
+                fmt(reported, width, precision),
+                fmt(calculated, width, precision),
+            )
+        });
+
+        if pretty {
+            // print human readable, calculated values in parentheses
+            println!(
+                "Lap {:3}: {}s ({}s), {}km ({}km), {}bpm ({}bpm), {}W ({}W)",
+                k,
+                values[0].0,
+                values[0].1,
+                values[1].0,
+                values[1].1,
+                values[2].0,
+                values[2].1,
+                values[3].0,
+                values[3].1
+            )
+        } else {
+            // print CSV style
+            println!(
+                "{}{sep}{}{sep}{}{sep}{}{sep}{}{sep}{}{sep}{}{sep}{}{sep}{}",
+                k,
+                values[0].0,
+                values[0].1,
+                values[1].0,
+                values[1].1,
+                values[2].0,
+                values[2].1,
+                values[3].0,
+                values[3].1,
+                sep = ','
+            )
+        }
+    }
+}
+
+fn write_segments(
+    session: &MultiSportSession,
+    filter: fn(&Trackpoint) -> bool,
+    qdh_length: f64,
+    pretty: bool,
+    temperature: bool,
+) {
+    let mut transitions = 0;
+    for segment in session.segments() {
+        // sport for legs, T1, T2, ... for transitions
+        let label = match segment {
+            Segment::Sport(activity) => activity
+                .sport
+                .map_or("Unknown".to_string(), |s| s.as_ref().to_string()),
+            Segment::Transition(_) => {
+                transitions += 1;
+                format!("T{}", transitions)
+            }
+        };
+
+        let points = Trackpoint::collect(segment.trackpoints(), filter);
+        let mut values = Values::zero();
+        let mut qdh = Qdh::zero();
+        for (m, n) in points.iter().zip(points.iter().skip(1)) {
+            let incs = Values::delta(m, n, GroupBy::Duration);
+            qdh.update(incs.distance, incs.elevation, qdh_length, false);
+            values = values.add(&incs);
+        }
+        qdh.update(0.0, 0.0, qdh_length, true);
+
+        if pretty {
+            print!("{:>7}: ", label);
+        } else {
+            print!("{},", label);
+        }
+        write_window(&values, &qdh, pretty, temperature);
+    }
+}
+
+/// Parquet output of track points of files or directories
+#[cfg(feature = "parquet")]
+mod export {
+    use super::*;
+    use parquet::arrow::ArrowWriter;
+    use std::path::Path;
+
+    /// write the track points of each file to its own record batch, files that cannot be read are reported and skipped
+    pub fn write_parquet(
+        cli: &cli::Cli,
+        path: &Path,
+        context: &mut ParseContext,
+    ) -> Result<(), Box<dyn Error>> {
+        let files = match cli.path.is_dir() {
+            true => TrackpointBatchBuilder::files(&cli.path)?,
+            false => vec![cli.path.clone()],
+        };
+
+        let mut writer =
+            ArrowWriter::try_new(File::create(path)?, TrackpointBatchBuilder::schema(), None)?;
+        let mut builder = TrackpointBatchBuilder::new();
+        let mut failed = 0;
+        for file in &files {
+            match builder.append_path(file, context) {
+                Ok(()) => writer.write(&builder.finish())?,
+                Err(err) => {
+                    eprintln!("{}: {}", file.display(), err);
+                    failed += 1;
+                }
+            }
+        }
+        writer.close()?;
+        write_parse_report(context);
+
+        match failed {
+            0 => Ok(()),
+            _ => Err(format!("{} of {} files could not be read", failed, files.len()).into()),
+        }
+    }
+}
+
+/// print a summary of warnings and timestamp interpretations to stderr
+///
+/// Individual warnings are printed up to a limit. Interpretations are only printed if any timestamp was not in UTC.
+fn write_parse_report(context: &ParseContext) {
+    const LIMIT: usize = 10;
+
+    if context
+        .interpretations
+        .keys()
+        .any(|i| *i != TimeInterpretation::Utc)
+    {
+        let mut interpretations = context
+            .interpretations
+            .iter()
+            .map(|(i, n)| format!("{} {}", n, i))
+            .collect::<Vec<_>>();
+        interpretations.sort();
+        eprintln!("Timestamps: {}", interpretations.join(", "));
+    }
+
+    let warnings = &context.warnings;
+    if warnings.is_empty() {
+        return;
+    }
+
+    let count = |recovery| warnings.iter().filter(|w| w.recovery == recovery).count();
+    eprintln!(
+        "{} warnings: {} fields dropped, {} trackpoints skipped",
+        warnings.len(),
+        count(Recovery::FieldDropped),
+        count(Recovery::TrackpointSkipped)
+    );
+    for warning in warnings.iter().take(LIMIT) {
+        eprintln!("  {}", warning);
+    }
+    if warnings.len() > LIMIT {
+        eprintln!("  ... and {} more", warnings.len() - LIMIT);
+    }
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     // parse command line
     let cli = cli::Cli::parse();
 
     // get points (filtered if not debug mode)
-    let filter: fn(&Trackpoint) -> bool = if cli.debug.is_some() {
-        |_| true
-    } else {
-        |t| t.altitude.is_some() && t.distance.is_some()
+    let filter: fn(&Trackpoint) -> bool =
+        if cli.debug.is_some() || cli.gpx.is_some() || cli.fit.is_some() || cli.geojson.is_some() {
+            |_| true
+        } else {
+            |t| t.altitude.is_some() && t.distance.is_some()
+        };
+
+    if cli.validate {
+        // validate and exit
+        let violations = validate(&fs::read_to_string(&cli.path)?.parse()?);
+        if violations.is_empty() {
+            println!("{}: valid", cli.path.display());
+            return Ok(());
+        }
+        for violation in &violations {
+            println!("{}", violation);
+        }
+        return Err(format!("{} violations found", violations.len()).into());
+    }
+
+    let mut context = ParseContext {
+        mode: if cli.lenient {
+            ParseMode::Lenient
+        } else {
+            ParseMode::Strict
+        },
+        timezone: cli.timezone,
+        namespaces: if cli.permissive {
+            NamespaceMatching::Permissive
+        } else {
+            NamespaceMatching::Strict
+        },
+        ..Default::default()
+    };
+
+    #[cfg(feature = "parquet")]
+    if let Some(path) = &cli.parquet {
+        // write all points to Parquet and exit
+        return export::write_parquet(&cli, path, &mut context);
+    }
+
+    let has_extension = |extension: &str| {
+        cli.path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+    };
+
+    if cli.csv {
+        // CSV has no structure, only points
+        let points =
+            Trackpoint::from_csv_with(&fs::read_to_string(&cli.path)?, filter, &mut context)?;
+        write_parse_report(&context);
+        return analyze(&cli, points);
+    }
+
+    if has_extension("gpx") {
+        // GPX has no activities, courses, sessions or laps
+        if cli.activity.is_some() || cli.course.is_some() || cli.session.is_some() || cli.laps {
+            return Err("Activities, courses, sessions and laps are not supported for GPX".into());
+        }
+        let points = Trackpoint::from_gpx_with(
+            &fs::read_to_string(&cli.path)?.parse()?,
+            filter,
+            &mut context,
+        )?;
+        write_parse_report(&context);
+        return analyze(&cli, points);
+    }
+
+    if has_extension("fit") {
+        // only record messages of FIT files are decoded
+        if cli.activity.is_some() || cli.course.is_some() || cli.session.is_some() || cli.laps {
+            return Err("Activities, courses, sessions and laps are not supported for FIT".into());
+        }
+        let points = Trackpoint::from_fit_with(&fs::read(&cli.path)?, filter, &mut context)?;
+        write_parse_report(&context);
+        return analyze(&cli, points);
+    }
+
+    if cli.activity.is_none()
+        && cli.course.is_none()
+        && cli.session.is_none()
+        && !cli.laps
+        && cli.geojson.is_none()
+    {
+        // no structure required, stream points without building a DOM
+        let mut reader =
+            TrackpointReader::with_context(BufReader::new(File::open(&cli.path)?), context);
+        let mut points = reader
+            .by_ref()
+            .filter(|t| t.as_ref().map_or(true, filter))
+            .collect::<Result<Vec<_>, _>>()?;
+        write_parse_report(reader.context());
+
+        // remove duplicates
+        points.dedup();
+
+        return analyze(&cli, points);
+    }
+
+    let tcx = Tcx::parse_with(&fs::read_to_string(&cli.path)?.parse()?, &mut context)?;
+    write_parse_report(&context);
+    let activity = cli
+        .activity
+        .map(|k| {
+            tcx.activities.get(k).ok_or_else(|| {
+                format!(
+                    "No activity with index {}, found {} activities",
+                    k,
+                    tcx.activities.len()
+                )
+            })
+        })
+        .transpose()?;
+    let course = cli
+        .course
+        .map(|k| {
+            tcx.courses.get(k).ok_or_else(|| {
+                format!(
+                    "No course with index {}, found {} courses",
+                    k,
+                    tcx.courses.len()
+                )
+            })
+        })
+        .transpose()?;
+    let session = cli
+        .session
+        .map(|k| {
+            tcx.multi_sport_sessions.get(k).ok_or_else(|| {
+                format!(
+                    "No multi sport session with index {}, found {} sessions",
+                    k,
+                    tcx.multi_sport_sessions.len()
+                )
+            })
+        })
+        .transpose()?;
+    let points = match (activity, course, session) {
+        (Some(activity), _, _) => Trackpoint::collect(activity.trackpoints(), filter),
+        (_, Some(course), _) => Trackpoint::collect(course.trackpoints(), filter),
+        (_, _, Some(session)) => Trackpoint::collect(session.trackpoints(), filter),
+        _ => Trackpoint::collect(tcx.trackpoints(), filter),
     };
-    let points = Trackpoint::from_tcx(&fs::read_to_string(cli.path)?.parse()?, filter)?;
 
+    if let Some(path) = &cli.geojson {
+        // laps of the selected activity or session, courses and files without laps are written as track points
+        let laps = match (activity, course, session) {
+            (Some(activity), _, _) => activity.laps.iter().collect::<Vec<_>>(),
+            (_, Some(_), _) => Vec::new(),
+            (_, _, Some(session)) => session
+                .segments()
+                .flat_map(|segment| match segment {
+                    Segment::Sport(activity) => activity.laps.iter().collect(),
+                    Segment::Transition(lap) => vec![lap],
+                })
+                .collect(),
+            _ => tcx.activities.iter().flat_map(|a| a.laps.iter()).collect(),
+        };
+        if !laps.is_empty() {
+            // write laps as separate GeoJSON features and exit
+            let mut file = BufWriter::new(File::create(path)?);
+            Lap::write_geojson(laps, filter, geojson_layout(&cli), &mut file)?;
+            file.flush()?;
+            return Ok(());
+        }
+    }
+
+    if cli.debug.is_none() && cli.geojson.is_none() {
+        if let Some(session) = session {
+            // write segment summaries and exit
+            write_segments(session, filter, cli.qdh, cli.pretty, cli.temperature);
+            return Ok(());
+        }
+
+        if cli.laps {
+            // write lap summaries and exit
+            let laps = match activity {
+                Some(activity) => activity.laps.iter().collect::<Vec<_>>(),
+                None => tcx.activities.iter().flat_map(|a| a.laps.iter()).collect(),
+            };
+            write_laps(&laps, filter, cli.pretty);
+            return Ok(());
+        }
+    }
+
+    analyze(&cli, points)
+}
+
+/// get the GeoJSON layout selected on the command line
+fn geojson_layout(cli: &cli::Cli) -> GeoJsonLayout {
+    match cli.geojson_points {
+        true => GeoJsonLayout::Points,
+        false => GeoJsonLayout::LineString,
+    }
+}
+
+/// write debug output, a GPX, FIT or GeoJSON file or windows grouped as specified on the command line
+fn analyze(cli: &cli::Cli, points: Vec<Trackpoint>) -> Result<(), Box<dyn Error>> {
     if let Some(debug) = cli.debug {
         // write debug output and exit
         return debug::debug(&debug, points);
     }
 
+    if let Some(path) = &cli.gpx {
+        // write GPX and exit
+        let mut file = BufWriter::new(File::create(path)?);
+        Trackpoint::write_gpx(&points, &mut file)?;
+        file.flush()?;
+        return Ok(());
+    }
+
+    if let Some(path) = &cli.geojson {
+        // write GeoJSON and exit
+        let mut file = BufWriter::new(File::create(path)?);
+        Trackpoint::write_geojson(&points, geojson_layout(cli), &mut file)?;
+        file.flush()?;
+        return Ok(());
+    }
+
+    if let Some(path) = &cli.fit {
+        // write FIT and exit
+        let mut file = BufWriter::new(File::create(path)?);
+        Trackpoint::write_fit(&points, &mut file)?;
+        file.flush()?;
+        return Ok(());
+    }
+
     // get group by from CLI
     let group_by = match cli.grouping {
         cli::Grouping::Count(group_by, _) => group_by,
@@ -412,6 +962,8 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let mut values = Values::zero();
     let mut qdh = Qdh::zero();
+    let mut placemarks = Vec::new();
+    let mut windows = 0;
 
     for (m, n) in points.iter().zip(points.iter().skip(1)) {
         // increments (group_length, distance, duration, elevation, power, heartrate)
@@ -423,7 +975,10 @@ fn main() -> Result<(), Box<dyn Error>> {
             qdh.update(incs.distance * f, incs.elevation * f, cli.qdh, true);
 
             // print group
-            write_window(&values.add(&incs.mult(f)), &qdh, cli.pretty);
+            let window = values.add(&incs.mult(f));
+            write_window(&window, &qdh, cli.pretty, cli.temperature);
+            placemarks.extend(window_placemark(&window, windows, m, n, f));
+            windows += 1;
 
             // reset Qdh and Values
             qdh = Qdh::zero();
@@ -444,8 +999,68 @@ fn main() -> Result<(), Box<dyn Error>> {
     // print last group if applicable
     if values.group_len > 1e-6 * group_len {
         qdh.update(0.0, 0.0, cli.qdh, true);
-        write_window(&values, &qdh, cli.pretty);
+        write_window(&values, &qdh, cli.pretty, cli.temperature);
+        if let Some(last) = points.last() {
+            placemarks.extend(window_placemark(&values, windows, last, last, 1.0));
+        }
+    }
+
+    if let Some(path) = &cli.kml {
+        // write colored track with window placemarks
+        let mut file = BufWriter::new(File::create(path)?);
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("kmz"))
+        {
+            Trackpoint::write_kmz(
+                &points,
+                cli.kml_field,
+                &cli.kml_gradient,
+                &placemarks,
+                &mut file,
+            )?;
+        } else {
+            Trackpoint::write_kml(
+                &points,
+                cli.kml_field,
+                &cli.kml_gradient,
+                &placemarks,
+                &mut file,
+            )?;
+        }
+        file.flush()?;
     }
 
     Ok(())
 }
+
+/// placemark at the end of the window with index `k`, which lies at fraction `f` between the points `m` and `n`
+///
+/// There is no placemark if either point has no position.
+fn window_placemark(
+    vals: &Values,
+    k: usize,
+    m: &Trackpoint,
+    n: &Trackpoint,
+    f: f64,
+) -> Option<KmlPlacemark> {
+    let (m_lat, m_lon, n_lat, n_lon) = (m.latitude?, m.longitude?, n.latitude?, n.longitude?);
+    // averages are unknown for windows without duration
+    let average = |integral: f64| match vals.duration > 0.0 {
+        true => format!("{:.0}", integral / vals.duration),
+        false => "-".to_string(),
+    };
+    Some(KmlPlacemark {
+        name: format!("Window {}", k + 1),
+        description: Some(format!(
+            "{:.3}km in {:.0}s, {:.0}m climbed, {}W, {}bpm",
+            vals.distance / 1000.0,
+            vals.duration,
+            vals.elevation,
+            average(vals.power),
+            average(vals.heartrate),
+        )),
+        latitude: m_lat + (n_lat - m_lat) * f,
+        longitude: m_lon + (n_lon - m_lon) * f,
+    })
+}
@@ -0,0 +1,157 @@
+use crate::Tag;
+
+/// XML namespaces of TCX and GPX files and their extensions and of KML files
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Namespace {
+    /// Training Center Database v2, the namespace of the TCX schema itself
+    TrainingCenterDatabaseV2,
+    /// Activity Extension v2 with [`<TPX>`][Tag::TPX] and [`<LX>`][Tag::LX]
+    ActivityExtensionV2,
+    /// Track Point Extension v1 with [`<TrackPointExtension>`][Tag::TrackPointExtension]
+    TrackPointExtensionV1,
+    /// Track Point Extension v2 with [`<TrackPointExtension>`][Tag::TrackPointExtension], adds speed, course and
+    /// bearing to v1
+    TrackPointExtensionV2,
+    /// GPX 1.1, the namespace of GPX files
+    Gpx11,
+    /// Garmin's Power Extension v1 for GPX with [`<PowerInWatts>`][Tag::PowerInWatts]
+    PowerExtensionV1,
+    /// Cluetrust GPX data extension v1.0 with [`<hr>`][Tag::Hr], [`<cadence>`][Tag::GpxdataCadence] and
+    /// [`<temp>`][Tag::Temp]
+    ClueTrustGpxData,
+    /// KML 2.2, the namespace of KML files written by [`Trackpoint::to_kml`][crate::Trackpoint::to_kml]
+    Kml22,
+}
+
+/// how elements are matched to [`Tag`]s, see [`ParseContext::namespaces`][crate::ParseContext::namespaces]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum NamespaceMatching {
+    /// An element only matches a tag if it has the tag's name and is in one of the tag's
+    /// [`namespaces`][Tag::namespaces]
+    ///
+    /// Elements with a known name in another namespace, e.g., a third party `<Speed>`, are not read as fields, but
+    /// collected into [`Trackpoint::extensions`][crate::Trackpoint::extensions].
+    #[default]
+    Strict,
+    /// Elements match by name in any namespace if no sibling matches strictly
+    ///
+    /// This reads documents without or with unexpected namespaces, elements from the expected namespaces still take
+    /// precedence over elements with the same name from third party extensions.
+    Permissive,
+}
+
+impl NamespaceMatching {
+    /// Check whether an element with the given local `name` in namespace `ns` is a `tag`
+    ///
+    /// This ignores the siblings, i.e., the element matches by name alone in permissive mode.
+    pub(crate) fn matches(self, name: &str, ns: &str, tag: Tag) -> bool {
+        name == tag.as_ref()
+            && match self {
+                NamespaceMatching::Strict => {
+                    Namespace::from_uri(ns).is_some_and(|ns| tag.namespaces().contains(&ns))
+                }
+                NamespaceMatching::Permissive => true,
+            }
+    }
+}
+
+impl Namespace {
+    /// Get the namespace's URI
+    pub fn uri(&self) -> &'static str {
+        match self {
+            Namespace::TrainingCenterDatabaseV2 => {
+                "http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2"
+            }
+            Namespace::ActivityExtensionV2 => {
+                "http://www.garmin.com/xmlschemas/ActivityExtension/v2"
+            }
+            Namespace::TrackPointExtensionV1 => {
+                "http://www.garmin.com/xmlschemas/TrackPointExtension/v1"
+            }
+            Namespace::TrackPointExtensionV2 => {
+                "http://www.garmin.com/xmlschemas/TrackPointExtension/v2"
+            }
+            Namespace::Gpx11 => "http://www.topografix.com/GPX/1/1",
+            Namespace::PowerExtensionV1 => "http://www.garmin.com/xmlschemas/PowerExtension/v1",
+            Namespace::ClueTrustGpxData => "http://www.cluetrust.com/XML/GPXDATA/1/0",
+            Namespace::Kml22 => "http://www.opengis.net/kml/2.2",
+        }
+    }
+
+    /// Get the namespace for an URI, if it is known
+    pub fn from_uri(uri: &str) -> Option<Self> {
+        [
+            Namespace::TrainingCenterDatabaseV2,
+            Namespace::ActivityExtensionV2,
+            Namespace::TrackPointExtensionV1,
+            Namespace::TrackPointExtensionV2,
+            Namespace::Gpx11,
+            Namespace::PowerExtensionV1,
+            Namespace::ClueTrustGpxData,
+            Namespace::Kml22,
+        ]
+        .into_iter()
+        .find(|ns| ns.uri() == uri)
+    }
+}
+
+impl Tag {
+    /// Get the namespaces the tag is defined in
+    ///
+    /// Elements only strictly match a tag if they are in one of these namespaces, see [`TcxElement`][crate::TcxElement].
+    pub fn namespaces(&self) -> &'static [Namespace] {
+        match self {
+            Tag::TPX
+            | Tag::Speed
+            | Tag::Watts
+            | Tag::RunCadence
+            | Tag::LX
+            | Tag::AvgSpeed
+            | Tag::AvgWatts
+            | Tag::MaxWatts => &[Namespace::ActivityExtensionV2],
+            Tag::TrackPointExtension
+            | Tag::AirTemperature
+            | Tag::WaterTemperature
+            | Tag::Depth
+            | Tag::Cad => &[
+                Namespace::TrackPointExtensionV1,
+                Namespace::TrackPointExtensionV2,
+            ],
+            Tag::Hr => &[
+                Namespace::TrackPointExtensionV1,
+                Namespace::TrackPointExtensionV2,
+                Namespace::ClueTrustGpxData,
+            ],
+            Tag::TpxV2Speed | Tag::TpxV2Course | Tag::Bearing => {
+                &[Namespace::TrackPointExtensionV2]
+            }
+            Tag::Gpx
+            | Tag::Trk
+            | Tag::Trkseg
+            | Tag::Trkpt
+            | Tag::Ele
+            | Tag::GpxTime
+            | Tag::GpxExtensions
+            | Tag::Lat
+            | Tag::Lon
+            | Tag::GpxPower => &[Namespace::Gpx11],
+            Tag::PowerInWatts => &[Namespace::PowerExtensionV1],
+            Tag::GpxdataCadence | Tag::Temp => &[Namespace::ClueTrustGpxData],
+            _ => &[Namespace::TrainingCenterDatabaseV2],
+        }
+    }
+
+    /// Get the namespace elements for the tag are written in
+    ///
+    /// This is the newest of Garmin's track point extensions if the tag is defined there, the first of the tag's
+    /// [`namespaces`][Tag::namespaces] otherwise.
+    pub fn namespace(&self) -> Namespace {
+        match self
+            .namespaces()
+            .contains(&Namespace::TrackPointExtensionV2)
+        {
+            true => Namespace::TrackPointExtensionV2,
+            false => self.namespaces()[0],
+        }
+    }
+}
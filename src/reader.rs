@@ -0,0 +1,357 @@
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, Read},
+};
+
+use minidom::Element;
+use rxml::{EventRead, PullParser, ResolvedEvent};
+
+use crate::{ParseContext, Recovery, Tag, TcxError, TextPosition, Trackpoint};
+
+/// Streaming reader yielding [`Trackpoint`]s while walking through a TCX document
+///
+/// In contrast to [`Tcx::parse`][crate::Tcx::parse] and [`Trackpoint::from_tcx`], the reader does not build a DOM for
+/// the whole document. Only the [`<Trackpoint>`][Tag::Trackpoint] element currently read is kept in memory, it is
+/// then converted using [`Trackpoint::parse`], so that the same [`TrkPtField::get_tags`][crate::TrkPtField::get_tags]
+/// mapping applies.
+///
+/// Any [`<Trackpoint>`][Tag::Trackpoint] is read, regardless of where it is nested in the document. Duplicates are not
+/// removed.
+///
+/// Errors carry the index of the track point in document order and the [`TextPosition`] of its start tag. XML errors
+/// carry the position of the end of the last successfully read XML event.
+///
+/// Use [`TrackpointReader::with_context`] to read in [`ParseMode::Lenient`][crate::ParseMode::Lenient]. Skipped track
+/// points are not yielded, warnings are available from [`TrackpointReader::context`].
+///
+/// # Examples
+/// ```
+/// # use tcx::*;
+///
+/// let doc = r#"<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+///   <Activities><Activity><Lap><Track>
+///     <Trackpoint><Time>2022-12-31T12:00:00Z</Time><DistanceMeters>0</DistanceMeters></Trackpoint>
+///     <Trackpoint><Time>2022-12-31T12:00:01Z</Time><DistanceMeters>3.6</DistanceMeters></Trackpoint>
+///   </Track></Lap></Activity></Activities>
+/// </TrainingCenterDatabase>"#;
+///
+/// let distances = TrackpointReader::new(doc.as_bytes())
+///     .map(|p| p.map(|p| p.distance))
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+///
+/// assert_eq!(distances, vec![Some(0.0), Some(3.6)]);
+/// ```
+pub struct TrackpointReader<R: BufRead> {
+    parser: PullParser<LineCounter<R>>,
+    /// Elements of the current track point under construction, outermost first
+    stack: Vec<Element>,
+    /// Set after the first error, the parser does not recover from errors
+    failed: bool,
+    /// Number of bytes covered by the events read so far
+    offset: usize,
+    /// Number of track points started so far
+    count: usize,
+    /// Position of the current track point's start tag
+    start: Option<TextPosition>,
+    context: ParseContext,
+}
+
+/// Wrapper around a [`BufRead`] recording the offsets of line breaks in the consumed bytes
+///
+/// The parser consumes bytes ahead of the events it emits, so line breaks are kept until the events' offsets pass
+/// them.
+struct LineCounter<R> {
+    inner: R,
+    /// Number of bytes consumed so far
+    consumed: usize,
+    /// Offsets of consumed line breaks not yet passed
+    newlines: VecDeque<usize>,
+    /// Current line, counting from 1
+    line: usize,
+    /// Offset of the current line's first byte
+    line_start: usize,
+}
+
+impl<R> LineCounter<R> {
+    fn new(inner: R) -> Self {
+        LineCounter {
+            inner,
+            consumed: 0,
+            newlines: VecDeque::new(),
+            line: 1,
+            line_start: 0,
+        }
+    }
+
+    /// Get the position of the byte at `offset`
+    ///
+    /// Offsets must not decrease between calls.
+    fn position(&mut self, offset: usize) -> TextPosition {
+        while let Some(&newline) = self.newlines.front() {
+            if newline >= offset {
+                break;
+            }
+            self.newlines.pop_front();
+            self.line += 1;
+            self.line_start = newline + 1;
+        }
+
+        TextPosition {
+            line: self.line,
+            column: offset - self.line_start + 1,
+        }
+    }
+}
+
+impl<R: Read> Read for LineCounter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        for (k, _) in buf[..n].iter().enumerate().filter(|(_, b)| **b == b'\n') {
+            self.newlines.push_back(self.consumed + k);
+        }
+        self.consumed += n;
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for LineCounter<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if let Ok(buf) = self.inner.fill_buf() {
+            for (k, _) in buf[..amt].iter().enumerate().filter(|(_, b)| **b == b'\n') {
+                self.newlines.push_back(self.consumed + k);
+            }
+        }
+        self.consumed += amt;
+        self.inner.consume(amt);
+    }
+}
+
+impl<R: BufRead> TrackpointReader<R> {
+    /// Create a reader for the TCX document provided by `read`
+    pub fn new(read: R) -> Self {
+        TrackpointReader::with_context(read, ParseContext::default())
+    }
+
+    /// Create a reader for the TCX document provided by `read` using the given [`ParseContext`]
+    pub fn with_context(read: R, context: ParseContext) -> Self {
+        TrackpointReader {
+            parser: PullParser::new(LineCounter::new(read)),
+            stack: Vec::new(),
+            failed: false,
+            offset: 0,
+            count: 0,
+            start: None,
+            context,
+        }
+    }
+
+    /// Get the parse context including the warnings collected so far
+    pub fn context(&self) -> &ParseContext {
+        &self.context
+    }
+
+    /// Consume the reader and return the parse context
+    pub fn into_context(self) -> ParseContext {
+        self.context
+    }
+
+    fn position(&mut self) -> TextPosition {
+        let offset = self.offset;
+        self.parser.get_inner_mut().position(offset)
+    }
+}
+
+impl<R: BufRead> Iterator for TrackpointReader<R> {
+    type Item = Result<Trackpoint, TcxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        loop {
+            let event = match self.parser.read() {
+                Ok(Some(event)) => event,
+                Ok(None) => return None,
+                Err(rxml::Error::IO(e)) => {
+                    self.failed = true;
+                    return Some(Err(TcxError::Io(io::Error::new(e.kind(), e.to_string()))));
+                }
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(TcxError::Xml {
+                        source: e.into(),
+                        position: Some(self.position()),
+                    }));
+                }
+            };
+
+            let len = event.metrics().len();
+
+            match event {
+                ResolvedEvent::StartElement(_, (ns, name), _) => {
+                    let ns = ns.as_ref().map_or("", |ns| ns.as_str());
+                    let is_trackpoint = self.stack.is_empty()
+                        && self
+                            .context
+                            .namespaces
+                            .matches(name.as_str(), ns, Tag::Trackpoint);
+                    if is_trackpoint {
+                        self.start = Some(self.position());
+                        self.count += 1;
+                    }
+
+                    // start collecting at a track point, collect everything nested in it
+                    if !self.stack.is_empty() || is_trackpoint {
+                        self.stack.push(Element::builder(name.as_str(), ns).build());
+                    }
+                }
+                ResolvedEvent::Text(_, text) => {
+                    if let Some(element) = self.stack.last_mut() {
+                        element.append_text_node(text.as_str());
+                    }
+                }
+                ResolvedEvent::EndElement(_) => {
+                    if let Some(element) = self.stack.pop() {
+                        match self.stack.last_mut() {
+                            Some(parent) => {
+                                parent.append_child(element);
+                            }
+                            None => {
+                                self.offset += len;
+                                let index = self.count - 1;
+                                let start = self.start.take();
+                                let locate = |e: TcxError| {
+                                    let e = e.with_trackpoint(index);
+                                    match start {
+                                        Some(start) => e.with_position(start),
+                                        None => e,
+                                    }
+                                };
+
+                                let since = self.context.warnings.len();
+                                let result = Trackpoint::parse_with(&element, &mut self.context);
+                                self.context.update_warnings(since, locate);
+                                match result {
+                                    Ok(point) => return Some(Ok(point)),
+                                    Err(e) => {
+                                        if let Err(e) = self
+                                            .context
+                                            .recover(locate(e), Recovery::TrackpointSkipped)
+                                        {
+                                            return Some(Err(e));
+                                        }
+                                    }
+                                }
+                                continue;
+                            }
+                        }
+                    }
+                }
+                ResolvedEvent::XmlDeclaration(..) => (),
+            }
+
+            self.offset += len;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParseMode, TrkPtField};
+
+    #[test]
+    fn test_reader_matches_parse() {
+        let doc = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2" xmlns:ns3="http://www.garmin.com/xmlschemas/ActivityExtension/v2">
+          <Activities>
+            <Activity>
+              <Lap>
+                <Track>
+                  <Trackpoint>
+                    <Time>2022-12-31T12:00:00Z</Time>
+                    <Position>
+                      <LatitudeDegrees>48.6</LatitudeDegrees>
+                      <LongitudeDegrees>9.0</LongitudeDegrees>
+                    </Position>
+                    <HeartRateBpm><Value>120</Value></HeartRateBpm>
+                    <Extensions>
+                      <ns3:TPX><ns3:Watts>250</ns3:Watts></ns3:TPX>
+                    </Extensions>
+                  </Trackpoint>
+                </Track>
+              </Lap>
+            </Activity>
+          </Activities>
+          <Courses>
+            <Course>
+              <Track>
+                <Trackpoint><Time>2022-12-31T12:00:01Z</Time></Trackpoint>
+              </Track>
+            </Course>
+          </Courses>
+        </TrainingCenterDatabase>"#;
+
+        let streamed = TrackpointReader::new(doc.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let parsed = Trackpoint::from_tcx(&doc.parse().unwrap(), |_| true).unwrap();
+
+        assert_eq!(parsed, streamed);
+        assert_eq!(Some(250.0), streamed[0][&TrkPtField::Power]);
+    }
+
+    #[test]
+    fn test_reader_lenient() {
+        let doc = r#"<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+          <Trackpoint><Time>not a time</Time></Trackpoint>
+          <Trackpoint><Time>2022-12-31T12:00:01Z</Time><DistanceMeters>x</DistanceMeters></Trackpoint>
+          <Trackpoint><Time>2022-12-31T12:00:02Z</Time><DistanceMeters>7.2</DistanceMeters></Trackpoint>
+        </TrainingCenterDatabase>"#;
+
+        let mut reader =
+            TrackpointReader::with_context(doc.as_bytes(), ParseContext::new(ParseMode::Lenient));
+        let points = reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+        let distances = points.iter().map(|p| p.distance).collect::<Vec<_>>();
+        assert_eq!(vec![None, Some(7.2)], distances);
+
+        let warnings = reader.into_context().warnings;
+        assert_eq!(2, warnings.len());
+        assert_eq!(Recovery::TrackpointSkipped, warnings[0].recovery);
+        assert_eq!(Some(0), warnings[0].error.context().unwrap().trackpoint);
+        assert_eq!(Recovery::FieldDropped, warnings[1].recovery);
+        assert_eq!(
+            "Invalid number 'x' in <DistanceMeters> (field Distance) of trackpoint 1 at line 3, column 11, field dropped",
+            warnings[1].to_string()
+        );
+    }
+
+    #[test]
+    fn test_reader_error() {
+        let doc = r#"<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+          <Trackpoint><Time>not a time</Time></Trackpoint>
+          <Trackpoint><Time>2022-12-31T12:00:01Z</Time></Trackpoint>
+          <Trackpoint>"#;
+
+        let mut reader = TrackpointReader::new(doc.as_bytes());
+        assert_eq!(
+            "Invalid timestamp 'not a time' in <Time> of trackpoint 0 at line 2, column 11",
+            reader.next().unwrap().unwrap_err().to_string()
+        );
+        assert!(reader.next().unwrap().is_ok());
+        assert!(matches!(
+            reader.next().unwrap(),
+            Err(TcxError::Xml {
+                position: Some(TextPosition { line: 4, .. }),
+                ..
+            })
+        ));
+        assert!(reader.next().is_none());
+    }
+}
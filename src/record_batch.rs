@@ -0,0 +1,371 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use arrow::{
+    array::{
+        ArrayBuilder, ArrayRef, Float64Builder, StringBuilder, TimestampMicrosecondBuilder,
+        UInt32Builder,
+    },
+    datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit},
+    record_batch::RecordBatch,
+};
+
+use crate::{ParseContext, Tag, Tcx, TcxError, Trackpoint, TRK_PT_FIELD};
+
+/// Column names of the ids
+const FILE: &str = "File";
+const ACTIVITY: &str = "Activity";
+const LAP: &str = "Lap";
+
+/// Timezone of the time column, an offset needs no timezone database to be interpreted
+const UTC: &str = "+00:00";
+
+/// Extensions of the files read by [`TrackpointBatchBuilder::append_path`]
+const EXTENSIONS: [&str; 4] = ["tcx", "gpx", "fit", "csv"];
+
+/// a builder collecting track points into an Arrow [`RecordBatch`]
+///
+/// The batch has the columns
+/// - `Time`, a timestamp in microseconds with timezone `+00:00`
+/// - one nullable `Float64` column per [`TrkPtField`][crate::TrkPtField], named like the field, e.g., `Heartrate`
+/// - `File`, a nullable string identifying the file the point was read from
+/// - `Activity` and `Lap`, the nullable `UInt32` indices of the activity in the file and of the lap in the activity
+///
+/// [`extensions`][Trackpoint::extensions] are not included.
+///
+/// # Examples
+/// ```
+/// # use tcx::*;
+///
+/// let doc = r#"<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+///   <Activities><Activity Sport="Biking"><Id>2022-12-31T12:00:00Z</Id>
+///     <Lap StartTime="2022-12-31T12:00:00Z"><Track>
+///       <Trackpoint><Time>2022-12-31T12:00:00Z</Time><DistanceMeters>0.0</DistanceMeters></Trackpoint>
+///     </Track></Lap>
+///     <Lap StartTime="2022-12-31T12:00:01Z"><Track>
+///       <Trackpoint><Time>2022-12-31T12:00:01Z</Time><DistanceMeters>7.2</DistanceMeters></Trackpoint>
+///     </Track></Lap>
+///   </Activity></Activities>
+/// </TrainingCenterDatabase>"#;
+/// let tcx = Tcx::parse(&doc.parse().unwrap()).unwrap();
+///
+/// let mut builder = TrackpointBatchBuilder::new();
+/// builder.append_tcx(&tcx, Some("ride.tcx"));
+/// let batch = builder.finish();
+///
+/// assert_eq!(batch.num_rows(), 2);
+/// assert_eq!(batch.schema(), TrackpointBatchBuilder::schema());
+/// assert!(batch.column_by_name("Heartrate").is_some());
+/// ```
+#[derive(Debug)]
+pub struct TrackpointBatchBuilder {
+    time: TimestampMicrosecondBuilder,
+    fields: Vec<Float64Builder>,
+    file: StringBuilder,
+    activity: UInt32Builder,
+    lap: UInt32Builder,
+}
+
+impl Default for TrackpointBatchBuilder {
+    fn default() -> Self {
+        TrackpointBatchBuilder {
+            time: TimestampMicrosecondBuilder::new().with_timezone(UTC),
+            fields: TRK_PT_FIELD.iter().map(|_| Float64Builder::new()).collect(),
+            file: StringBuilder::new(),
+            activity: UInt32Builder::new(),
+            lap: UInt32Builder::new(),
+        }
+    }
+}
+
+impl TrackpointBatchBuilder {
+    /// Create an empty builder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the schema of the batches created
+    pub fn schema() -> SchemaRef {
+        let mut fields = vec![Field::new(
+            Tag::Time.as_ref(),
+            DataType::Timestamp(TimeUnit::Microsecond, Some(UTC.into())),
+            false,
+        )];
+        fields.extend(
+            TRK_PT_FIELD
+                .iter()
+                .map(|field| Field::new(field.as_ref(), DataType::Float64, true)),
+        );
+        fields.push(Field::new(FILE, DataType::Utf8, true));
+        fields.push(Field::new(ACTIVITY, DataType::UInt32, true));
+        fields.push(Field::new(LAP, DataType::UInt32, true));
+
+        Arc::new(Schema::new(fields))
+    }
+
+    /// Get the number of track points appended since the last [`finish`][TrackpointBatchBuilder::finish]
+    pub fn len(&self) -> usize {
+        self.time.len()
+    }
+
+    /// Check if no track points were appended since the last [`finish`][TrackpointBatchBuilder::finish]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Append a track point with the ids of its file, activity and lap
+    pub fn append(
+        &mut self,
+        point: &Trackpoint,
+        file: Option<&str>,
+        activity: Option<u32>,
+        lap: Option<u32>,
+    ) {
+        self.time.append_value(point.time.timestamp_micros());
+        for (builder, field) in self.fields.iter_mut().zip(&TRK_PT_FIELD) {
+            builder.append_option(point[field]);
+        }
+        self.file.append_option(file);
+        self.activity.append_option(activity);
+        self.lap.append_option(lap);
+    }
+
+    /// Append track points of a file without activity and lap ids, e.g., from [`Trackpoint::from_gpx`]
+    pub fn append_points<'a>(
+        &mut self,
+        points: impl IntoIterator<Item = &'a Trackpoint>,
+        file: Option<&str>,
+    ) {
+        for point in points {
+            self.append(point, file, None, None);
+        }
+    }
+
+    /// Append the track points of all activities of a TCX document with activity and lap ids
+    ///
+    /// Consecutive duplicates are removed like in [`Trackpoint::collect`], i.e., a point repeated as the first point
+    /// of the next lap keeps the ids of the lap it first appeared in. Track points of multi sport sessions and courses
+    /// are not appended.
+    pub fn append_tcx(&mut self, tcx: &Tcx, file: Option<&str>) {
+        let mut previous = None;
+        for (k, activity) in tcx.activities.iter().enumerate() {
+            for (l, lap) in activity.laps.iter().enumerate() {
+                for point in lap.trackpoints() {
+                    if previous != Some(point) {
+                        self.append(point, file, Some(k as u32), Some(l as u32));
+                    }
+                    previous = Some(point);
+                }
+            }
+        }
+    }
+
+    /// Append the track points of a file, or of all files in a directory, with the file's path as file id
+    ///
+    /// The format is chosen by extension: `.gpx` is read as GPX, `.fit` as FIT, `.csv` as CSV and everything else as
+    /// TCX. Files in a directory are read in the order of [`TrackpointBatchBuilder::files`], the first file that
+    /// cannot be read is an error. A file is only appended if it was read completely.
+    pub fn append_path(&mut self, path: &Path, context: &mut ParseContext) -> Result<(), TcxError> {
+        if path.is_dir() {
+            for file in Self::files(path)? {
+                self.append_path(&file, context)?;
+            }
+            return Ok(());
+        }
+
+        let file = path.display().to_string();
+        let has_extension = |extension: &str| {
+            path.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+        };
+        if has_extension("gpx") {
+            let points =
+                Trackpoint::from_gpx_with(&fs::read_to_string(path)?.parse()?, |_| true, context)?;
+            self.append_points(&points, Some(&file));
+        } else if has_extension("fit") {
+            let points = Trackpoint::from_fit_with(&fs::read(path)?, |_| true, context)?;
+            self.append_points(&points, Some(&file));
+        } else if has_extension("csv") {
+            let points = Trackpoint::from_csv_with(&fs::read_to_string(path)?, |_| true, context)?;
+            self.append_points(&points, Some(&file));
+        } else {
+            let tcx = Tcx::parse_with(&fs::read_to_string(path)?.parse()?, context)?;
+            self.append_tcx(&tcx, Some(&file));
+        }
+        Ok(())
+    }
+
+    /// Get the files in directory `dir` read by [`TrackpointBatchBuilder::append_path`]
+    ///
+    /// These are the files with extension `.tcx`, `.gpx`, `.fit` or `.csv`, sorted by path.
+    pub fn files(dir: &Path) -> Result<Vec<PathBuf>, io::Error> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file()
+                && path.extension().is_some_and(|ext| {
+                    EXTENSIONS
+                        .iter()
+                        .any(|extension| ext.eq_ignore_ascii_case(extension))
+                })
+            {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Create a record batch of all track points appended and reset the builder
+    pub fn finish(&mut self) -> RecordBatch {
+        let mut columns: Vec<ArrayRef> = vec![Arc::new(self.time.finish())];
+        columns.extend(
+            self.fields
+                .iter_mut()
+                .map(|builder| Arc::new(builder.finish()) as ArrayRef),
+        );
+        columns.push(Arc::new(self.file.finish()));
+        columns.push(Arc::new(self.activity.finish()));
+        columns.push(Arc::new(self.lap.finish()));
+
+        RecordBatch::try_new(Self::schema(), columns).expect("columns match the schema")
+    }
+}
+
+impl Trackpoint {
+    /// Create an Arrow record batch of track points without file, activity and lap ids, see
+    /// [`TrackpointBatchBuilder`]
+    pub fn to_record_batch<'a>(points: impl IntoIterator<Item = &'a Trackpoint>) -> RecordBatch {
+        let mut builder = TrackpointBatchBuilder::new();
+        builder.append_points(points, None);
+        builder.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::{
+        array::{Array, AsArray},
+        datatypes::{Float64Type, TimestampMicrosecondType, UInt32Type},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_record_batch() {
+        let points = [
+            Trackpoint {
+                time: "2022-12-31T12:00:00.5Z".parse().unwrap(),
+                heartrate: Some(120.0),
+                power: Some(f64::NAN),
+                ..Default::default()
+            },
+            Trackpoint {
+                time: "2022-12-31T12:00:01Z".parse().unwrap(),
+                ..Default::default()
+            },
+        ];
+
+        let mut builder = TrackpointBatchBuilder::new();
+        builder.append_points(&points, Some("a.gpx"));
+        builder.append(&points[1], None, Some(2), Some(3));
+        assert_eq!(builder.len(), 3);
+        let batch = builder.finish();
+        assert!(builder.is_empty());
+
+        assert_eq!(batch.num_rows(), 3);
+        assert_eq!(batch.num_columns(), 1 + TRK_PT_FIELD.len() + 3);
+
+        let time = batch.column(0).as_primitive::<TimestampMicrosecondType>();
+        assert_eq!(time.value(0), points[0].time.timestamp_micros());
+        assert_eq!(time.value(0) % 1_000_000, 500_000);
+
+        let heartrate = batch
+            .column_by_name("Heartrate")
+            .unwrap()
+            .as_primitive::<Float64Type>();
+        assert_eq!(heartrate.value(0), 120.0);
+        assert!(heartrate.is_null(1));
+        let power = batch
+            .column_by_name("Power")
+            .unwrap()
+            .as_primitive::<Float64Type>();
+        assert!(power.value(0).is_nan());
+
+        let file = batch.column_by_name(FILE).unwrap().as_string::<i32>();
+        assert_eq!(file.value(1), "a.gpx");
+        assert!(file.is_null(2));
+        let lap = batch
+            .column_by_name(LAP)
+            .unwrap()
+            .as_primitive::<UInt32Type>();
+        assert!(lap.is_null(0));
+        assert_eq!(lap.value(2), 3);
+
+        let batch = Trackpoint::to_record_batch(&points);
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.column_by_name(ACTIVITY).unwrap().null_count(), 2);
+    }
+
+    #[test]
+    fn test_append_path() {
+        let dir = std::env::temp_dir().join(format!("tcx-record-batch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        // the last point of the first lap is repeated in the second lap
+        fs::write(
+            dir.join("a.tcx"),
+            r#"<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+              <Activities><Activity Sport="Biking">
+                <Lap><Track>
+                  <Trackpoint><Time>2022-12-31T12:00:00Z</Time></Trackpoint>
+                  <Trackpoint><Time>2022-12-31T12:00:01Z</Time></Trackpoint>
+                </Track></Lap>
+                <Lap><Track>
+                  <Trackpoint><Time>2022-12-31T12:00:01Z</Time></Trackpoint>
+                  <Trackpoint><Time>2022-12-31T12:00:02Z</Time></Trackpoint>
+                </Track></Lap>
+              </Activity></Activities>
+            </TrainingCenterDatabase>"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("b.csv"),
+            "Time,Heartrate\n2022-12-31T13:00:00Z,120\n",
+        )
+        .unwrap();
+        fs::write(dir.join("c.txt"), "ignored").unwrap();
+
+        assert_eq!(
+            vec![dir.join("a.tcx"), dir.join("b.csv")],
+            TrackpointBatchBuilder::files(&dir).unwrap()
+        );
+
+        let mut builder = TrackpointBatchBuilder::new();
+        builder
+            .append_path(&dir, &mut ParseContext::default())
+            .unwrap();
+        let batch = builder.finish();
+        assert_eq!(batch.num_rows(), 4);
+        let lap = batch
+            .column_by_name(LAP)
+            .unwrap()
+            .as_primitive::<UInt32Type>();
+        assert_eq!(lap.value(1), 0);
+        assert_eq!(lap.value(2), 1);
+        assert!(lap.is_null(3));
+        let file = batch.column_by_name(FILE).unwrap().as_string::<i32>();
+        assert!(file.value(3).ends_with("b.csv"));
+
+        // a file that cannot be read is not appended
+        fs::write(dir.join("d.fit"), "not FIT").unwrap();
+        assert!(builder
+            .append_path(&dir.join("d.fit"), &mut ParseContext::default())
+            .is_err());
+        assert!(builder.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,317 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use chrono::{
+    DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Utc,
+};
+
+use crate::{ErrorContext, IntoTcxError, TcxError};
+
+/// timezone to interpret timestamps without UTC designator or offset in
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum FallbackTimezone {
+    /// Interpret as UTC
+    #[default]
+    Utc,
+    /// Interpret in the system's local timezone
+    Local,
+    /// Interpret with a fixed offset
+    Offset(FixedOffset),
+}
+
+/// how a timestamp's timezone was determined, see [`parse_timestamp`]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum TimeInterpretation {
+    /// The timestamp has a UTC designator (`Z` or `UTC`)
+    Utc,
+    /// The timestamp has an explicit offset, e.g., `+02:00`
+    Offset(FixedOffset),
+    /// The timestamp has no zone and was interpreted in the [`FallbackTimezone`], the offset used is given
+    Fallback(FixedOffset),
+}
+
+/// error returned by [`parse_timestamp`]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseTimestampError {
+    reason: &'static str,
+}
+
+/// Parse an ISO 8601 timestamp
+///
+/// Accepted are a date `YYYY-MM-DD` followed by `T`, `t` or a space and a time `hh:mm:ss` or `hh:mm`. Seconds may have
+/// a fraction of any length separated by `.` or `,`, digits beyond nanoseconds are truncated. The time is followed by
+/// an optional UTC designator (`Z`, `z` or ` UTC`) or offset (`±hh:mm`, `±hhmm` or `±hh`). Timestamps without either
+/// are interpreted in the `fallback` timezone.
+///
+/// # Examples
+/// ```
+/// # use tcx::*;
+/// use chrono::{DateTime, FixedOffset, Utc};
+///
+/// let expected = "2022-12-31T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+/// let plus_2 = FixedOffset::east_opt(2 * 3600).unwrap();
+///
+/// let (time, interpretation) = parse_timestamp("2022-12-31T12:00:00+02:00", FallbackTimezone::Utc).unwrap();
+/// assert_eq!(time, expected);
+/// assert_eq!(interpretation, TimeInterpretation::Offset(plus_2));
+///
+/// let (time, interpretation) = parse_timestamp("2022-12-31 10:00:00.0000000000Z", FallbackTimezone::Utc).unwrap();
+/// assert_eq!(time, expected);
+/// assert_eq!(interpretation, TimeInterpretation::Utc);
+///
+/// let (time, interpretation) =
+///     parse_timestamp("2022-12-31T12:00:00", FallbackTimezone::Offset(plus_2)).unwrap();
+/// assert_eq!(time, expected);
+/// assert_eq!(interpretation, TimeInterpretation::Fallback(plus_2));
+///
+/// assert!(parse_timestamp("2022-12-31T25:00:00Z", FallbackTimezone::Utc).is_err());
+/// ```
+pub fn parse_timestamp(
+    text: &str,
+    fallback: FallbackTimezone,
+) -> Result<(DateTime<Utc>, TimeInterpretation), ParseTimestampError> {
+    let text = text.trim();
+    let sep = text
+        .find(['T', 't', ' '])
+        .ok_or_else(|| ParseTimestampError::new("missing time"))?;
+    let (date, time) = (&text[..sep], &text[sep + 1..]);
+
+    // split off zone
+    let (time, zone) = if let Some(time) = time.strip_suffix("UTC") {
+        (time.trim_end(), Some(TimeInterpretation::Utc))
+    } else if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        (time, Some(TimeInterpretation::Utc))
+    } else if let Some(k) = time.rfind(['+', '-']) {
+        (
+            &time[..k],
+            Some(TimeInterpretation::Offset(parse_offset(&time[k..])?)),
+        )
+    } else {
+        (time, None)
+    };
+
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| ParseTimestampError::new("invalid date"))?;
+    let naive = NaiveDateTime::new(date, parse_time(time)?);
+
+    match zone {
+        Some(TimeInterpretation::Utc) => {
+            Ok((Utc.from_utc_datetime(&naive), TimeInterpretation::Utc))
+        }
+        Some(TimeInterpretation::Offset(offset)) => Ok((
+            from_local(&offset, &naive)?.with_timezone(&Utc),
+            TimeInterpretation::Offset(offset),
+        )),
+        _ => {
+            let (time, offset) = match fallback {
+                FallbackTimezone::Utc => (Utc.from_utc_datetime(&naive), Utc.fix()),
+                FallbackTimezone::Local => {
+                    let time = from_local(&Local, &naive)?;
+                    (time.with_timezone(&Utc), time.offset().fix())
+                }
+                FallbackTimezone::Offset(offset) => {
+                    (from_local(&offset, &naive)?.with_timezone(&Utc), offset)
+                }
+            };
+            Ok((time, TimeInterpretation::Fallback(offset)))
+        }
+    }
+}
+
+/// Convert local time to a timezone aware time, the earlier one for ambiguous times
+fn from_local<Tz: TimeZone>(
+    tz: &Tz,
+    naive: &NaiveDateTime,
+) -> Result<DateTime<Tz>, ParseTimestampError> {
+    tz.from_local_datetime(naive)
+        .earliest()
+        .ok_or_else(|| ParseTimestampError::new("local time does not exist"))
+}
+
+/// Parse `hh:mm:ss` with optional fraction or `hh:mm`
+fn parse_time(text: &str) -> Result<NaiveTime, ParseTimestampError> {
+    let (hms, fraction) = match text.find(['.', ',']) {
+        Some(k) => (&text[..k], Some(&text[k + 1..])),
+        None => (text, None),
+    };
+
+    let nanos = match fraction {
+        Some(digits) if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) => {
+            // truncate or pad to nanoseconds
+            let digits = &digits[..digits.len().min(9)];
+            digits.parse::<u32>().unwrap_or(0) * 10u32.pow(9 - digits.len() as u32)
+        }
+        Some(_) => return Err(ParseTimestampError::new("invalid fraction of seconds")),
+        None => 0,
+    };
+
+    let time = NaiveTime::parse_from_str(hms, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(hms, "%H:%M"))
+        .map_err(|_| ParseTimestampError::new("invalid time"))?;
+
+    Ok(time + chrono::Duration::nanoseconds(nanos.into()))
+}
+
+/// Parse `±hh:mm`, `±hhmm` or `±hh`
+fn parse_offset(text: &str) -> Result<FixedOffset, ParseTimestampError> {
+    let invalid = || ParseTimestampError::new("invalid offset");
+
+    let (sign, digits) = match text.split_at(1) {
+        ("+", digits) => (1, digits),
+        ("-", digits) => (-1, digits),
+        _ => return Err(invalid()),
+    };
+    let digits = digits.replacen(':', "", 1);
+    if !matches!(digits.len(), 2 | 4) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    let hours = digits[..2].parse::<i32>().map_err(|_| invalid())?;
+    let minutes = digits[2..].parse::<i32>().unwrap_or(0);
+    if hours > 23 || minutes > 59 {
+        return Err(invalid());
+    }
+
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).ok_or_else(invalid)
+}
+
+impl ParseTimestampError {
+    fn new(reason: &'static str) -> Self {
+        ParseTimestampError { reason }
+    }
+}
+
+impl Display for ParseTimestampError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid timestamp: {}", self.reason)
+    }
+}
+
+impl std::error::Error for ParseTimestampError {}
+
+impl IntoTcxError for ParseTimestampError {
+    fn into_tcx_error(self, context: ErrorContext, text: &str) -> TcxError {
+        TcxError::InvalidTimestamp {
+            context,
+            text: text.to_string(),
+        }
+    }
+}
+
+impl Display for FallbackTimezone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FallbackTimezone::Utc => write!(f, "UTC"),
+            FallbackTimezone::Local => write!(f, "Local"),
+            FallbackTimezone::Offset(offset) => write!(f, "{}", offset),
+        }
+    }
+}
+
+impl FromStr for FallbackTimezone {
+    type Err = String;
+
+    /// Parse `UTC`, `Local` (case insensitive) or an offset `±hh:mm`, `±hhmm` or `±hh`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "utc" => Ok(FallbackTimezone::Utc),
+            "local" => Ok(FallbackTimezone::Local),
+            _ => parse_offset(s)
+                .map(FallbackTimezone::Offset)
+                .map_err(|_| format!("'{}' is not a valid FallbackTimezone", s)),
+        }
+    }
+}
+
+impl Display for TimeInterpretation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeInterpretation::Utc => write!(f, "UTC"),
+            TimeInterpretation::Offset(offset) => write!(f, "offset {}", offset),
+            TimeInterpretation::Fallback(offset) => write!(f, "zone-less, fallback {}", offset),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        let expected = "2022-12-31T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let plus_2 = FixedOffset::east_opt(2 * 3600).unwrap();
+        let minus_130 = FixedOffset::west_opt(90 * 60).unwrap();
+
+        for (text, interpretation) in [
+            ("2022-12-31T10:00:00Z", TimeInterpretation::Utc),
+            ("2022-12-31t10:00:00z", TimeInterpretation::Utc),
+            ("2022-12-31 10:00:00 UTC", TimeInterpretation::Utc),
+            ("2022-12-31T10:00Z", TimeInterpretation::Utc),
+            ("  2022-12-31T10:00:00.000Z\n", TimeInterpretation::Utc),
+            (
+                "2022-12-31T12:00:00+02:00",
+                TimeInterpretation::Offset(plus_2),
+            ),
+            (
+                "2022-12-31T12:00:00+0200",
+                TimeInterpretation::Offset(plus_2),
+            ),
+            (
+                "2022-12-31T12:00:00,0+02",
+                TimeInterpretation::Offset(plus_2),
+            ),
+            (
+                "2022-12-31T08:30:00-01:30",
+                TimeInterpretation::Offset(minus_130),
+            ),
+            (
+                "2022-12-31T10:00:00",
+                TimeInterpretation::Fallback(Utc.fix()),
+            ),
+        ] {
+            assert_eq!(
+                Ok((expected, interpretation)),
+                parse_timestamp(text, FallbackTimezone::Utc),
+                "{}",
+                text
+            );
+        }
+
+        let (time, _) =
+            parse_timestamp("2022-12-31T10:00:00.1234567899Z", FallbackTimezone::Utc).unwrap();
+        assert_eq!(123_456_789, time.timestamp_subsec_nanos());
+        let (time, _) = parse_timestamp("2022-12-31T10:00:00.5Z", FallbackTimezone::Utc).unwrap();
+        assert_eq!(500_000_000, time.timestamp_subsec_nanos());
+
+        for text in [
+            "2022-12-31",
+            "2022-12-31T10:00:00.Z",
+            "2022-12-31T10:00:00+2",
+            "2022-12-31T10:00:00+24:00",
+            "2022-13-31T10:00:00Z",
+            "2022-12-31T10:00:00 CET",
+        ] {
+            assert!(
+                parse_timestamp(text, FallbackTimezone::Utc).is_err(),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_fallback_timezone_from_str() {
+        assert_eq!(Ok(FallbackTimezone::Utc), "utc".parse());
+        assert_eq!(Ok(FallbackTimezone::Local), "Local".parse());
+        assert_eq!(
+            Ok(FallbackTimezone::Offset(
+                FixedOffset::east_opt(3600).unwrap()
+            )),
+            "+01:00".parse()
+        );
+        assert!("CET".parse::<FallbackTimezone>().is_err());
+    }
+}
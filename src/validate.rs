@@ -0,0 +1,689 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use minidom::Element;
+
+use crate::{
+    workout::xsi_type, Intensity, Namespace, NamespaceMatching, PointType, Sport, Tag, TcxElement,
+    TriggerMethod,
+};
+
+/// a violation of the TrainingCenterDatabase v2 schema found by [`validate`]
+#[derive(Clone, PartialEq, Debug)]
+pub struct Violation {
+    /// Path to the offending element or attribute, e.g., `/TrainingCenterDatabase/Activities/Activity[0]/@Sport`
+    ///
+    /// Elements which may occur more than once are given with their (zero based) index among their siblings.
+    pub path: String,
+    /// What is wrong
+    pub kind: ViolationKind,
+}
+
+/// kinds of [`Violation`]s
+#[derive(Clone, PartialEq, Debug)]
+pub enum ViolationKind {
+    /// A required element is missing
+    MissingElement(Tag),
+    /// A required attribute is missing
+    MissingAttribute(Tag),
+    /// An element is not in the expected namespace
+    WrongNamespace { expected: Namespace, found: String },
+    /// A text is not a valid value of the expected type or enumeration
+    InvalidValue {
+        text: String,
+        expected: &'static str,
+    },
+    /// A number is out of the allowed range
+    OutOfRange { value: f64, min: f64, max: f64 },
+    /// A text is longer than allowed
+    TooLong { text: String, max_len: usize },
+}
+
+/// Validate a TCX document against the TrainingCenterDatabase v2 structure
+///
+/// The document's root element, i.e., the [`<TrainingCenterDatabase>`][Tag::TrainingCenterDatabase], is checked for
+/// required elements and attributes, enumeration values, value ranges and namespaces. Elements which are not part of
+/// the structure modelled by this crate, e.g., extensions, are not checked.
+///
+/// Timestamps must be `xs:dateTime`s with timezone, even though parsing accepts more, see
+/// [`parse_timestamp`][crate::parse_timestamp].
+///
+/// # Examples
+/// ```
+/// # use tcx::*;
+///
+/// let doc = r#"<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+///   <Activities>
+///     <Activity Sport="Swimming">
+///       <Id>2022-12-31T12:00:00Z</Id>
+///     </Activity>
+///   </Activities>
+/// </TrainingCenterDatabase>"#;
+///
+/// let violations = validate(&doc.parse().unwrap());
+///
+/// assert_eq!(violations.len(), 2);
+/// assert_eq!(
+///     violations[0].to_string(),
+///     "/TrainingCenterDatabase/Activities/Activity[0]/@Sport: Invalid value 'Swimming', expected Sport"
+/// );
+/// assert_eq!(violations[1].kind, ViolationKind::MissingElement(Tag::Lap));
+/// ```
+pub fn validate(root: &Element) -> Vec<Violation> {
+    let mut validator = Validator::default();
+
+    if root.has_name(Tag::TrainingCenterDatabase) {
+        validator.visit(
+            root,
+            Tag::TrainingCenterDatabase,
+            None,
+            Validator::training_center_database,
+        );
+    } else {
+        validator.report(ViolationKind::MissingElement(Tag::TrainingCenterDatabase));
+    }
+
+    validator.violations
+}
+
+/// lengths of restricted tokens in the schema
+const NAME_LEN: usize = 15;
+const COURSE_POINT_NAME_LEN: usize = 10;
+
+#[derive(Default)]
+struct Validator {
+    path: Vec<String>,
+    violations: Vec<Violation>,
+}
+
+impl Validator {
+    fn report(&mut self, kind: ViolationKind) {
+        self.violations.push(Violation {
+            path: format!("/{}", self.path.join("/")),
+            kind,
+        });
+    }
+
+    /// Check `element`'s namespace and validate it using `f` with the path extended by `tag` and `index`
+    fn visit(
+        &mut self,
+        element: &Element,
+        tag: Tag,
+        index: Option<usize>,
+        f: impl FnOnce(&mut Self, &Element),
+    ) {
+        self.path.push(match index {
+            Some(k) => format!("{}[{}]", tag.as_ref(), k),
+            None => tag.as_ref().to_string(),
+        });
+
+        if !element.is_tag(tag) {
+            self.report(ViolationKind::WrongNamespace {
+                expected: tag.namespaces()[0],
+                found: element.ns(),
+            });
+        }
+        f(self, element);
+
+        self.path.pop();
+    }
+
+    /// Validate a child element which may occur at most once
+    ///
+    /// Children are found by name, i.e., with [`NamespaceMatching::Permissive`], so that wrong namespaces are reported.
+    fn one(
+        &mut self,
+        parent: &Element,
+        tag: Tag,
+        required: bool,
+        f: impl FnOnce(&mut Self, &Element),
+    ) {
+        match parent.child_matching(&[tag], NamespaceMatching::Permissive) {
+            Some(element) => self.visit(element, tag, None, f),
+            None if required => self.report(ViolationKind::MissingElement(tag)),
+            None => (),
+        }
+    }
+
+    /// Validate child elements which may occur more than once
+    fn many(&mut self, parent: &Element, tag: Tag, min: usize, f: impl Fn(&mut Self, &Element)) {
+        let mut count = 0;
+        for (k, element) in parent
+            .children_matching(tag, NamespaceMatching::Permissive)
+            .enumerate()
+        {
+            self.visit(element, tag, Some(k), &f);
+            count += 1;
+        }
+        if count < min {
+            self.report(ViolationKind::MissingElement(tag));
+        }
+    }
+
+    /// Check that `text` parses into `T`
+    fn text<T: FromStr>(&mut self, text: &str, expected: &'static str) -> Option<T> {
+        let value = text.trim().parse().ok();
+        if value.is_none() {
+            self.report(ViolationKind::InvalidValue {
+                text: text.to_string(),
+                expected,
+            });
+        }
+        value
+    }
+
+    /// Validate a child element's text which parses into `T`, e.g., an enumeration
+    fn value<T: FromStr>(
+        &mut self,
+        parent: &Element,
+        tag: Tag,
+        required: bool,
+        expected: &'static str,
+    ) {
+        self.one(parent, tag, required, |v, e| {
+            v.text::<T>(&e.text(), expected);
+        });
+    }
+
+    /// Validate an attribute which parses into `T`
+    fn attr<T: FromStr>(&mut self, element: &Element, tag: Tag, expected: &'static str) {
+        self.path.push(format!("@{}", tag.as_ref()));
+        match element.attr(tag.as_ref()) {
+            Some(text) => {
+                self.text::<T>(text, expected);
+            }
+            None => self.report(ViolationKind::MissingAttribute(tag)),
+        }
+        self.path.pop();
+    }
+
+    /// Validate a number in `[min, max]`
+    fn number(&mut self, parent: &Element, tag: Tag, required: bool, min: f64, max: f64) {
+        self.one(parent, tag, required, |v, e| {
+            if let Some(value) = v.text::<f64>(&e.text(), "number") {
+                if !(min..=max).contains(&value) {
+                    v.report(ViolationKind::OutOfRange { value, min, max });
+                }
+            }
+        });
+    }
+
+    /// Validate an integer in `[min, max]`
+    fn integer(&mut self, parent: &Element, tag: Tag, required: bool, min: u64, max: u64) {
+        self.one(parent, tag, required, |v, e| {
+            if let Some(value) = v.text::<u64>(&e.text(), "unsigned integer") {
+                if !(min..=max).contains(&value) {
+                    v.report(ViolationKind::OutOfRange {
+                        value: value as f64,
+                        min: min as f64,
+                        max: max as f64,
+                    });
+                }
+            }
+        });
+    }
+
+    /// Validate a non-negative double
+    fn non_negative(&mut self, parent: &Element, tag: Tag, required: bool) {
+        self.number(parent, tag, required, 0.0, f64::INFINITY);
+    }
+
+    /// Validate a timestamp, see [`is_date_time`]
+    fn time(&mut self, parent: &Element, tag: Tag, required: bool) {
+        self.one(parent, tag, required, |v, e| {
+            if !is_date_time(&e.text()) {
+                v.report(ViolationKind::InvalidValue {
+                    text: e.text(),
+                    expected: "timestamp",
+                });
+            }
+        });
+    }
+
+    /// Validate a token with a maximum length
+    fn token(&mut self, parent: &Element, tag: Tag, required: bool, max_len: usize) {
+        self.one(parent, tag, required, |v, e| {
+            let text = e.text();
+            if text.trim().chars().count() > max_len {
+                v.report(ViolationKind::TooLong { text, max_len });
+            }
+        });
+    }
+
+    /// Validate a heart rate in beats per minute (`HeartRateInBeatsPerMinute_t`)
+    fn heart_rate(&mut self, parent: &Element, tag: Tag, required: bool) {
+        self.one(parent, tag, required, |v, e| {
+            v.integer(e, Tag::Value, true, 1, 255)
+        });
+    }
+
+    fn training_center_database(&mut self, tcx: &Element) {
+        self.one(tcx, Tag::Folders, false, Validator::folders);
+        self.one(tcx, Tag::Activities, false, |v, e| {
+            v.many(e, Tag::Activity, 0, Validator::activity);
+            v.many(e, Tag::MultiSportSession, 0, Validator::multi_sport_session);
+        });
+        self.one(tcx, Tag::Workouts, false, |v, e| {
+            v.many(e, Tag::Workout, 0, Validator::workout)
+        });
+        self.one(tcx, Tag::Courses, false, |v, e| {
+            v.many(e, Tag::Course, 0, Validator::course)
+        });
+        self.one(tcx, Tag::Author, false, Validator::application);
+    }
+
+    fn folders(&mut self, folders: &Element) {
+        let sport_folders = |v: &mut Self, e: &Element| {
+            for tag in [Tag::Running, Tag::Biking, Tag::Other, Tag::MultiSport] {
+                v.one(e, tag, false, Validator::folder);
+            }
+        };
+        self.one(folders, Tag::History, false, sport_folders);
+        self.one(folders, Tag::Workouts, false, sport_folders);
+        self.one(folders, Tag::Courses, false, |v, e| {
+            v.one(e, Tag::CourseFolder, true, Validator::folder)
+        });
+    }
+
+    fn folder(&mut self, folder: &Element) {
+        if folder.attr(Tag::Name.as_ref()).is_none() {
+            self.path.push(format!("@{}", Tag::Name.as_ref()));
+            self.report(ViolationKind::MissingAttribute(Tag::Name));
+            self.path.pop();
+        }
+        self.many(folder, Tag::Folder, 0, Validator::folder);
+    }
+
+    fn activity(&mut self, activity: &Element) {
+        self.attr::<Sport>(activity, Tag::Sport, "Sport");
+        self.time(activity, Tag::Id, true);
+        self.many(activity, Tag::Lap, 1, Validator::lap);
+        self.one(activity, Tag::Creator, false, Validator::device);
+    }
+
+    fn multi_sport_session(&mut self, session: &Element) {
+        self.time(session, Tag::Id, true);
+        self.one(session, Tag::FirstSport, true, |v, e| {
+            v.one(e, Tag::Activity, true, Validator::activity)
+        });
+        self.many(session, Tag::NextSport, 0, |v, e| {
+            v.one(e, Tag::Transition, false, Validator::lap);
+            v.one(e, Tag::Activity, true, Validator::activity);
+        });
+    }
+
+    fn lap(&mut self, lap: &Element) {
+        self.path.push(format!("@{}", Tag::StartTime.as_ref()));
+        match lap.attr(Tag::StartTime.as_ref()) {
+            Some(text) if !is_date_time(text) => self.report(ViolationKind::InvalidValue {
+                text: text.to_string(),
+                expected: "timestamp",
+            }),
+            Some(_) => (),
+            None => self.report(ViolationKind::MissingAttribute(Tag::StartTime)),
+        }
+        self.path.pop();
+
+        self.non_negative(lap, Tag::TotalTimeSeconds, true);
+        self.non_negative(lap, Tag::DistanceMeters, true);
+        self.non_negative(lap, Tag::MaximumSpeed, false);
+        self.integer(lap, Tag::Calories, true, 0, u16::MAX as u64);
+        self.heart_rate(lap, Tag::AverageHeartRateBpm, false);
+        self.heart_rate(lap, Tag::MaximumHeartRateBpm, false);
+        self.value::<Intensity>(lap, Tag::Intensity, true, "Intensity");
+        self.integer(lap, Tag::Cadence, false, 0, 254);
+        self.value::<TriggerMethod>(lap, Tag::TriggerMethod, true, "TriggerMethod");
+        self.many(lap, Tag::Track, 0, Validator::track);
+    }
+
+    fn track(&mut self, track: &Element) {
+        self.many(track, Tag::Trackpoint, 1, Validator::trackpoint);
+    }
+
+    fn trackpoint(&mut self, trackpoint: &Element) {
+        self.time(trackpoint, Tag::Time, true);
+        self.one(trackpoint, Tag::Position, false, Validator::position);
+        self.number(trackpoint, Tag::AltitudeMeters, false, f64::MIN, f64::MAX);
+        self.non_negative(trackpoint, Tag::DistanceMeters, false);
+        self.heart_rate(trackpoint, Tag::HeartRateBpm, false);
+        self.integer(trackpoint, Tag::Cadence, false, 0, 254);
+    }
+
+    fn position(&mut self, position: &Element) {
+        self.number(position, Tag::LatitudeDegrees, true, -90.0, 90.0);
+        self.number(position, Tag::LongitudeDegrees, true, -180.0, 180.0);
+    }
+
+    fn course(&mut self, course: &Element) {
+        self.token(course, Tag::Name, true, NAME_LEN);
+        self.many(course, Tag::Lap, 0, |v, e| {
+            v.non_negative(e, Tag::TotalTimeSeconds, true);
+            v.non_negative(e, Tag::DistanceMeters, true);
+            v.one(e, Tag::BeginPosition, false, Validator::position);
+            v.one(e, Tag::EndPosition, false, Validator::position);
+            v.heart_rate(e, Tag::AverageHeartRateBpm, false);
+            v.heart_rate(e, Tag::MaximumHeartRateBpm, false);
+            v.value::<Intensity>(e, Tag::Intensity, true, "Intensity");
+            v.integer(e, Tag::Cadence, false, 0, 254);
+        });
+        self.many(course, Tag::Track, 0, Validator::track);
+        self.many(course, Tag::CoursePoint, 0, |v, e| {
+            v.token(e, Tag::Name, true, COURSE_POINT_NAME_LEN);
+            v.time(e, Tag::Time, true);
+            v.one(e, Tag::Position, true, Validator::position);
+            v.value::<PointType>(e, Tag::PointType, true, "PointType");
+        });
+        self.one(course, Tag::Creator, false, Validator::device);
+    }
+
+    fn workout(&mut self, workout: &Element) {
+        self.attr::<Sport>(workout, Tag::Sport, "Sport");
+        self.token(workout, Tag::Name, true, NAME_LEN);
+        self.many(workout, Tag::Step, 1, Validator::workout_step);
+        self.many(workout, Tag::ScheduledOn, 0, |v, e| {
+            v.text::<chrono::NaiveDate>(&e.text(), "date");
+        });
+        self.one(workout, Tag::Creator, false, Validator::device);
+    }
+
+    fn workout_step(&mut self, step: &Element) {
+        self.integer(step, Tag::StepId, true, 1, 20);
+
+        if xsi_type(step) == Some("Repeat_t")
+            || step
+                .child_matching(&[Tag::Repetitions], NamespaceMatching::Permissive)
+                .is_some()
+        {
+            self.integer(step, Tag::Repetitions, true, 2, 99);
+            self.many(step, Tag::Child, 1, Validator::workout_step);
+        } else {
+            self.token(step, Tag::Name, false, NAME_LEN);
+            self.one(step, Tag::Duration, true, |_, _| ());
+            self.value::<Intensity>(step, Tag::Intensity, true, "Intensity");
+            self.one(step, Tag::Target, true, |_, _| ());
+        }
+    }
+
+    fn version(&mut self, version: &Element) {
+        self.integer(version, Tag::VersionMajor, true, 0, u16::MAX as u64);
+        self.integer(version, Tag::VersionMinor, true, 0, u16::MAX as u64);
+        self.integer(version, Tag::BuildMajor, false, 0, u16::MAX as u64);
+        self.integer(version, Tag::BuildMinor, false, 0, u16::MAX as u64);
+    }
+
+    fn device(&mut self, device: &Element) {
+        self.one(device, Tag::Name, true, |_, _| ());
+        self.integer(device, Tag::UnitId, true, 0, u32::MAX as u64);
+        self.integer(device, Tag::ProductID, true, 0, u16::MAX as u64);
+        self.one(device, Tag::Version, true, Validator::version);
+    }
+
+    fn application(&mut self, application: &Element) {
+        self.one(application, Tag::Name, true, |_, _| ());
+        self.one(application, Tag::Build, true, |v, e| {
+            v.one(e, Tag::Version, true, Validator::version)
+        });
+        self.one(application, Tag::LangID, true, |_, _| ());
+        self.one(application, Tag::PartNumber, true, |v, e| {
+            let text = e.text();
+            let valid = text.len() == 12
+                && text.char_indices().all(|(k, c)| match k {
+                    3 | 9 => c == '-',
+                    _ => c.is_ascii_uppercase() || c.is_ascii_digit(),
+                });
+            if !valid {
+                v.report(ViolationKind::InvalidValue {
+                    text,
+                    expected: "part number XXX-XXXXX-XX",
+                });
+            }
+        });
+    }
+}
+
+/// Check that `text` is an `xs:dateTime` with timezone like `2022-12-31T12:00:00.5Z` or `2022-12-31T13:00:00+01:00`
+///
+/// This is stricter than [`parse_timestamp`][crate::parse_timestamp], which reads what devices actually write: a
+/// separator other than `T`, missing seconds, `,` as decimal separator, zone names like `UTC` and missing zones are
+/// invalid.
+fn is_date_time(text: &str) -> bool {
+    let text = text.trim();
+    let (head, zone) = match text.strip_suffix('Z') {
+        Some(head) => (head, "+00:00"),
+        None if text.is_char_boundary(text.len().saturating_sub(6)) => {
+            text.split_at(text.len().saturating_sub(6))
+        }
+        None => return false,
+    };
+    let layout = head.len() >= 19
+        && head.bytes().take(19).enumerate().all(|(k, b)| match k {
+            4 | 7 => b == b'-',
+            10 => b == b'T',
+            13 | 16 => b == b':',
+            _ => b.is_ascii_digit(),
+        });
+    layout
+        && chrono::DateTime::parse_from_str(&format!("{}{}", head, zone), "%Y-%m-%dT%H:%M:%S%.f%:z")
+            .is_ok()
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.path)?;
+        match &self.kind {
+            ViolationKind::MissingElement(tag) => write!(f, "Missing element <{}>", tag.as_ref()),
+            ViolationKind::MissingAttribute(tag) => {
+                write!(f, "Missing attribute {}", tag.as_ref())
+            }
+            ViolationKind::WrongNamespace { expected, found } => write!(
+                f,
+                "Wrong namespace '{}', expected '{}'",
+                found,
+                expected.uri()
+            ),
+            ViolationKind::InvalidValue { text, expected } => {
+                write!(f, "Invalid value '{}', expected {}", text, expected)
+            }
+            ViolationKind::OutOfRange { value, min, max } => {
+                write!(f, "Value {} out of range [{}, {}]", value, min, max)
+            }
+            ViolationKind::TooLong { text, max_len } => {
+                write!(f, "Text '{}' is longer than {} characters", text, max_len)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NS: &str = "http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2";
+
+    #[test]
+    fn test_validate_valid() {
+        let doc = format!(
+            r#"<TrainingCenterDatabase xmlns="{}">
+              <Activities>
+                <Activity Sport="Biking">
+                  <Id>2022-12-31T12:00:00Z</Id>
+                  <Lap StartTime="2022-12-31T12:00:00Z">
+                    <TotalTimeSeconds>1.0</TotalTimeSeconds>
+                    <DistanceMeters>5.0</DistanceMeters>
+                    <Calories>0</Calories>
+                    <Intensity>Active</Intensity>
+                    <TriggerMethod>Manual</TriggerMethod>
+                    <Track>
+                      <Trackpoint>
+                        <Time>2022-12-31T12:00:00Z</Time>
+                        <Position>
+                          <LatitudeDegrees>48.6</LatitudeDegrees>
+                          <LongitudeDegrees>9.0</LongitudeDegrees>
+                        </Position>
+                        <HeartRateBpm><Value>120</Value></HeartRateBpm>
+                      </Trackpoint>
+                    </Track>
+                  </Lap>
+                </Activity>
+              </Activities>
+              <Author>
+                <Name>tcx</Name>
+                <Build><Version><VersionMajor>0</VersionMajor><VersionMinor>2</VersionMinor></Version></Build>
+                <LangID>en</LangID>
+                <PartNumber>000-00000-00</PartNumber>
+              </Author>
+            </TrainingCenterDatabase>"#,
+            NS
+        );
+
+        assert_eq!(Vec::<Violation>::new(), validate(&doc.parse().unwrap()));
+    }
+
+    #[test]
+    fn test_is_date_time() {
+        for text in [
+            "2022-12-31T12:00:00Z",
+            "2022-12-31T12:00:00.125Z",
+            "2022-12-31T13:00:00+01:00",
+            " 2022-12-31T06:00:00-06:00\n",
+        ] {
+            assert!(is_date_time(text), "{}", text);
+        }
+        for text in [
+            "2022-12-31 12:00:00Z",
+            "2022-12-31T12:00Z",
+            "2022-12-31T12:00:00,5Z",
+            "2022-12-31T12:00:00 UTC",
+            "2022-12-31T12:00:00",
+            "2022-12-31T12:00:00+0100",
+            "2022-12-31T12:00:00.Z",
+            "2022-12-31T12:0:00Z",
+            "2022-12-32T12:00:00Z",
+            "2022-12-31T12:00:00.äöüZ",
+        ] {
+            assert!(!is_date_time(text), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_validate_part_number() {
+        for (part_number, valid) in [
+            ("006-D2449-00", true),
+            ("ABC-XYZGH-QR", true),
+            ("006-d2449-00", false),
+            ("006-D2449-0", false),
+            ("006_D2449-00", false),
+        ] {
+            let doc = format!(
+                r#"<TrainingCenterDatabase xmlns="{}">
+                  <Author>
+                    <Name>tcx</Name>
+                    <Build><Version><VersionMajor>0</VersionMajor><VersionMinor>2</VersionMinor></Version></Build>
+                    <LangID>en</LangID>
+                    <PartNumber>{}</PartNumber>
+                  </Author>
+                </TrainingCenterDatabase>"#,
+                NS, part_number
+            );
+            assert_eq!(
+                valid,
+                validate(&doc.parse().unwrap()).is_empty(),
+                "{}",
+                part_number
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_violations() {
+        let doc = format!(
+            r#"<TrainingCenterDatabase xmlns="{}" xmlns:x="urn:vendor">
+              <Activities>
+                <Activity Sport="Running">
+                  <Id>2022-12-31T12:00:00Z</Id>
+                  <Lap>
+                    <TotalTimeSeconds>-1.0</TotalTimeSeconds>
+                    <DistanceMeters>5.0</DistanceMeters>
+                    <Calories>0</Calories>
+                    <Intensity>Lazy</Intensity>
+                    <x:TriggerMethod>Manual</x:TriggerMethod>
+                    <Track>
+                      <Trackpoint>
+                        <Position><LatitudeDegrees>91</LatitudeDegrees></Position>
+                        <HeartRateBpm><Value>0</Value></HeartRateBpm>
+                      </Trackpoint>
+                    </Track>
+                    <Track/>
+                  </Lap>
+                </Activity>
+              </Activities>
+            </TrainingCenterDatabase>"#,
+            NS
+        );
+
+        let lap = "/TrainingCenterDatabase/Activities/Activity[0]/Lap[0]";
+        let trackpoint = format!("{}/Track[0]/Trackpoint[0]", lap);
+        let violations = validate(&doc.parse().unwrap())
+            .into_iter()
+            .map(|v| (v.path, v.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (
+                    format!("{}/@StartTime", lap),
+                    ViolationKind::MissingAttribute(Tag::StartTime)
+                ),
+                (
+                    format!("{}/TotalTimeSeconds", lap),
+                    ViolationKind::OutOfRange {
+                        value: -1.0,
+                        min: 0.0,
+                        max: f64::INFINITY
+                    }
+                ),
+                (
+                    format!("{}/Intensity", lap),
+                    ViolationKind::InvalidValue {
+                        text: "Lazy".to_string(),
+                        expected: "Intensity"
+                    }
+                ),
+                (
+                    format!("{}/TriggerMethod", lap),
+                    ViolationKind::WrongNamespace {
+                        expected: Namespace::TrainingCenterDatabaseV2,
+                        found: "urn:vendor".to_string()
+                    }
+                ),
+                (trackpoint.clone(), ViolationKind::MissingElement(Tag::Time)),
+                (
+                    format!("{}/Position/LatitudeDegrees", trackpoint),
+                    ViolationKind::OutOfRange {
+                        value: 91.0,
+                        min: -90.0,
+                        max: 90.0
+                    }
+                ),
+                (
+                    format!("{}/Position", trackpoint),
+                    ViolationKind::MissingElement(Tag::LongitudeDegrees)
+                ),
+                (
+                    format!("{}/HeartRateBpm/Value", trackpoint),
+                    ViolationKind::OutOfRange {
+                        value: 0.0,
+                        min: 1.0,
+                        max: 255.0
+                    }
+                ),
+                (
+                    format!("{}/Track[1]", lap),
+                    ViolationKind::MissingElement(Tag::Trackpoint)
+                ),
+            ],
+            violations
+        );
+    }
+}
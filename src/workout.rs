@@ -0,0 +1,516 @@
+use chrono::NaiveDate;
+use minidom::Element;
+use tcx_macro_derive::{AsRefStr, EnumFromStr};
+
+use crate::{Device, ErrorContext, Intensity, IntoTcxError, ParseContext, Sport, Tag, TcxError};
+
+/// Get the local part of an element's `xsi:type` attribute, e.g. `"Step_t"` for `xsi:type="Step_t"`
+///
+/// Abstract types of the TCX schema ([`WorkoutStep`], [`StepDuration`], [`StepTarget`], ...) use the `xsi:type`
+/// attribute to specify the concrete type.
+pub(crate) fn xsi_type(element: &Element) -> Option<&str> {
+    element
+        .attrs()
+        .find(|(name, _)| *name == "type" || name.ends_with(":type"))
+        .map(|(_, value)| value.rsplit(':').next().unwrap_or(value))
+}
+
+/// a heart rate value used in workout durations and targets ([`<HeartRate>`][Tag::HeartRate], [`<Low>`][Tag::Low],
+/// [`<High>`][Tag::High])
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HeartRateValue {
+    /// Heart rate in beats per minute (`HeartRateInBeatsPerMinute_t`)
+    Bpm(u8),
+    /// Heart rate in percent of the maximum heart rate (`HeartRateAsPercentOfMax_t`)
+    PercentOfMax(u8),
+}
+
+/// when a workout [`Step`] is completed ([`<Duration>`][Tag::Duration])
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StepDuration {
+    /// After the given number of seconds ([`<Seconds>`][Tag::Seconds])
+    Time(u16),
+    /// After the given number of meters ([`<Meters>`][Tag::Meters])
+    Distance(u16),
+    /// When the heart rate rises above the value ([`<HeartRate>`][Tag::HeartRate])
+    HeartRateAbove(HeartRateValue),
+    /// When the heart rate falls below the value ([`<HeartRate>`][Tag::HeartRate])
+    HeartRateBelow(HeartRateValue),
+    /// After the given number of calories is burnt ([`<Calories>`][Tag::Calories])
+    CaloriesBurned(u16),
+    /// When the user presses the lap button
+    UserInitiated,
+}
+
+/// how speed zones are displayed ([`<ViewAs>`][Tag::ViewAs])
+#[derive(Clone, Copy, PartialEq, Eq, Debug, AsRefStr, EnumFromStr)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpeedType {
+    Pace,
+    Speed,
+}
+
+/// a speed zone ([`<SpeedZone>`][Tag::SpeedZone])
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpeedZone {
+    /// One of the zones configured on the device ([`<Number>`][Tag::Number])
+    Predefined(u8),
+    /// A custom zone
+    Custom {
+        /// How the zone is displayed ([`<ViewAs>`][Tag::ViewAs])
+        view_as: SpeedType,
+        /// Lower bound ([`<LowInMetersPerSecond>`][Tag::LowInMetersPerSecond])
+        low: f64,
+        /// Upper bound ([`<HighInMetersPerSecond>`][Tag::HighInMetersPerSecond])
+        high: f64,
+    },
+}
+
+/// a heart rate zone ([`<HeartRateZone>`][Tag::HeartRateZone])
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HeartRateZone {
+    /// One of the zones configured on the device ([`<Number>`][Tag::Number])
+    Predefined(u8),
+    /// A custom zone
+    Custom {
+        /// Lower bound ([`<Low>`][Tag::Low])
+        low: HeartRateValue,
+        /// Upper bound ([`<High>`][Tag::High])
+        high: HeartRateValue,
+    },
+}
+
+/// what to aim at during a workout [`Step`] ([`<Target>`][Tag::Target])
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StepTarget {
+    /// Stay in speed zone ([`<SpeedZone>`][Tag::SpeedZone])
+    Speed(SpeedZone),
+    /// Stay in heart rate zone ([`<HeartRateZone>`][Tag::HeartRateZone])
+    HeartRate(HeartRateZone),
+    /// Keep cadence between [`<Low>`][Tag::Low] and [`<High>`][Tag::High]
+    Cadence { low: f64, high: f64 },
+    /// No target
+    None,
+}
+
+/// a single workout step ([`<Step>`][Tag::Step] of type `Step_t`)
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Step {
+    /// ID of the step within the workout ([`<StepId>`][Tag::StepId])
+    pub step_id: u8,
+    /// Name of the step ([`<Name>`][Tag::Name])
+    pub name: Option<String>,
+    /// When the step is completed ([`<Duration>`][Tag::Duration])
+    pub duration: StepDuration,
+    /// Intensity ([`<Intensity>`][Tag::Intensity])
+    pub intensity: Option<Intensity>,
+    /// What to aim at ([`<Target>`][Tag::Target])
+    pub target: StepTarget,
+}
+
+/// a block of workout steps to be repeated ([`<Step>`][Tag::Step] or [`<Child>`][Tag::Child] of type `Repeat_t`)
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Repeat {
+    /// ID of the step within the workout ([`<StepId>`][Tag::StepId])
+    pub step_id: u8,
+    /// Number of repetitions ([`<Repetitions>`][Tag::Repetitions])
+    pub repetitions: u8,
+    /// The steps to repeat ([`<Child>`][Tag::Child])
+    pub children: Vec<WorkoutStep>,
+}
+
+/// a workout step, either a [`Step`] or a [`Repeat`] ([`<Step>`][Tag::Step] or [`<Child>`][Tag::Child])
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WorkoutStep {
+    Step(Step),
+    Repeat(Repeat),
+}
+
+/// a structured workout ([`<Workout>`][Tag::Workout])
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Workout {
+    /// The workout's sport (attribute [`Sport`][Tag::Sport])
+    pub sport: Option<Sport>,
+    /// Name of the workout ([`<Name>`][Tag::Name])
+    pub name: String,
+    /// The workout's steps ([`<Step>`][Tag::Step])
+    pub steps: Vec<WorkoutStep>,
+    /// Dates the workout is scheduled on ([`<ScheduledOn>`][Tag::ScheduledOn])
+    pub scheduled_on: Vec<NaiveDate>,
+    /// Notes ([`<Notes>`][Tag::Notes])
+    pub notes: Option<String>,
+    /// The device that created the workout ([`<Creator>`][Tag::Creator])
+    pub creator: Option<Device>,
+}
+
+impl HeartRateValue {
+    /// Parse an element of type `HeartRateValue_t`
+    pub fn parse(value: &Element) -> Result<Self, TcxError> {
+        HeartRateValue::parse_with(value, &mut ParseContext::default())
+    }
+
+    /// Parse an element of type `HeartRateValue_t` using the given [`ParseContext`]
+    pub fn parse_with(value: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        let v = context
+            .child_value(value, &[Tag::Value])?
+            .ok_or_else(|| TcxError::missing(&[Tag::Value]))?;
+        match xsi_type(value) {
+            Some("HeartRateAsPercentOfMax_t") => Ok(HeartRateValue::PercentOfMax(v)),
+            _ => Ok(HeartRateValue::Bpm(v)),
+        }
+    }
+}
+
+impl StepDuration {
+    /// Parse a [`<Duration>`][Tag::Duration] element
+    ///
+    /// If the `xsi:type` attribute is missing, the type is inferred from the children where possible.
+    pub fn parse(duration: &Element) -> Result<Self, TcxError> {
+        StepDuration::parse_with(duration, &mut ParseContext::default())
+    }
+
+    /// Parse a [`<Duration>`][Tag::Duration] element using the given [`ParseContext`]
+    pub fn parse_with(duration: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        let heart_rate = |context: &mut ParseContext| -> Result<HeartRateValue, TcxError> {
+            HeartRateValue::parse_with(
+                context
+                    .child(duration, &[Tag::HeartRate])
+                    .ok_or_else(|| TcxError::missing(&[Tag::HeartRate]))?,
+                context,
+            )
+        };
+
+        match xsi_type(duration) {
+            Some("Time_t") => Ok(StepDuration::Time(
+                context
+                    .child_value(duration, &[Tag::Seconds])?
+                    .ok_or_else(|| TcxError::missing(&[Tag::Seconds]))?,
+            )),
+            Some("Distance_t") => Ok(StepDuration::Distance(
+                context
+                    .child_value(duration, &[Tag::Meters])?
+                    .ok_or_else(|| TcxError::missing(&[Tag::Meters]))?,
+            )),
+            Some("HeartRateAbove_t") => Ok(StepDuration::HeartRateAbove(heart_rate(context)?)),
+            Some("HeartRateBelow_t") => Ok(StepDuration::HeartRateBelow(heart_rate(context)?)),
+            Some("CaloriesBurned_t") => Ok(StepDuration::CaloriesBurned(
+                context
+                    .child_value(duration, &[Tag::Calories])?
+                    .ok_or_else(|| TcxError::missing(&[Tag::Calories]))?,
+            )),
+            Some("UserInitiated_t") => Ok(StepDuration::UserInitiated),
+            Some(t) => Err(TcxError::InvalidValue {
+                context: ErrorContext {
+                    tags: vec![Tag::Duration],
+                    ..Default::default()
+                },
+                text: t.to_string(),
+            }),
+            None => {
+                if let Some(seconds) = context.child_value(duration, &[Tag::Seconds])? {
+                    Ok(StepDuration::Time(seconds))
+                } else if let Some(meters) = context.child_value(duration, &[Tag::Meters])? {
+                    Ok(StepDuration::Distance(meters))
+                } else if let Some(calories) = context.child_value(duration, &[Tag::Calories])? {
+                    Ok(StepDuration::CaloriesBurned(calories))
+                } else if context.child(duration, &[Tag::HeartRate]).is_some() {
+                    // heart rate above or below cannot be inferred
+                    Err(TcxError::InvalidValue {
+                        context: ErrorContext {
+                            tags: vec![Tag::Duration],
+                            ..Default::default()
+                        },
+                        text: String::new(),
+                    })
+                } else {
+                    Ok(StepDuration::UserInitiated)
+                }
+            }
+        }
+    }
+}
+
+impl SpeedZone {
+    /// Parse a [`<SpeedZone>`][Tag::SpeedZone] element
+    pub fn parse(zone: &Element) -> Result<Self, TcxError> {
+        SpeedZone::parse_with(zone, &mut ParseContext::default())
+    }
+
+    /// Parse a [`<SpeedZone>`][Tag::SpeedZone] element using the given [`ParseContext`]
+    pub fn parse_with(zone: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        match context.child_value(zone, &[Tag::Number])? {
+            Some(number) => Ok(SpeedZone::Predefined(number)),
+            None => Ok(SpeedZone::Custom {
+                view_as: context
+                    .child_value(zone, &[Tag::ViewAs])?
+                    .ok_or_else(|| TcxError::missing(&[Tag::ViewAs]))?,
+                low: context
+                    .child_value(zone, &[Tag::LowInMetersPerSecond])?
+                    .ok_or_else(|| TcxError::missing(&[Tag::LowInMetersPerSecond]))?,
+                high: context
+                    .child_value(zone, &[Tag::HighInMetersPerSecond])?
+                    .ok_or_else(|| TcxError::missing(&[Tag::HighInMetersPerSecond]))?,
+            }),
+        }
+    }
+}
+
+impl HeartRateZone {
+    /// Parse a [`<HeartRateZone>`][Tag::HeartRateZone] element
+    pub fn parse(zone: &Element) -> Result<Self, TcxError> {
+        HeartRateZone::parse_with(zone, &mut ParseContext::default())
+    }
+
+    /// Parse a [`<HeartRateZone>`][Tag::HeartRateZone] element using the given [`ParseContext`]
+    pub fn parse_with(zone: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        match context.child_value(zone, &[Tag::Number])? {
+            Some(number) => Ok(HeartRateZone::Predefined(number)),
+            None => Ok(HeartRateZone::Custom {
+                low: HeartRateValue::parse_with(
+                    context
+                        .child(zone, &[Tag::Low])
+                        .ok_or_else(|| TcxError::missing(&[Tag::Low]))?,
+                    context,
+                )?,
+                high: HeartRateValue::parse_with(
+                    context
+                        .child(zone, &[Tag::High])
+                        .ok_or_else(|| TcxError::missing(&[Tag::High]))?,
+                    context,
+                )?,
+            }),
+        }
+    }
+}
+
+impl StepTarget {
+    /// Parse a [`<Target>`][Tag::Target] element
+    ///
+    /// If the `xsi:type` attribute is missing, the type is inferred from the children.
+    pub fn parse(target: &Element) -> Result<Self, TcxError> {
+        StepTarget::parse_with(target, &mut ParseContext::default())
+    }
+
+    /// Parse a [`<Target>`][Tag::Target] element using the given [`ParseContext`]
+    pub fn parse_with(target: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        if let Some(zone) = context.child(target, &[Tag::SpeedZone]) {
+            Ok(StepTarget::Speed(SpeedZone::parse_with(zone, context)?))
+        } else if let Some(zone) = context.child(target, &[Tag::HeartRateZone]) {
+            Ok(StepTarget::HeartRate(HeartRateZone::parse_with(
+                zone, context,
+            )?))
+        } else if xsi_type(target) == Some("Cadence_t")
+            || context.child(target, &[Tag::Low]).is_some()
+        {
+            Ok(StepTarget::Cadence {
+                low: context
+                    .child_value(target, &[Tag::Low])?
+                    .ok_or_else(|| TcxError::missing(&[Tag::Low]))?,
+                high: context
+                    .child_value(target, &[Tag::High])?
+                    .ok_or_else(|| TcxError::missing(&[Tag::High]))?,
+            })
+        } else {
+            Ok(StepTarget::None)
+        }
+    }
+}
+
+impl WorkoutStep {
+    /// Parse a [`<Step>`][Tag::Step] or [`<Child>`][Tag::Child] element
+    ///
+    /// The element is parsed as [`Repeat`] if its `xsi:type` is `Repeat_t` or if it has a
+    /// [`<Repetitions>`][Tag::Repetitions] child, otherwise as [`Step`].
+    pub fn parse(step: &Element) -> Result<Self, TcxError> {
+        WorkoutStep::parse_with(step, &mut ParseContext::default())
+    }
+
+    /// Parse a [`<Step>`][Tag::Step] or [`<Child>`][Tag::Child] element using the given [`ParseContext`]
+    pub fn parse_with(step: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        let step_id = context
+            .child_value(step, &[Tag::StepId])?
+            .ok_or_else(|| TcxError::missing(&[Tag::StepId]))?;
+
+        if xsi_type(step) == Some("Repeat_t") || context.child(step, &[Tag::Repetitions]).is_some()
+        {
+            let children = context
+                .children_tagged(step, Tag::Child)
+                .map(|e| WorkoutStep::parse_with(e, context))
+                .collect::<Result<_, _>>()?;
+
+            Ok(WorkoutStep::Repeat(Repeat {
+                step_id,
+                repetitions: context
+                    .child_value(step, &[Tag::Repetitions])?
+                    .ok_or_else(|| TcxError::missing(&[Tag::Repetitions]))?,
+                children,
+            }))
+        } else {
+            Ok(WorkoutStep::Step(Step {
+                step_id,
+                name: context.child_value(step, &[Tag::Name])?,
+                duration: context
+                    .child(step, &[Tag::Duration])
+                    .map(|e| StepDuration::parse_with(e, context))
+                    .transpose()?
+                    .unwrap_or(StepDuration::UserInitiated),
+                intensity: context.child_value(step, &[Tag::Intensity])?,
+                target: context
+                    .child(step, &[Tag::Target])
+                    .map(|e| StepTarget::parse_with(e, context))
+                    .transpose()?
+                    .unwrap_or(StepTarget::None),
+            }))
+        }
+    }
+}
+
+impl Workout {
+    /// Parse a [`<Workout>`][Tag::Workout] element
+    pub fn parse(workout: &Element) -> Result<Self, TcxError> {
+        Workout::parse_with(workout, &mut ParseContext::default())
+    }
+
+    /// Parse a [`<Workout>`][Tag::Workout] element using the given [`ParseContext`]
+    pub fn parse_with(workout: &Element, context: &mut ParseContext) -> Result<Self, TcxError> {
+        let steps = context
+            .children_tagged(workout, Tag::Step)
+            .map(|e| WorkoutStep::parse_with(e, context))
+            .collect::<Result<_, _>>()?;
+
+        let scheduled_on = context
+            .children_tagged(workout, Tag::ScheduledOn)
+            .map(|e| {
+                let text = e.text();
+                text.parse().map_err(|err: chrono::ParseError| {
+                    err.into_tcx_error(
+                        ErrorContext {
+                            tags: vec![Tag::ScheduledOn],
+                            ..Default::default()
+                        },
+                        &text,
+                    )
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Workout {
+            sport: context.attr_value(workout, Tag::Sport)?,
+            name: context
+                .child_value(workout, &[Tag::Name])?
+                .unwrap_or_default(),
+            steps,
+            scheduled_on,
+            notes: context.child_value(workout, &[Tag::Notes])?,
+            creator: context
+                .child(workout, &[Tag::Creator])
+                .map(|e| Device::parse_with(e, context))
+                .transpose()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = r#"<Workout xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" Sport="Running">
+      <Name>Intervals</Name>
+      <Step xsi:type="Step_t">
+        <StepId>1</StepId>
+        <Name>Warm up</Name>
+        <Duration xsi:type="Time_t"><Seconds>600</Seconds></Duration>
+        <Intensity>Active</Intensity>
+        <Target xsi:type="HeartRate_t">
+          <HeartRateZone xsi:type="PredefinedHeartRateZone_t"><Number>2</Number></HeartRateZone>
+        </Target>
+      </Step>
+      <Step xsi:type="Repeat_t">
+        <StepId>4</StepId>
+        <Repetitions>5</Repetitions>
+        <Child xsi:type="Step_t">
+          <StepId>2</StepId>
+          <Duration xsi:type="Distance_t"><Meters>400</Meters></Duration>
+          <Intensity>Active</Intensity>
+          <Target xsi:type="Speed_t">
+            <SpeedZone xsi:type="CustomSpeedZone_t">
+              <ViewAs>Pace</ViewAs>
+              <LowInMetersPerSecond>4.0</LowInMetersPerSecond>
+              <HighInMetersPerSecond>4.5</HighInMetersPerSecond>
+            </SpeedZone>
+          </Target>
+        </Child>
+        <Child xsi:type="Step_t">
+          <StepId>3</StepId>
+          <Duration xsi:type="HeartRateBelow_t">
+            <HeartRate xsi:type="HeartRateAsPercentOfMax_t"><Value>65</Value></HeartRate>
+          </Duration>
+          <Intensity>Resting</Intensity>
+          <Target xsi:type="None_t"/>
+        </Child>
+      </Step>
+      <ScheduledOn>2023-01-07</ScheduledOn>
+    </Workout>"#;
+
+    #[test]
+    fn test_workout_parse() {
+        let workout = Workout::parse(&DOC.parse().unwrap()).unwrap();
+
+        assert_eq!(Some(Sport::Running), workout.sport);
+        assert_eq!("Intervals", workout.name);
+        assert_eq!(
+            vec![NaiveDate::from_ymd_opt(2023, 1, 7).unwrap()],
+            workout.scheduled_on
+        );
+        assert_eq!(2, workout.steps.len());
+
+        assert_eq!(
+            WorkoutStep::Step(Step {
+                step_id: 1,
+                name: Some("Warm up".to_string()),
+                duration: StepDuration::Time(600),
+                intensity: Some(Intensity::Active),
+                target: StepTarget::HeartRate(HeartRateZone::Predefined(2)),
+            }),
+            workout.steps[0]
+        );
+
+        let repeat = match &workout.steps[1] {
+            WorkoutStep::Repeat(repeat) => repeat,
+            step => panic!("Expected repeat, found {:?}", step),
+        };
+        assert_eq!(5, repeat.repetitions);
+        assert_eq!(
+            vec![
+                WorkoutStep::Step(Step {
+                    step_id: 2,
+                    name: None,
+                    duration: StepDuration::Distance(400),
+                    intensity: Some(Intensity::Active),
+                    target: StepTarget::Speed(SpeedZone::Custom {
+                        view_as: SpeedType::Pace,
+                        low: 4.0,
+                        high: 4.5
+                    }),
+                }),
+                WorkoutStep::Step(Step {
+                    step_id: 3,
+                    name: None,
+                    duration: StepDuration::HeartRateBelow(HeartRateValue::PercentOfMax(65)),
+                    intensity: Some(Intensity::Resting),
+                    target: StepTarget::None,
+                })
+            ],
+            repeat.children
+        );
+    }
+}
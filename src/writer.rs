@@ -0,0 +1,724 @@
+use std::io::Write;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use minidom::Element;
+
+use crate::{
+    Activity, Application, Course, CourseLap, CoursePoint, Device, Intensity, Lap, LapField,
+    MultiSportSession, Namespace, PointType, Position, Sport, Tag, Tcx, TcxError, Track,
+    Trackpoint, TriggerMethod, Version, TRK_PT_FIELD,
+};
+
+/// XML schema instance namespace used for `xsi:type` attributes
+const XSI: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
+/// Create an empty element for `tag` in the tag's [`namespace`][Tag::namespace]
+pub(crate) fn element(tag: Tag) -> Element {
+    Element::bare(tag.as_ref(), tag.namespace().uri())
+}
+
+/// Create an element for `tag` containing `text`
+fn text_element(tag: Tag, text: impl Into<String>) -> Element {
+    let mut element = element(tag);
+    element.append_text_node(text);
+    element
+}
+
+/// Create an empty element for `tag` with an `xsi:type` attribute
+///
+/// The `xsi` prefix is declared on the element itself, so it is also well-formed outside of [`Tcx::to_element`].
+fn typed_element(tag: Tag, xsi_type: &str) -> Element {
+    Element::builder(tag.as_ref(), tag.namespace().uri())
+        .prefix(Some("xsi".to_string()), XSI)
+        .expect("single prefix")
+        .attr("xsi:type", xsi_type)
+        .build()
+}
+
+pub(crate) fn time_text(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Format `value` for the element at the end of `tags`
+///
+/// Values of integer schema types are rounded and clamped to the type's range: heart rates (1 to 255), cadences (0 to
+/// 254 or 255 for [`<RunCadence>`][Tag::RunCadence]), calories and watts (0 to 65535). Other values are written as
+/// they are.
+pub(crate) fn value_text(tags: &[Tag], value: f64) -> String {
+    let (min, max) = match tags.last() {
+        Some(Tag::Value | Tag::Hr) => (1.0, u8::MAX as f64),
+        Some(Tag::Cadence | Tag::Cad) => (0.0, 254.0),
+        Some(Tag::RunCadence) => (0.0, u8::MAX as f64),
+        Some(Tag::Calories | Tag::Watts | Tag::AvgWatts | Tag::MaxWatts) => (0.0, u16::MAX as f64),
+        _ => return value.to_string(),
+    };
+    value.round().clamp(min, max).to_string()
+}
+
+/// Append a child for each of the `tags` to `parent` if there is none yet and put `text` into the last one
+///
+/// Intermediate elements are reused, e.g., values in [`<Extensions>`][Tag::Extensions]&#173;[`<TPX>`][Tag::TPX] end up
+/// in one [`<TPX>`][Tag::TPX] element.
+pub(crate) fn append_path(parent: &mut Element, tags: &[Tag], text: String) {
+    match tags {
+        [] => parent.append_text_node(text),
+        [tag, tail @ ..] => {
+            let ns = element(*tag).ns();
+            if tail.is_empty() || !parent.has_child(tag.as_ref(), ns.as_str()) {
+                parent.append_child(element(*tag));
+            }
+            let child = parent
+                .children_mut()
+                .filter(|e| e.is(tag.as_ref(), ns.as_str()))
+                .last()
+                .expect("child appended above");
+            append_path(child, tail, text);
+        }
+    }
+}
+
+/// Append an element for a Clark notation path as used in [`Trackpoint::extensions`] to `parent`, reusing existing
+/// elements along the path
+pub(crate) fn append_extension(parent: &mut Element, key: &str, text: String) {
+    let (ns, rest) = match key.strip_prefix('{').and_then(|k| k.split_once('}')) {
+        Some((ns, rest)) => (ns, rest),
+        None => ("", key),
+    };
+
+    match rest.split_once('/') {
+        None => {
+            let mut child = Element::bare(rest, ns);
+            child.append_text_node(text);
+            parent.append_child(child);
+        }
+        Some((name, tail)) => {
+            if !parent.has_child(name, ns) {
+                parent.append_child(Element::bare(name, ns));
+            }
+            let child = parent
+                .get_child_mut(name, ns)
+                .expect("child appended above");
+            append_extension(child, tail, text);
+        }
+    }
+}
+
+impl Version {
+    /// Create a [`<Version>`][Tag::Version] element
+    pub fn to_element(&self) -> Element {
+        let mut version = element(Tag::Version);
+        version.append_child(text_element(
+            Tag::VersionMajor,
+            self.version_major.to_string(),
+        ));
+        version.append_child(text_element(
+            Tag::VersionMinor,
+            self.version_minor.to_string(),
+        ));
+        if let Some(build_major) = self.build_major {
+            version.append_child(text_element(Tag::BuildMajor, build_major.to_string()));
+        }
+        if let Some(build_minor) = self.build_minor {
+            version.append_child(text_element(Tag::BuildMinor, build_minor.to_string()));
+        }
+        version
+    }
+}
+
+impl Device {
+    /// Create a [`<Creator>`][Tag::Creator] element
+    ///
+    /// Missing required values are written as zeros.
+    pub fn to_element(&self) -> Element {
+        let mut device = typed_element(Tag::Creator, "Device_t");
+        device.append_child(text_element(Tag::Name, self.name.as_str()));
+        device.append_child(text_element(
+            Tag::UnitId,
+            self.unit_id.unwrap_or_default().to_string(),
+        ));
+        device.append_child(text_element(
+            Tag::ProductID,
+            self.product_id.unwrap_or_default().to_string(),
+        ));
+        device.append_child(self.version.unwrap_or_default().to_element());
+        device
+    }
+}
+
+impl Application {
+    /// Create an [`<Author>`][Tag::Author] element
+    ///
+    /// Missing required values are written as version 0.0, language `en` and part number `000-00000-00`.
+    pub fn to_element(&self) -> Element {
+        let mut application = typed_element(Tag::Author, "Application_t");
+        application.append_child(text_element(Tag::Name, self.name.as_str()));
+        let mut build = element(Tag::Build);
+        build.append_child(self.build.unwrap_or_default().to_element());
+        application.append_child(build);
+        application.append_child(text_element(
+            Tag::LangID,
+            self.lang_id.as_deref().unwrap_or("en"),
+        ));
+        application.append_child(text_element(
+            Tag::PartNumber,
+            self.part_number.as_deref().unwrap_or("000-00000-00"),
+        ));
+        application
+    }
+}
+
+impl Position {
+    /// Create a position element with the given tag, e.g., [`<Position>`][Tag::Position] or
+    /// [`<BeginPosition>`][Tag::BeginPosition]
+    pub fn to_element(&self, tag: Tag) -> Element {
+        let mut position = element(tag);
+        position.append_child(text_element(
+            Tag::LatitudeDegrees,
+            self.latitude.to_string(),
+        ));
+        position.append_child(text_element(
+            Tag::LongitudeDegrees,
+            self.longitude.to_string(),
+        ));
+        position
+    }
+}
+
+impl Trackpoint {
+    /// Create a [`<Trackpoint>`][Tag::Trackpoint] element
+    ///
+    /// Each field is written to the first of its paths, see [`TrkPtField::get_tags`][crate::TrkPtField::get_tags],
+    /// followed by the [`extensions`][Trackpoint::extensions]. Parsing the element yields the same track point, except
+    /// for heart rates, cadences and powers, which are rounded and clamped to the range of their integer schema types.
+    ///
+    /// # Examples
+    /// ```
+    /// # use tcx::*;
+    ///
+    /// let point = Trackpoint {
+    ///     time: "2022-12-31T12:00:00.25Z".parse().unwrap(),
+    ///     heartrate: Some(140.0),
+    ///     power: Some(210.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let element = point.to_element();
+    ///
+    /// let tpx = element.child(&[Tag::Extensions, Tag::TPX]).unwrap();
+    /// assert_eq!(tpx.ns(), Namespace::ActivityExtensionV2.uri());
+    /// assert_eq!(Trackpoint::parse(&element).unwrap(), point);
+    /// ```
+    pub fn to_element(&self) -> Element {
+        let mut trackpoint = element(Tag::Trackpoint);
+        trackpoint.append_child(text_element(Tag::Time, time_text(&self.time)));
+
+        for field in &TRK_PT_FIELD {
+            if let (Some(value), Some(tags)) = (self[field], field.get_tags().first()) {
+                append_path(&mut trackpoint, tags, value_text(tags, value));
+            }
+        }
+
+        if !self.extensions.is_empty() {
+            let ns = element(Tag::Extensions).ns();
+            if !trackpoint.has_child(Tag::Extensions.as_ref(), ns.as_str()) {
+                trackpoint.append_child(element(Tag::Extensions));
+            }
+            let extensions = trackpoint
+                .get_child_mut(Tag::Extensions.as_ref(), ns.as_str())
+                .expect("child appended above");
+            for (key, value) in &self.extensions {
+                append_extension(extensions, key, value.to_string());
+            }
+        }
+
+        trackpoint
+    }
+}
+
+impl Track {
+    /// Create a [`<Track>`][Tag::Track] element
+    pub fn to_element(&self) -> Element {
+        let mut track = element(Tag::Track);
+        for point in &self.trackpoints {
+            track.append_child(point.to_element());
+        }
+        track
+    }
+}
+
+impl Lap {
+    /// Create a [`<Lap>`][Tag::Lap] element
+    ///
+    /// Missing required values are derived from the track points where possible: the start time is the first track
+    /// point's time, the total time is the time between the first and the last track point and the distance is the
+    /// difference of the first and the last track point's distances. Otherwise, they are written as zero. Intensity
+    /// defaults to [`Intensity::Active`], the trigger method defaults to [`TriggerMethod::Manual`]. Calories, heart
+    /// rates, cadence and powers are rounded and clamped to the range of their integer schema types.
+    pub fn to_element(&self) -> Element {
+        self.to_element_tagged(Tag::Lap)
+    }
+
+    /// Create a [`<Lap>`][Tag::Lap] or [`<Transition>`][Tag::Transition] element
+    fn to_element_tagged(&self, tag: Tag) -> Element {
+        let mut lap = element(tag);
+
+        let first = self.trackpoints().next();
+        let last = self.trackpoints().last();
+        if let Some(start_time) = self.start_time.or_else(|| first.map(|t| t.time)) {
+            lap.set_attr(Tag::StartTime.as_ref(), time_text(&start_time));
+        }
+        let total_time = self
+            .total_time
+            .or_else(|| Some((last?.time - first?.time).num_milliseconds() as f64 / 1000.0));
+        let distance = self
+            .distance
+            .or_else(|| Some(last?.distance? - first?.distance?));
+
+        let mut append = |field: LapField, value: Option<f64>| {
+            if let Some(value) = value {
+                let tags = field.get_tags()[0];
+                append_path(&mut lap, tags, value_text(tags, value));
+            }
+        };
+        append(LapField::TotalTime, total_time.or(Some(0.0)));
+        append(LapField::Distance, distance.or(Some(0.0)));
+        append(LapField::MaximumSpeed, self.maximum_speed);
+        append(LapField::Calories, self.calories.or(Some(0.0)));
+        append(LapField::AverageHeartrate, self.average_heartrate);
+        append(LapField::MaximumHeartrate, self.maximum_heartrate);
+
+        lap.append_child(text_element(
+            Tag::Intensity,
+            self.intensity.unwrap_or(Intensity::Active).as_ref(),
+        ));
+        if let Some(cadence) = self.cadence {
+            lap.append_child(text_element(
+                Tag::Cadence,
+                value_text(&[Tag::Cadence], cadence),
+            ));
+        }
+        lap.append_child(text_element(
+            Tag::TriggerMethod,
+            self.trigger_method
+                .unwrap_or(TriggerMethod::Manual)
+                .as_ref(),
+        ));
+        for track in &self.tracks {
+            lap.append_child(track.to_element());
+        }
+
+        for field in [
+            LapField::AverageSpeed,
+            LapField::AveragePower,
+            LapField::MaximumPower,
+        ] {
+            if let Some(value) = self[&field] {
+                let tags = field.get_tags()[0];
+                append_path(&mut lap, tags, value_text(tags, value));
+            }
+        }
+
+        lap
+    }
+}
+
+impl Activity {
+    /// Create an [`<Activity>`][Tag::Activity] element
+    ///
+    /// A missing sport is written as [`Sport::Other`], a missing ID defaults to the first lap's
+    /// start time or the first track point's time.
+    pub fn to_element(&self) -> Element {
+        let mut activity = element(Tag::Activity);
+        activity.set_attr(
+            Tag::Sport.as_ref(),
+            self.sport.unwrap_or(Sport::Other).as_ref(),
+        );
+        let id = self
+            .id
+            .or_else(|| self.laps.first().and_then(|l| l.start_time))
+            .or_else(|| self.trackpoints().next().map(|t| t.time));
+        if let Some(id) = id {
+            activity.append_child(text_element(Tag::Id, time_text(&id)));
+        }
+        for lap in &self.laps {
+            activity.append_child(lap.to_element());
+        }
+        if let Some(notes) = &self.notes {
+            activity.append_child(text_element(Tag::Notes, notes.as_str()));
+        }
+        if let Some(creator) = &self.creator {
+            activity.append_child(creator.to_element());
+        }
+        activity
+    }
+}
+
+impl MultiSportSession {
+    /// Create a [`<MultiSportSession>`][Tag::MultiSportSession] element
+    pub fn to_element(&self) -> Element {
+        let mut session = element(Tag::MultiSportSession);
+        let id = self
+            .id
+            .or_else(|| self.trackpoints().next().map(|t| t.time));
+        if let Some(id) = id {
+            session.append_child(text_element(Tag::Id, time_text(&id)));
+        }
+        let mut first_sport = element(Tag::FirstSport);
+        first_sport.append_child(self.first_sport.to_element());
+        session.append_child(first_sport);
+        for next_sport in &self.next_sports {
+            let mut next = element(Tag::NextSport);
+            if let Some(transition) = &next_sport.transition {
+                next.append_child(transition.to_element_tagged(Tag::Transition));
+            }
+            next.append_child(next_sport.activity.to_element());
+            session.append_child(next);
+        }
+        if let Some(notes) = &self.notes {
+            session.append_child(text_element(Tag::Notes, notes.as_str()));
+        }
+        session
+    }
+}
+
+impl CourseLap {
+    /// Create a [`<Lap>`][Tag::Lap] element of a course
+    ///
+    /// Missing total time and distance are written as zero, intensity defaults to [`Intensity::Active`].
+    pub fn to_element(&self) -> Element {
+        let mut lap = element(Tag::Lap);
+        let mut append = |tag: Tag, value: Option<f64>| {
+            if let Some(value) = value {
+                lap.append_child(text_element(tag, value.to_string()));
+            }
+        };
+        append(Tag::TotalTimeSeconds, self.total_time.or(Some(0.0)));
+        append(Tag::DistanceMeters, self.distance.or(Some(0.0)));
+        if let Some(position) = &self.begin_position {
+            lap.append_child(position.to_element(Tag::BeginPosition));
+        }
+        if let Some(altitude) = self.begin_altitude {
+            lap.append_child(text_element(Tag::BeginAltitudeMeters, altitude.to_string()));
+        }
+        if let Some(position) = &self.end_position {
+            lap.append_child(position.to_element(Tag::EndPosition));
+        }
+        if let Some(altitude) = self.end_altitude {
+            lap.append_child(text_element(Tag::EndAltitudeMeters, altitude.to_string()));
+        }
+        for (tag, value) in [
+            (Tag::AverageHeartRateBpm, self.average_heartrate),
+            (Tag::MaximumHeartRateBpm, self.maximum_heartrate),
+        ] {
+            if let Some(value) = value {
+                let mut heartrate = element(tag);
+                heartrate.append_child(text_element(Tag::Value, value_text(&[Tag::Value], value)));
+                lap.append_child(heartrate);
+            }
+        }
+        lap.append_child(text_element(
+            Tag::Intensity,
+            self.intensity.unwrap_or(Intensity::Active).as_ref(),
+        ));
+        if let Some(cadence) = self.cadence {
+            lap.append_child(text_element(
+                Tag::Cadence,
+                value_text(&[Tag::Cadence], cadence),
+            ));
+        }
+        lap
+    }
+}
+
+impl CoursePoint {
+    /// Create a [`<CoursePoint>`][Tag::CoursePoint] element
+    ///
+    /// A missing point type is written as [`PointType::Generic`].
+    pub fn to_element(&self) -> Element {
+        let mut point = element(Tag::CoursePoint);
+        point.append_child(text_element(Tag::Name, self.name.as_str()));
+        point.append_child(text_element(Tag::Time, time_text(&self.time)));
+        point.append_child(self.position.to_element(Tag::Position));
+        if let Some(altitude) = self.altitude {
+            point.append_child(text_element(Tag::AltitudeMeters, altitude.to_string()));
+        }
+        point.append_child(text_element(
+            Tag::PointType,
+            self.point_type.unwrap_or(PointType::Generic).as_ref(),
+        ));
+        if let Some(notes) = &self.notes {
+            point.append_child(text_element(Tag::Notes, notes.as_str()));
+        }
+        point
+    }
+}
+
+impl Course {
+    /// Create a [`<Course>`][Tag::Course] element
+    pub fn to_element(&self) -> Element {
+        let mut course = element(Tag::Course);
+        course.append_child(text_element(Tag::Name, self.name.as_str()));
+        for lap in &self.laps {
+            course.append_child(lap.to_element());
+        }
+        for track in &self.tracks {
+            course.append_child(track.to_element());
+        }
+        if let Some(notes) = &self.notes {
+            course.append_child(text_element(Tag::Notes, notes.as_str()));
+        }
+        for point in &self.course_points {
+            course.append_child(point.to_element());
+        }
+        if let Some(creator) = &self.creator {
+            course.append_child(creator.to_element());
+        }
+        course
+    }
+}
+
+impl Tcx {
+    /// Create a [`<TrainingCenterDatabase>`][Tag::TrainingCenterDatabase] element
+    ///
+    /// The element declares the TCX v2 namespace as default namespace, `ns3` for the
+    /// [activity extension][Namespace::ActivityExtensionV2] and `ns2` for the
+    /// [track point extension][Namespace::TrackPointExtensionV2]. Workouts and folders are not written.
+    pub fn to_element(&self) -> Element {
+        let root = element(Tag::TrainingCenterDatabase);
+        let mut builder = Element::builder(root.name(), root.ns());
+        for (prefix, uri) in [
+            (None, Namespace::TrainingCenterDatabaseV2.uri()),
+            (Some("ns3"), Namespace::ActivityExtensionV2.uri()),
+            (Some("ns2"), Namespace::TrackPointExtensionV2.uri()),
+        ] {
+            builder = builder
+                .prefix(prefix.map(str::to_string), uri)
+                .expect("prefixes are distinct");
+        }
+        let mut tcx = builder.build();
+
+        if !self.activities.is_empty() || !self.multi_sport_sessions.is_empty() {
+            let mut activities = element(Tag::Activities);
+            for activity in &self.activities {
+                activities.append_child(activity.to_element());
+            }
+            for session in &self.multi_sport_sessions {
+                activities.append_child(session.to_element());
+            }
+            tcx.append_child(activities);
+        }
+        if !self.courses.is_empty() {
+            let mut courses = element(Tag::Courses);
+            for course in &self.courses {
+                courses.append_child(course.to_element());
+            }
+            tcx.append_child(courses);
+        }
+        if let Some(author) = &self.author {
+            tcx.append_child(author.to_element());
+        }
+
+        tcx
+    }
+
+    /// Write the TCX document including the XML declaration, see [`Tcx::to_element`]
+    ///
+    /// # Examples
+    /// ```
+    /// # use tcx::*;
+    ///
+    /// let tcx = Tcx {
+    ///     activities: vec![Activity {
+    ///         sport: Some(Sport::Biking),
+    ///         laps: vec![Lap {
+    ///             tracks: vec![Track {
+    ///                 trackpoints: vec![Trackpoint {
+    ///                     time: "2022-12-31T12:00:00Z".parse().unwrap(),
+    ///                     speed: Some(7.5),
+    ///                     ..Default::default()
+    ///                 }],
+    ///             }],
+    ///             ..Default::default()
+    ///         }],
+    ///         ..Default::default()
+    ///     }],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut buf = Vec::new();
+    /// tcx.write(&mut buf).unwrap();
+    /// let element = String::from_utf8(buf).unwrap().parse().unwrap();
+    ///
+    /// assert!(validate(&element).is_empty());
+    /// assert_eq!(Tcx::parse(&element).unwrap().activities[0].laps[0].tracks, tcx.activities[0].laps[0].tracks);
+    /// ```
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), TcxError> {
+        self.to_element()
+            .write_to_decl(writer)
+            .map_err(|source| TcxError::Xml {
+                source,
+                position: None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{validate, TcxElement};
+
+    use super::*;
+
+    fn trackpoints() -> Vec<Trackpoint> {
+        let time = "2022-12-31T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        (0..3)
+            .map(|k| Trackpoint {
+                time: time + chrono::Duration::milliseconds(1500 * k),
+                latitude: Some(48.1 + k as f64 * 1e-5),
+                longitude: Some(9.0 - k as f64 * 1e-5),
+                altitude: Some(310.4),
+                distance: Some(k as f64 * 3.7),
+                heartrate: Some(140.0),
+                cadence: Some(88.0),
+                speed: Some(7.4),
+                power: Some(212.0),
+                air_temperature: Some(21.5),
+                water_temperature: None,
+                depth: None,
+                course: Some(271.3),
+                bearing: Some(270.0),
+                extensions: BTreeMap::from([
+                    ("{urn:vendor}Stryd/{urn:vendor}FormPower".to_string(), 65.0),
+                    ("{urn:vendor}Stryd/{urn:vendor}LSS".to_string(), 9.1),
+                    ("{urn:other}Slope".to_string(), -0.5),
+                ]),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let lap = Lap {
+            tracks: vec![Track {
+                trackpoints: trackpoints(),
+            }],
+            average_power: Some(210.0),
+            ..Default::default()
+        };
+        let tcx = Tcx {
+            activities: vec![Activity {
+                sport: Some(Sport::Biking),
+                laps: vec![lap.clone()],
+                creator: Some(Device {
+                    name: "Edge 530".to_string(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            multi_sport_sessions: vec![MultiSportSession {
+                first_sport: Activity {
+                    laps: vec![lap.clone()],
+                    ..Default::default()
+                },
+                next_sports: vec![crate::NextSport {
+                    transition: Some(lap.clone()),
+                    activity: Activity {
+                        laps: vec![lap.clone()],
+                        ..Default::default()
+                    },
+                }],
+                ..Default::default()
+            }],
+            author: Some(Application {
+                name: "tcx".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let mut buf = Vec::new();
+        tcx.write(&mut buf).unwrap();
+        let doc = String::from_utf8(buf).unwrap();
+        let element = doc.parse().unwrap();
+
+        assert_eq!(Vec::<crate::Violation>::new(), validate(&element));
+
+        let parsed = Tcx::parse(&element).unwrap();
+        let expected = tcx.trackpoints().cloned().collect::<Vec<_>>();
+        assert_eq!(expected, parsed.trackpoints().cloned().collect::<Vec<_>>());
+        assert_eq!(4 * 3, expected.len());
+
+        let lap = &parsed.activities[0].laps[0];
+        assert_eq!(lap.start_time, Some(expected[0].time));
+        assert_eq!(lap.total_time, Some(3.0));
+        assert_eq!(lap.distance, Some(7.4));
+        assert_eq!(lap.average_power, Some(210.0));
+        assert!(parsed.multi_sport_sessions[0].next_sports[0]
+            .transition
+            .is_some());
+        assert_eq!(parsed.author.unwrap().name, "tcx");
+    }
+
+    #[test]
+    fn test_write_integer_values() {
+        let lap = Lap {
+            calories: Some(70000.4),
+            average_heartrate: Some(140.6),
+            maximum_heartrate: Some(0.0),
+            cadence: Some(300.0),
+            tracks: vec![Track {
+                trackpoints: vec![Trackpoint {
+                    time: "2022-12-31T12:00:00Z".parse().unwrap(),
+                    latitude: Some(48.1),
+                    longitude: Some(9.0),
+                    heartrate: Some(120.5),
+                    cadence: Some(-3.0),
+                    power: Some(212.7),
+                    ..Default::default()
+                }],
+            }],
+            ..Default::default()
+        };
+
+        let element = lap.to_element();
+        let text = |tags: &[Tag]| element.child(tags).unwrap().text();
+        assert_eq!("65535", text(&[Tag::Calories]));
+        assert_eq!("141", text(&[Tag::AverageHeartRateBpm, Tag::Value]));
+        assert_eq!("1", text(&[Tag::MaximumHeartRateBpm, Tag::Value]));
+        assert_eq!("254", text(&[Tag::Cadence]));
+        assert_eq!("0", text(&[Tag::Track, Tag::Trackpoint, Tag::Cadence]));
+        assert_eq!(
+            "121",
+            text(&[Tag::Track, Tag::Trackpoint, Tag::HeartRateBpm, Tag::Value])
+        );
+        assert_eq!(
+            "213",
+            text(&[
+                Tag::Track,
+                Tag::Trackpoint,
+                Tag::Extensions,
+                Tag::TPX,
+                Tag::Watts
+            ])
+        );
+
+        let trkpt = lap.tracks[0].trackpoints[0].to_gpx_element();
+        let text = |tag: Tag| {
+            let tags = [Tag::GpxExtensions, Tag::TrackPointExtension, tag];
+            trkpt.child(&tags).unwrap().text()
+        };
+        assert_eq!("121", text(Tag::Hr));
+        assert_eq!("0", text(Tag::Cad));
+    }
+
+    #[test]
+    fn test_write_xsi_type() {
+        // the xsi prefix is declared without the root element
+        let mut buf = Vec::new();
+        Device::default().to_element().write_to(&mut buf).unwrap();
+        let element: Element = String::from_utf8(buf).unwrap().parse().unwrap();
+
+        assert_eq!(Some("Device_t"), crate::workout::xsi_type(&element));
+    }
+}
@@ -2,15 +2,34 @@ use convert_case::{Casing, Case};
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{quote, format_ident};
-use syn::{self, spanned::Spanned, Data, Error, Fields};
+use syn::{self, spanned::Spanned, Data, Error, Fields, Lit, Meta, MetaNameValue};
 
-#[proc_macro_derive(AsRefStr)]
+/// Derive `AsRef<str>` for enums with unit variants
+///
+/// The string is the variant's name unless it is given by an attribute like `#[as_ref = "speed"]`.
+#[proc_macro_derive(AsRefStr, attributes(as_ref))]
 pub fn as_ref_macro_derive(input: TokenStream) -> TokenStream {
     let ast = syn::parse(input).unwrap();
 
     impl_as_ref_macro(&ast)
 }
 
+/// Get the string of a variant's `#[as_ref = "..."]` attribute, if any
+fn as_ref_name(variant: &syn::Variant) -> Result<Option<syn::LitStr>, Error> {
+    for attr in &variant.attrs {
+        if attr.path.is_ident("as_ref") {
+            return match attr.parse_meta()? {
+                Meta::NameValue(MetaNameValue {
+                    lit: Lit::Str(name),
+                    ..
+                }) => Ok(Some(name)),
+                meta => Err(Error::new(meta.span(), "expected #[as_ref = \"...\"]")),
+            };
+        }
+    }
+    Ok(None)
+}
+
 fn impl_as_ref_macro(ast: &syn::DeriveInput) -> TokenStream {
     let name = &ast.ident;
     let data = &ast.data;
@@ -22,8 +41,13 @@ fn impl_as_ref_macro(ast: &syn::DeriveInput) -> TokenStream {
                 match variant.fields {
                     Fields::Unit => {
                         let variant_name = &variant.ident;
+                        let variant_str = match as_ref_name(variant) {
+                            Ok(Some(as_ref)) => quote!(#as_ref),
+                            Ok(None) => quote!(stringify!(#variant_name)),
+                            Err(err) => return err.into_compile_error().into(),
+                        };
                         match_body.extend(quote! {
-                            #name::#variant_name => stringify!(#variant_name),
+                            #name::#variant_name => #variant_str,
                         })
                     }
                     _ => {
@@ -106,3 +130,56 @@ fn impl_const_array_macro(ast: &syn::DeriveInput) -> TokenStream {
     }
 }
 
+#[proc_macro_derive(EnumFromStr)]
+pub fn enum_from_str_macro_derive(input: TokenStream) -> TokenStream {
+    let ast = syn::parse(input).unwrap();
+
+    impl_enum_from_str_macro(&ast)
+}
+
+fn impl_enum_from_str_macro(ast: &syn::DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+    let data = &ast.data;
+
+    match data {
+        Data::Enum(data_enum) => {
+            let mut match_body = TokenStream2::new();
+            for variant in &data_enum.variants {
+                match variant.fields {
+                    Fields::Unit => {
+                        let variant_name = &variant.ident;
+                        match_body.extend(quote! {
+                            stringify!(#variant_name) => Ok(#name::#variant_name),
+                        })
+                    }
+                    _ => {
+                        return Error::new(
+                            variant.span(),
+                            "EnumFromStr is only supported on enums with variants without any fields",
+                        )
+                        .into_compile_error()
+                        .into()
+                    }
+                }
+            }
+
+            let from_str_impl = quote! {
+                impl std::str::FromStr for #name {
+                    type Err = String;
+
+                    fn from_str(s: &str) -> Result<Self, Self::Err> {
+                        match s {
+                            #match_body
+                            _ => Err(format!("'{}' is not a valid {}", s, stringify!(#name))),
+                        }
+                    }
+                }
+            };
+
+            from_str_impl.into()
+        }
+        _ => Error::new(name.span(), "EnumFromStr is only supported on enum types")
+            .into_compile_error()
+            .into(),
+    }
+}